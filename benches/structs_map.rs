@@ -0,0 +1,74 @@
+use std::fs::File;
+use std::io::Write;
+use std::process::Command;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use memmap2::Mmap;
+use tempfile::TempDir;
+
+use dwat::prelude::*;
+use dwat::Dwarf;
+
+const STRUCTS: usize = 400;
+const MEMBERS: usize = 8;
+const UNITS: usize = 40;
+
+// Build a binary where every compile unit includes the same header of
+// structs, as in large C projects, so most structs are seen once per unit
+fn build_fixture() -> anyhow::Result<(TempDir, Mmap)> {
+    let tmp_dir = TempDir::new()?;
+
+    let mut header = String::new();
+    for idx in 0..STRUCTS {
+        header.push_str(&format!("struct s{idx} {{"));
+        for member in 0..MEMBERS {
+            header.push_str(&format!(" long m{member};"));
+        }
+        header.push_str(" };\n");
+    }
+    File::create(tmp_dir.path().join("structs.h"))?
+        .write_all(header.as_bytes())?;
+
+    let mut src_paths = vec![];
+    for unit in 0..UNITS {
+        let src_path = tmp_dir.path().join(format!("src{unit}.c"));
+        let mut source = String::from("#include \"structs.h\"\n");
+        if unit == 0 {
+            source.push_str("int main() {}\n");
+        }
+        File::create(&src_path)?.write_all(source.as_bytes())?;
+        src_paths.push(src_path);
+    }
+
+    let out_path = tmp_dir.path().join("bin");
+    let output = Command::new("gcc")
+        .args(["-g", "-fno-eliminate-unused-debug-types"])
+        .args(&src_paths)
+        .arg("-o")
+        .arg(&out_path)
+        .output()?;
+    if !output.status.success() {
+        panic!("gcc failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    let file = File::open(&out_path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    Ok((tmp_dir, mmap))
+}
+
+fn structs_map(c: &mut Criterion) {
+    let (_tmp_dir, mmap) = build_fixture().unwrap();
+    let dwarf = Dwarf::load(&*mmap).unwrap();
+
+    let mut group = c.benchmark_group("fg_named_structs_map");
+    group.bench_function("full", |b| {
+        b.iter(|| dwarf.get_fg_named_structs_map().unwrap())
+    });
+    group.bench_function("fast", |b| {
+        b.iter(|| dwarf.get_fg_named_structs_map_fast().unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, structs_map);
+criterion_main!(benches);
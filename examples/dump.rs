@@ -18,7 +18,7 @@ fn main() -> anyhow::Result<()> {
     let struct_map = dwarf.get_fg_named_structs_map()?;
 
     for (key, struc) in struct_map.into_iter() {
-        let members = struc.members(&dwarf)?.len();
+        let members = struc.member_count(&dwarf)?;
         println!("{}\t{}", key.name, members);
     };
 
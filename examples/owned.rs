@@ -0,0 +1,35 @@
+/// Example of using OwnedDwarf, which doesn't borrow from the file it was
+/// loaded from and so can be stored or passed around freely
+use dwat::prelude::*;
+use dwat::OwnedDwarf;
+
+fn main() -> anyhow::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let struct_name = args.next().unwrap_or_else(|| {
+        eprintln!("Usage: owned <struct_name> <path>");
+        std::process::exit(1);
+    });
+    let path = args.next().unwrap_or_else(|| {
+        eprintln!("Usage: owned <struct_name> <path>");
+        std::process::exit(1);
+    });
+
+    let dwarf = OwnedDwarf::from_path(path)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>(struct_name)?;
+    if let Some(found) = found {
+        println!("{}", found.to_string_verbose(&dwarf, 1)?);
+
+        for member in found.members(&dwarf)? {
+            let name = member.name(&dwarf).unwrap_or("".to_string());
+            let layout = member.bit_layout(&dwarf)?;
+            println!("{name}: {layout:?}");
+        }
+
+        let opts = dwat::FormatOptions::default();
+        let header = dwarf.emit_c_header(&[dwat::Type::Struct(found)], &opts)?;
+        println!("{header}");
+    }
+
+    Ok(())
+}
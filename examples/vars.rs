@@ -17,11 +17,11 @@ fn main() -> anyhow::Result<()> {
 
     let vars = dwarf.get_named_types::<dwat::Variable>()?;
 
-    // find all variables that are of type union
-    // then print the union
+    // find all variables that are of type union, including through typedefs
+    // and qualifiers, then print the union
     for (name, var) in vars.into_iter() {
-        let typ = var.get_type(&dwarf)?;
-        if let dwat::Type::Union(u) = typ {
+        let typ = var.get_type(&dwarf)?.as_aggregate(&dwarf)?;
+        if let Some(u) = typ.and_then(|typ| typ.as_union()) {
             println!("{} : {}", name, u.to_string(&dwarf)?)
         }
     };
@@ -119,10 +119,14 @@ class Member:
     byte_size: typing.Optional[int]
     bit_size: typing.Optional[int]
     offset: typing.Optional[str]
+    has_location: bool
     name: typing.Optional[str]
+    type_name: str
 
 class Parameter:
     def type(self) -> typing.Union[
         Struct | Array | Enum | Pointer | Subroutine | Typedef | Union |
         Base | Const | Volatile | Restrict
     ]: ...
+    name: typing.Optional[str]
+    is_optional: bool
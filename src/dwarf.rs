@@ -1,18 +1,32 @@
 //! Loading of DWARF information
-use std::{collections::HashMap, borrow::Cow};
-use object::{Object, ObjectSection, ReadRef};
+use std::{collections::{BTreeMap, BTreeSet, HashMap, HashSet}, borrow::Cow};
+use std::{fs::File, path::{Path, PathBuf}};
+use std::sync::{Arc, OnceLock};
+use memmap2::Mmap;
+use object::{Object, ObjectSection, ObjectSymbol, ReadRef};
 use gimli::RunTimeEndian;
 
 use crate::dwarf::borrowable_dwarf::BorrowableDwarf;
-use crate::unit_has_members::UnitHasMembers;
 use crate::unit_name_type::UnitNamedType;
-use crate::{DIE, CU, GimliDwarf};
+use crate::{DIE, CU, GimliDwarf, R};
 // use crate::owned_get_entry_name;
 use crate::get_entry_name;
+use crate::types::{get_entry_linkage_name, entry_to_type};
 use crate::Location;
 use crate::Tagged;
 use crate::Struct;
+use crate::Union;
+use crate::Enum;
+use crate::Base;
+use crate::Typedef;
+use crate::CompileUnit;
+use crate::Member;
+use crate::HasMembers;
+use crate::NamedType;
+use crate::Type;
+use crate::UnknownTagPolicy;
 use crate::Error;
+use crate::format::FormatOptions;
 
 /// A struct to hold the HashMap key for `get_named_structs_map`
 #[derive(Eq, Hash, PartialEq)]
@@ -27,19 +41,171 @@ pub struct StructHashKey {
     pub members: Vec<(String, usize)>
 }
 
-fn for_each_die<T: Tagged, F>(dwarf: &GimliDwarf, mut f: F)
+/// A struct to hold the HashMap key for `get_fg_named_enums_map`
+#[derive(Eq, Hash, PartialEq)]
+pub struct EnumHashKey {
+    /// The name of the enum
+    pub name: String,
+
+    /// The size of the enum in bytes
+    pub byte_size: usize,
+
+    /// A tuple of: enumerator name, enumerator value
+    pub enumerators: Vec<(String, i64)>
+}
+
+/// Options for `lookup_type_opts`
+#[derive(Clone, Copy, Debug)]
+pub struct LookupOptions {
+    /// Ignore declarations (e.g. `struct foo;`), as `lookup_type` does
+    pub skip_declarations: bool,
+    /// When declarations aren't skipped, keep scanning past a declaration in
+    /// case there is a definition, the declaration is returned otherwise
+    pub prefer_definition: bool,
+}
+
+impl Default for LookupOptions {
+    fn default() -> Self {
+        Self { skip_declarations: true, prefer_definition: false }
+    }
+}
+
+/// Maps the names of the symbols of a file to their addresses, only defined
+/// symbols which have an address are captured (not e.g. undefined imports or
+/// absolute symbols at 0), see `Dwarf::load_with_symbols`
+pub type SymbolTable = HashMap<String, u64>;
+
+/// Progress of a scan over all compile units, reported after each unit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScanProgress {
+    pub cus_done: usize,
+    pub cus_total: usize,
+    /// Entries with the tag being scanned for found so far
+    pub items_found: usize,
+    /// Entries which failed to parse and were skipped so far
+    pub entries_skipped: usize,
+}
+
+// Walks the entries of a unit depth first like `EntriesCursor::next_dfs`, but
+// when an entry fails to parse the walk resumes at the DW_AT_sibling of the
+// closest enclosing entry which has one instead of abandoning the unit
+struct RecoveringEntries<'u, 'a> {
+    unit: &'u CU<'a>,
+    cursor: gimli::EntriesCursor<'u, 'u, R<'a>>,
+    // the sibling of the last entry seen at each depth of the current path
+    siblings: Vec<Option<gimli::UnitOffset>>,
+    depth: isize,
+    skipped: usize,
+}
+
+impl<'u, 'a> RecoveringEntries<'u, 'a> {
+    fn new(unit: &'u CU<'a>) -> Self {
+        Self { unit, cursor: unit.entries(), siblings: vec![], depth: 0,
+               skipped: 0 }
+    }
+
+    fn next(&mut self)
+    -> Option<&gimli::DebuggingInformationEntry<'u, 'u, R<'a>, usize>> {
+        let mut resumed = false;
+        loop {
+            match self.cursor.next_dfs() {
+                Ok(Some((delta_depth, _))) => {
+                    self.depth += delta_depth;
+                    break
+                },
+                Ok(None) => return None,
+                Err(_) => {
+                    // failing again right where the walk resumed means the
+                    // bad entry is the sibling itself, so keep unwinding
+                    if !resumed {
+                        self.skipped += 1;
+                    }
+                    let sibling = loop {
+                        match self.siblings.pop() {
+                            Some(Some(sibling)) => break sibling,
+                            Some(None) => continue,
+                            None => return None
+                        }
+                    };
+                    self.cursor = match self.unit.entries_at_offset(sibling) {
+                        Ok(cursor) => cursor,
+                        Err(_) => return None
+                    };
+                    self.depth = self.siblings.len() as isize;
+                    resumed = true;
+                }
+            }
+        }
+
+        let entry = self.cursor.current()?;
+        let sibling = match entry.attr_value(gimli::DW_AT_sibling) {
+            Ok(Some(gimli::AttributeValue::UnitRef(offset))) => Some(offset),
+            _ => None
+        };
+        self.siblings.truncate(self.depth.max(0) as usize);
+        self.siblings.push(sibling);
+        Some(entry)
+    }
+}
+
+// Count the unit headers without parsing the units
+fn count_units(dwarf: &GimliDwarf) -> Result<usize, Error> {
+    let mut count = 0;
+    let mut unit_headers = dwarf.debug_info.units();
+    loop {
+        match unit_headers.next() {
+            Ok(Some(_)) => count += 1,
+            Ok(None) => return Ok(count),
+            Err(e) => return Err(
+                Error::CUError(
+                    format!("Failed to read UnitHeader, error: {}", e)
+                ))
+        }
+    }
+}
+
+fn for_each_die<T: Tagged, F>(dwarf: &GimliDwarf,
+                              progress: Option<&mut dyn FnMut(ScanProgress)>,
+                              f: F)
 -> Result<(), Error>
 where F: FnMut(&CU, &DIE, Location) -> Result<bool, Error> {
+    for_each_die_also::<T, F>(dwarf, progress, None, f)
+}
+
+// Like for_each_die, but `f` is also called for the definitions tagged `also`
+// in the same walk, these aren't counted as items found
+fn for_each_die_also<T: Tagged, F>(
+    dwarf: &GimliDwarf, mut progress: Option<&mut dyn FnMut(ScanProgress)>,
+    also: Option<gimli::DwTag>, mut f: F
+) -> Result<(), Error>
+where F: FnMut(&CU, &DIE, Location) -> Result<bool, Error> {
+    // only pay for counting the units when someone is listening
+    let cus_total = match progress {
+        Some(_) => count_units(dwarf)?,
+        None => 0
+    };
+    let mut cus_done = 0;
+    let mut items_found = 0;
+    let mut entries_skipped = 0;
+
     let mut unit_headers = dwarf.debug_info.units();
     while let Ok(Some(header)) = unit_headers.next() {
+        cus_done += 1;
         let unit = match dwarf.unit(header) {
             Ok(unit) => unit,
-            Err(_) => continue
+            Err(_) => {
+                if let Some(progress) = progress.as_mut() {
+                    progress(ScanProgress { cus_done, cus_total, items_found,
+                                            entries_skipped });
+                }
+                continue
+            }
         };
-        let mut entries = unit.entries();
+        let mut entries = RecoveringEntries::new(&unit);
         'entries:
-        while let Ok(Some((_delta_depth, entry))) = entries.next_dfs() {
-            if entry.tag() != T::tag() {
+        while let Some(entry) = entries.next() {
+            let is_item = entry.tag() == T::tag();
+            if !is_item && Some(entry.tag()) != also {
                 continue;
             }
 
@@ -50,15 +216,43 @@ where F: FnMut(&CU, &DIE, Location) -> Result<bool, Error> {
                 }
             }
 
-            let header_offset =
-                match header.offset().as_debug_info_offset() {
-                    Some(offset) => offset,
-                    // should be unreachable
-                    None => return Err(Error::HeaderOffsetError)
+            let location = Location {
+                header: header.offset(),
+                offset: entry.offset(),
             };
+            if is_item {
+                items_found += 1;
+            }
+
+            // return if function returns true
+            if f(&unit, entry, location)? {
+                return Ok(())
+            }
+        }
+        entries_skipped += entries.skipped;
+
+        if let Some(progress) = progress.as_mut() {
+            progress(ScanProgress { cus_done, cus_total, items_found,
+                                    entries_skipped });
+        }
+    }
+    Ok(())
+}
 
+// Like for_each_die, but visits every entry regardless of tag, including
+// declarations
+fn for_each_entry<F>(dwarf: &GimliDwarf, mut f: F) -> Result<(), Error>
+where F: FnMut(&CU, &DIE, Location) -> Result<bool, Error> {
+    let mut unit_headers = dwarf.debug_info.units();
+    while let Ok(Some(header)) = unit_headers.next() {
+        let unit = match dwarf.unit(header) {
+            Ok(unit) => unit,
+            Err(_) => continue
+        };
+        let mut entries = RecoveringEntries::new(&unit);
+        while let Some(entry) = entries.next() {
             let location = Location {
-                header: header_offset,
+                header: header.offset(),
                 offset: entry.offset(),
             };
 
@@ -71,45 +265,519 @@ where F: FnMut(&CU, &DIE, Location) -> Result<bool, Error> {
     Ok(())
 }
 
-/// Represents DWARF data
+// Levenshtein distance between two strings, counted in chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != *cb);
+            cur.push(substitute.min(prev[j + 1] + 1).min(cur[j] + 1));
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+// Get the (decompressed) data of a DWARF section, or an empty slice if it is
+// missing. gimli names sections with the ELF `.debug_*` spelling, object
+// translates that to the `__debug_*` spelling used by Mach-O and resolves the
+// `/N` string table names PE/COFF uses for long section names, so the same
+// lookup works for every container format object can parse
+fn section_data<'data, R>(object: &object::File<'data, R>,
+                          id: gimli::SectionId) -> Cow<'data, [u8]>
+where R: ReadRef<'data> {
+    match object.section_by_name(id.name()) {
+        Some(ref section) => {
+            section.uncompressed_data()
+                   .unwrap_or(Cow::Borrowed(&[][..]))
+        },
+        None => Cow::Borrowed(&[][..]),
+    }
+}
+
+// The path stored in the .gnu_debugaltlink section, it is followed by the
+// build id of the supplementary file
+fn debug_altlink<'data, R>(object: &object::File<'data, R>) -> Option<PathBuf>
+where R: ReadRef<'data> {
+    let data = object.section_by_name(".gnu_debugaltlink")?.data().ok()?;
+    let end = data.iter().position(|byte| *byte == 0)?;
+    let path = String::from_utf8_lossy(&data[..end]);
+    Some(PathBuf::from(path.into_owned()))
+}
+
+// The defined symbols which have an address, the dynamic symbols are included
+// so that stripped binaries still have their exports
+fn symbol_table<'data, R>(object: &object::File<'data, R>) -> SymbolTable
+where R: ReadRef<'data> {
+    let mut table = SymbolTable::new();
+    for symbol in object.symbols().chain(object.dynamic_symbols()) {
+        if !symbol.is_definition() || symbol.address() == 0 {
+            continue;
+        }
+        if let Ok(name) = symbol.name() {
+            if !name.is_empty() {
+                table.entry(name.to_string()).or_insert(symbol.address());
+            }
+        }
+    }
+    table
+}
+
+// Fail up front when there are units but no abbreviations to parse them with,
+// as left behind by a bad strip, instead of silently skipping every unit
+fn check_abbrev(dwarf: &GimliDwarf) -> Result<(), Error> {
+    use gimli::Section;
+
+    if !dwarf.debug_info.reader().is_empty() &&
+       dwarf.debug_abbrev.reader().is_empty() {
+        return Err(Error::DwarfLoadError("missing .debug_abbrev".to_string()))
+    }
+    Ok(())
+}
+
+// Get the lowest DWARF version of the compile units, only the headers are
+// read so this is cheap enough to do on every load
+fn min_unit_version(dwarf: &GimliDwarf) -> Option<u16> {
+    let mut oldest: Option<u16> = None;
+    let mut unit_headers = dwarf.debug_info.units();
+    while let Ok(Some(header)) = unit_headers.next() {
+        let version = header.version();
+        oldest = Some(oldest.unwrap_or(version).min(version));
+    }
+    oldest
+}
+
+// Get the name of an entry of `unit` using an already borrowed `dwarf`
+fn unit_entry_name(dwarf: &GimliDwarf, unit: &CU, entry: &DIE)
+-> Option<String> {
+    match entry.attr_value(gimli::DW_AT_name) {
+        Ok(Some(value)) => match dwarf.attr_string(unit, value) {
+            Ok(name) => Some(name.to_string_lossy().into_owned()),
+            Err(_) => None
+        },
+        _ => None
+    }
+}
+
+// The DW_AT_byte_size of the struct at `entry`, the cheap half of its key
+fn struct_byte_size(entry: &DIE) -> Result<usize, Error> {
+    let byte_size = match entry.attr_value(gimli::DW_AT_byte_size) {
+        Ok(Some(value)) => value.udata_value(),
+        _ => None
+    };
+    match byte_size {
+        Some(byte_size) => Ok(byte_size as usize),
+        None => Err(Error::ByteSizeAttributeNotFound)
+    }
+}
+
+// Build the key of the struct at `entry` for `get_fg_named_structs_map`, the
+// members are read while walking the struct's children instead of seeking to
+// each one, which dominates the cost of scanning a whole binary
+fn struct_hash_key(dwarf: &GimliDwarf, unit: &CU, name: String,
+                   byte_size: usize, entry: &DIE)
+-> Result<StructHashKey, Error> {
+    let mut tree = match unit.entries_tree(Some(entry.offset())) {
+        Ok(tree) => tree,
+        Err(_) => return Err(Error::DIEError(
+            format!("Failed to seek to DIE at {:?}", entry.offset())
+        ))
+    };
+    let root = match tree.root() {
+        Ok(root) => root,
+        Err(_) => return Err(Error::DIEError(
+            format!("Failed to find next DIE at {:?}", entry.offset())
+        ))
+    };
+
+    let mut members: Vec<(String, usize)> = vec![];
+    let mut children = root.children();
+    while let Ok(Some(child)) = children.next() {
+        let member = child.entry();
+        if member.tag() != gimli::DW_TAG_member {
+            continue;
+        }
+        let member_name = {
+            unit_entry_name(dwarf, unit, member).unwrap_or_default()
+        };
+
+        // DWARF 5 bitfields only have a DW_AT_data_bit_offset, members with
+        // neither (as in unions) are at the start
+        let location = member.attr_value(gimli::DW_AT_data_member_location);
+        let bit_offset = member.attr_value(gimli::DW_AT_data_bit_offset);
+        let offset = match (location, bit_offset) {
+            (Ok(Some(value)), _) => value.udata_value(),
+            (Ok(None), Ok(Some(value))) => {
+                value.udata_value().map(|bit_offset| bit_offset / 8)
+            },
+            (Ok(None), Ok(None)) => Some(0),
+            _ => None
+        };
+        let offset = match offset {
+            Some(offset) => offset,
+            // other locations, such as expressions, have no offset to key
+            // on, so the struct is keyed on its name and size alone
+            None => return Ok(StructHashKey {
+                name, byte_size, members: vec![]
+            })
+        };
+        members.push((member_name, offset as usize));
+    }
+    Ok(StructHashKey { name, byte_size, members })
+}
+
+// Select the slice for `arch` out of a universal (fat) Mach-O binary
+fn fat_arch_slice<'data, R>(data: R, arch: object::Architecture)
+-> Result<&'data [u8], Error>
+where R: ReadRef<'data> {
+    use object::read::macho::{FatArch, FatHeader};
+
+    match object::FileKind::parse(data)? {
+        object::FileKind::MachOFat32 => {
+            for fat_arch in FatHeader::parse_arch32(data)? {
+                if fat_arch.architecture() == arch {
+                    return Ok(fat_arch.data(data)?)
+                }
+            }
+        },
+        object::FileKind::MachOFat64 => {
+            for fat_arch in FatHeader::parse_arch64(data)? {
+                if fat_arch.architecture() == arch {
+                    return Ok(fat_arch.data(data)?)
+                }
+            }
+        },
+        kind => return Err(Error::DwarfLoadError(
+            format!("Expected a fat Mach-O binary, found {:?}", kind)
+        ))
+    }
+    Err(Error::DwarfLoadError(
+        format!("The fat Mach-O binary has no {:?} slice", arch)
+    ))
+}
+
+// Move the entries of a unit which has been walked into `items`, naming the
+// anonymous ones after the typedef wrapping them and dropping the rest
+fn name_unit_items<T: Tagged>(
+    items: &mut Vec<(String, T)>,
+    unit_items: &mut Vec<(Option<String>, Location)>,
+    typedef_names: &mut HashMap<gimli::UnitOffset, String>
+) {
+    for (name, loc) in unit_items.drain(..) {
+        let name = match name {
+            Some(name) => name,
+            None => match typedef_names.get(&loc.offset) {
+                Some(name) => name.clone(),
+                None => continue
+            }
+        };
+        items.push((name, T::new(loc)));
+    }
+    typedef_names.clear();
+}
+
+/// Represents DWARF data borrowed from the loaded file, uncompressed sections
+/// are not copied (see `is_section_copied`), use `OwnedDwarf` when the data
+/// can't outlive it
 pub struct Dwarf<'a> {
     dwarf_cow: gimli::Dwarf<Cow<'a, [u8]>>,
-    endianness: RunTimeEndian
+    endianness: RunTimeEndian,
+    unknown_tag_policy: UnknownTagPolicy,
+    type_units: OnceLock<TypeUnitIndex>,
+    section_sizes: HashMap<gimli::SectionId, usize>,
+    copied_sections: HashSet<gimli::SectionId>,
+    debug_altlink: Option<PathBuf>,
+    min_version: Option<u16>
 }
 
 impl<'a> Dwarf<'a> {
+    /// Load the DWARF sections of an ELF, Mach-O (object file or dSYM) or
+    /// PE/COFF (e.g. MinGW built) file, uncompressed sections are borrowed
+    /// from `data`
     pub fn load(data: impl ReadRef<'a>) -> Result<Self, Error> {
         let object = object::File::parse(data)?;
+        Self::from_object(&object)
+    }
+
+    /// Like `load`, but also collect the symbol table of the file without
+    /// parsing it again, see `SymbolTable`
+    pub fn load_with_symbols(data: impl ReadRef<'a>)
+    -> Result<(Self, SymbolTable), Error> {
+        let object = object::File::parse(data)?;
+        Ok((Self::from_object(&object)?, symbol_table(&object)))
+    }
 
+    // Load the DWARF sections of an already parsed file
+    fn from_object<R>(object: &object::File<'a, R>) -> Result<Self, Error>
+    where R: ReadRef<'a> {
         let endianness = if object.is_little_endian() {
             gimli::RunTimeEndian::Little
         } else {
             gimli::RunTimeEndian::Big
         };
 
+        let mut section_sizes = HashMap::new();
+        let mut copied_sections = HashSet::new();
         let load_section = |id: gimli::SectionId|
         -> Result<Cow<[u8]>, gimli::Error> {
-            match object.section_by_name(id.name()) {
-                Some(ref section) => Ok(section
-                    .uncompressed_data()
-                    .unwrap_or(Cow::Borrowed(&[][..]))),
-                None => Ok(Cow::Borrowed(&[][..])),
+            let data = section_data(object, id);
+            section_sizes.insert(id, data.len());
+            if let Cow::Owned(_) = data {
+                copied_sections.insert(id);
             }
+            Ok(data)
         };
 
         // Load all of the sections
-        let dwarf_cow = gimli::Dwarf::load(&load_section).unwrap();
+        let dwarf_cow = gimli::Dwarf::load(load_section).unwrap();
+
+        let mut dwarf = Self{dwarf_cow, endianness,
+                             unknown_tag_policy: UnknownTagPolicy::default(),
+                             type_units: OnceLock::new(), section_sizes,
+                             copied_sections,
+                             debug_altlink: debug_altlink(object),
+                             min_version: None};
+        dwarf.borrow_dwarf(check_abbrev)?;
+        dwarf.min_version = dwarf.borrow_dwarf(min_unit_version);
+        Ok(dwarf)
+    }
+
+    /// Like `load`, but fail with `Error::DwarfLoadError` when the file has no
+    /// debug info rather than loading something where every lookup is empty
+    pub fn load_strict(data: impl ReadRef<'a>) -> Result<Self, Error> {
+        let dwarf = Self::load(data)?;
+        if !dwarf.has_debug_info() {
+            return Err(Error::DwarfLoadError("no debug info".to_string()))
+        }
+        Ok(dwarf)
+    }
+
+    /// Load the DWARF sections of the `arch` slice of a universal (fat)
+    /// Mach-O binary
+    pub fn load_arch(data: impl ReadRef<'a>, arch: object::Architecture)
+    -> Result<Self, Error> {
+        Self::load(fat_arch_slice(data, arch)?)
+    }
+
+    /// Set how types with tags that aren't modeled by `Type` are handled
+    pub fn set_unknown_tag_policy(&mut self, policy: UnknownTagPolicy) {
+        self.unknown_tag_policy = policy;
+    }
+
+    /// The size of the loaded (decompressed) data of a debug section, 0 when
+    /// the section is missing or isn't one that is loaded (e.g. .debug_frame)
+    pub fn section_size(&self, id: gimli::SectionId) -> usize {
+        self.section_sizes.get(&id).copied().unwrap_or(0)
+    }
+
+    /// The byte order of the target the file was built for, values read
+    /// from its memory have to be interpreted with it
+    pub fn target_endianness(&self) -> RunTimeEndian {
+        self.endianness
+    }
+
+    /// Whether loading the section made a copy of it rather than borrowing
+    /// it from the file, only compressed sections are copied (decompressed)
+    pub fn is_section_copied(&self, id: gimli::SectionId) -> bool {
+        self.copied_sections.contains(&id)
+    }
+
+    /// The path of the supplementary file named by `.gnu_debugaltlink`, as
+    /// written by dwz, it may be relative to the directory of the file
+    pub fn debug_altlink(&self) -> Option<&Path> {
+        self.debug_altlink.as_deref()
+    }
 
-        Ok(Self{dwarf_cow, endianness})
+    /// Load the supplementary file (see `debug_altlink`) which types and
+    /// strings of this file may be moved to, the types are resolved by
+    /// `get_type` just like those of this file. Its uncompressed sections
+    /// are borrowed from `data`
+    pub fn load_sup(&mut self, data: impl ReadRef<'a>) -> Result<(), Error> {
+        let object = object::File::parse(data)?;
+        let load_section = |id: gimli::SectionId|
+        -> Result<Cow<[u8]>, gimli::Error> {
+            Ok(section_data(&object, id))
+        };
+        self.dwarf_cow.load_sup(load_section).unwrap();
+        Ok(())
     }
 }
 
 pub(crate) mod borrowable_dwarf {
     use crate::GimliDwarf;
+    use crate::UnknownTagPolicy;
+    use crate::Location;
+    use crate::Error;
+    use super::TypeUnitIndex;
 
     pub trait BorrowableDwarf {
         fn borrow_dwarf<F,R>(&self, f: F) -> R
         where F: FnOnce(&GimliDwarf) -> R;
+
+        fn unknown_tag_policy(&self) -> UnknownTagPolicy;
+
+        fn type_units(&self) -> &TypeUnitIndex;
+
+        fn recorded_min_version(&self) -> Option<u16>;
+
+        /// Get the location of the type DIE of the type unit with the
+        /// specified signature
+        fn resolve_type_signature(&self, signature: gimli::DebugTypeSignature)
+        -> Result<Location, Error> {
+            match self.type_units().get(&signature) {
+                Some(location) => Ok(*location),
+                None => Err(Error::TypeSignatureNotFound(signature))
+            }
+        }
+
+        /// Get the location of the entry at `offset` in the supplementary
+        /// file, fails with `Error::SupplementaryReference` if it isn't
+        /// loaded
+        fn resolve_sup_reference(&self, offset: gimli::DebugInfoOffset)
+        -> Result<Location, Error> {
+            self.borrow_dwarf(|dwarf| super::sup_location(dwarf, offset))
+        }
+    }
+}
+
+// Maps the signature of each type unit to the location of its type DIE
+pub(crate) type TypeUnitIndex = HashMap<gimli::DebugTypeSignature, Location>;
+
+// Index the type units of .debug_types (DWARF 4) and .debug_info (DWARF 5)
+fn build_type_unit_index(dwarf: &GimliDwarf) -> TypeUnitIndex {
+    let mut index = HashMap::new();
+    let mut insert = |header: gimli::UnitHeader<_>| {
+        if let gimli::UnitType::Type { type_signature, type_offset } =
+        header.type_() {
+            let location = Location {
+                header: header.offset(),
+                offset: type_offset,
+            };
+            index.insert(type_signature, location);
+        }
+    };
+
+    let mut unit_headers = dwarf.debug_types.units();
+    while let Ok(Some(header)) = unit_headers.next() {
+        insert(header);
+    }
+    let mut unit_headers = dwarf.debug_info.units();
+    while let Ok(Some(header)) = unit_headers.next() {
+        insert(header);
+    }
+    index
+}
+
+// Units of the supplementary file are located as if its .debug_info followed
+// that of the main file, offsets from here on are in the supplementary file
+fn sup_base(dwarf: &GimliDwarf) -> usize {
+    use gimli::Section;
+    gimli::Reader::len(dwarf.debug_info.reader())
+}
+
+// Whether `unit` was parsed out of the supplementary file
+pub(crate) fn is_sup_unit(dwarf: &GimliDwarf, unit: &CU) -> bool {
+    match (dwarf.sup(), unit.header.offset().as_debug_info_offset()) {
+        (Some(_), Some(offset)) => offset.0 >= sup_base(dwarf),
+        _ => false
+    }
+}
+
+// The location of the entry at `offset` in the .debug_info of the
+// supplementary file, e.g. from a DW_FORM_GNU_ref_alt reference
+fn sup_location(dwarf: &GimliDwarf, offset: gimli::DebugInfoOffset)
+-> Result<Location, Error> {
+    let sup = match dwarf.sup() {
+        Some(sup) => sup,
+        None => return Err(Error::SupplementaryReference(offset))
+    };
+    let base = sup_base(dwarf);
+    let mut unit_headers = sup.debug_info.units();
+    while let Ok(Some(header)) = unit_headers.next() {
+        let start = match header.offset().as_debug_info_offset() {
+            Some(start) => start.0,
+            None => continue
+        };
+        let end = start + header.length_including_self();
+        if (start..end).contains(&offset.0) {
+            return Ok(Location {
+                header: gimli::DebugInfoOffset(base + start).into(),
+                offset: gimli::UnitOffset(offset.0 - start)
+            })
+        }
+    }
+    Err(Error::DIEError(
+        format!("No supplementary unit contains offset {:#x}", offset.0)
+    ))
+}
+
+// Parse the unit at `offset`, which may be in the supplementary file
+fn located_unit<'a>(dwarf: &GimliDwarf<'a>, offset: gimli::UnitSectionOffset)
+-> Result<CU<'a>, Error> {
+    let base = sup_base(dwarf);
+    let unit = match (dwarf.sup(), offset) {
+        (Some(sup), gimli::UnitSectionOffset::DebugInfoOffset(offset))
+        if offset.0 >= base => {
+            let sup_offset = gimli::DebugInfoOffset(offset.0 - base);
+            let header = unit_header(sup, sup_offset.into())?;
+            // keep the offset of the header in the combined space so the
+            // locations of its entries lead back here
+            let entries_offset = gimli::UnitOffset(header.header_size());
+            let entries = match header.range_from(entries_offset..) {
+                Ok(entries) => entries,
+                Err(e) => return Err(Error::CUError(
+                    format!("Failed to read UnitHeader, error: {}", e)
+                ))
+            };
+            let header = gimli::UnitHeader::new(
+                header.encoding(), header.unit_length(), header.type_(),
+                header.debug_abbrev_offset(), offset.into(), entries
+            );
+            gimli::Unit::new(sup, header)
+        },
+        _ => gimli::Unit::new(dwarf, unit_header(dwarf, offset)?)
+    };
+    match unit {
+        Ok(unit) => Ok(unit),
+        Err(e) => Err(Error::CUError(
+            format!("Failed to parse Unit, error: {}", e)
+        ))
+    }
+}
+
+// Parse the header of the unit at `offset` in either .debug_info or
+// .debug_types
+fn unit_header<'a>(dwarf: &GimliDwarf<'a>, offset: gimli::UnitSectionOffset)
+-> Result<gimli::UnitHeader<gimli::EndianSlice<'a, RunTimeEndian>>, Error> {
+    let header = match offset {
+        gimli::UnitSectionOffset::DebugInfoOffset(offset) => {
+            dwarf.debug_info.header_from_offset(offset)
+        },
+        // gimli can't seek within .debug_types, so walk the unit headers
+        gimli::UnitSectionOffset::DebugTypesOffset(offset) => {
+            let mut unit_headers = dwarf.debug_types.units();
+            loop {
+                match unit_headers.next() {
+                    Ok(Some(header)) => {
+                        if header.offset().as_debug_types_offset() ==
+                        Some(offset) {
+                            break Ok(header)
+                        }
+                    },
+                    Ok(None) => break Err(gimli::Error::NoEntryAtGivenOffset),
+                    Err(e) => break Err(e)
+                }
+            }
+        }
+    };
+    match header {
+        Ok(header) => Ok(header),
+        Err(e) => Err(
+            Error::CUError(
+                format!("Failed to seek to UnitHeader, error: {}", e)
+            ))
     }
 }
 
@@ -122,8 +790,8 @@ where Self: Sized + DwarfContext {
     -> Result<Option<T>, Error> {
         let mut item: Option<T> = None;
         self.borrow_dwarf(|dwarf| {
-            let _ = for_each_die::<T, _>(dwarf, |_, entry, loc| {
-                if let Some(entry_name) = get_entry_name(self, entry) {
+            let _ = for_each_die::<T, _>(dwarf, None, |unit, entry, loc| {
+                if let Some(entry_name) = get_entry_name(self, unit, entry) {
                     if name == entry_name {
                         item = Some(T::new(loc));
                         return Ok(true);
@@ -135,13 +803,226 @@ where Self: Sized + DwarfContext {
         Ok(item)
     }
 
-    /// Get a HashMap of all debug info of some type hashed by name
+    /// Like `lookup_type`, with control over whether declarations are hits
+    fn lookup_type_opts<T: Tagged>(&self, name: &str, opts: &LookupOptions)
+    -> Result<Option<T>, Error> {
+        let mut item: Option<T> = None;
+        let mut declaration: Option<T> = None;
+        self.borrow_dwarf(|dwarf| {
+            for_each_entry(dwarf, |unit, entry, loc| {
+                if entry.tag() != T::tag() {
+                    return Ok(false)
+                }
+                if get_entry_name(self, unit, entry).as_deref() != Some(name) {
+                    return Ok(false)
+                }
+
+                let is_declaration = matches!(
+                    entry.attr(gimli::DW_AT_declaration), Ok(Some(_))
+                );
+                if !is_declaration {
+                    item = Some(T::new(loc));
+                    return Ok(true)
+                }
+                if opts.skip_declarations {
+                    return Ok(false)
+                }
+                if opts.prefer_definition {
+                    declaration.get_or_insert(T::new(loc));
+                    return Ok(false)
+                }
+                item = Some(T::new(loc));
+                Ok(true)
+            })
+        })?;
+        Ok(item.or(declaration))
+    }
+
+    /// Get the definition named `name` with whichever of `tags` comes first,
+    /// e.g. `[DW_TAG_structure_type, DW_TAG_typedef]` prefers a struct over a
+    /// typedef of the same name, all tags are searched in a single pass which
+    /// stops early on a match of the first tag
+    fn lookup_first_of(&self, name: &str, tags: &[gimli::DwTag])
+    -> Result<Option<Type>, Error> {
+        let mut best: Option<(usize, Type)> = None;
+        self.borrow_dwarf(|dwarf| {
+            for_each_entry(dwarf, |unit, entry, loc| {
+                let rank = match tags.iter().position(|t| *t == entry.tag()) {
+                    Some(rank) => rank,
+                    None => return Ok(false)
+                };
+                if matches!(best, Some((best_rank, _)) if best_rank <= rank) {
+                    return Ok(false)
+                }
+                if matches!(entry.attr(gimli::DW_AT_declaration), Ok(Some(_))) {
+                    return Ok(false)
+                }
+                if get_entry_name(self, unit, entry).as_deref() != Some(name) {
+                    return Ok(false)
+                }
+
+                // tags which aren't modeled by Type never match
+                if let Ok(typ) = entry_to_type(loc, entry) {
+                    best = Some((rank, typ));
+                }
+                Ok(rank == 0)
+            })
+        })?;
+        Ok(best.map(|(_, typ)| typ))
+    }
+
+    /// Get every occurrence of debug info of some type with the specified
+    /// name, a name may refer to distinct definitions in different CUs
+    fn lookup_all<T: Tagged>(&self, name: &str) -> Result<Vec<T>, Error> {
+        let mut items: Vec<T> = Vec::new();
+        self.borrow_dwarf(|dwarf| {
+            let _ = for_each_die::<T, _>(dwarf, None, |unit, entry, loc| {
+                if let Some(entry_name) = get_entry_name(self, unit, entry) {
+                    if name == entry_name {
+                        items.push(T::new(loc));
+                    }
+                }
+                Ok(false)
+            });
+        });
+        Ok(items)
+    }
+
+    /// Look up the struct named `struct_name` and call `f` on each of its
+    /// members in declaration order, stopping at the first error
+    fn for_each_member<F>(&self, struct_name: &str, mut f: F)
+    -> Result<(), Error>
+    where F: FnMut(&Member, &Self) -> Result<(), Error> {
+        let struc = match self.lookup_type::<Struct>(struct_name.to_string())? {
+            Some(struc) => struc,
+            None => return Err(Error::TypeNotFound(struct_name.to_string()))
+        };
+        for member in struc.members(self)?.iter() {
+            f(member, self)?;
+        }
+        Ok(())
+    }
+
+    /// Look up the struct named `struct_name` and map the name of each of its
+    /// members to its offset in bytes. Members without a constant location
+    /// (e.g. C++ static members) and anonymous members are skipped, the
+    /// members of an anonymous struct or union can be found through
+    /// `member_by_name` instead
+    fn member_offsets(&self, struct_name: &str)
+    -> Result<HashMap<String, usize>, Error> {
+        let mut offsets: HashMap<String, usize> = HashMap::new();
+        self.for_each_member(struct_name, |member, dwarf| {
+            if !member.has_location(dwarf)? {
+                return Ok(())
+            }
+            let name = match member.name(dwarf) {
+                Ok(name) => name,
+                Err(Error::NameAttributeNotFound) => return Ok(()),
+                Err(e) => return Err(e)
+            };
+            match member.offset(dwarf) {
+                Ok(offset) => offsets.insert(name, offset),
+                Err(Error::MemberLocationAttributeNotFound) => return Ok(()),
+                Err(e) => return Err(e)
+            };
+            Ok(())
+        })?;
+        Ok(offsets)
+    }
+
+    /// The name of the tag of the DIE at `location`, e.g.
+    /// `"DW_TAG_structure_type"`, including tags which aren't modeled by
+    /// `Type`, unknown (e.g. vendor) tags are given as their numeric value
+    fn tag_name_at(&self, location: &Location) -> Result<String, Error> {
+        self.entry_context(location, |entry| {
+            let tag = entry.tag();
+            match tag.static_string() {
+                Some(name) => name.to_string(),
+                None => format!("{:#x}", tag.0)
+            }
+        })
+    }
+
+    /// Get every type with the specified mangled name (DW_AT_linkage_name),
+    /// both declarations and definitions are returned
+    fn lookup_by_linkage_name(&self, name: &str) -> Result<Vec<Type>, Error> {
+        let mut items: Vec<Type> = Vec::new();
+        self.borrow_dwarf(|dwarf| {
+            for_each_entry(dwarf, |unit, entry, loc| {
+                if let Some(linkage_name) =
+                    get_entry_linkage_name(self, unit, entry) {
+                    if name == linkage_name {
+                        // entries which aren't types are skipped
+                        if let Ok(typ) = entry_to_type(loc, entry) {
+                            items.push(typ);
+                        }
+                    }
+                }
+                Ok(false)
+            })
+        })?;
+        Ok(items)
+    }
+
+    /// Get every entry of some type (e.g. Subprogram or Variable) with the
+    /// specified mangled name, both declarations and definitions are returned
+    fn lookup_tagged_by_linkage_name<T: Tagged>(&self, name: &str)
+    -> Result<Vec<T>, Error> {
+        let mut items: Vec<T> = Vec::new();
+        self.borrow_dwarf(|dwarf| {
+            for_each_entry(dwarf, |unit, entry, loc| {
+                if entry.tag() != T::tag() {
+                    return Ok(false)
+                }
+                if let Some(linkage_name) =
+                    get_entry_linkage_name(self, unit, entry) {
+                    if name == linkage_name {
+                        items.push(T::new(loc));
+                    }
+                }
+                Ok(false)
+            })
+        })?;
+        Ok(items)
+    }
+
+    /// Get up to `max` names of some type closest to `name` by edit
+    /// distance, closest first, e.g. to suggest alternatives on a lookup miss
+    fn suggest_names<T: Tagged>(&self, name: &str, max: usize)
+    -> Result<Vec<String>, Error> {
+        let mut names: HashSet<String> = HashSet::new();
+        self.borrow_dwarf(|dwarf| {
+            let _ = for_each_die::<T, _>(dwarf, None, |unit, entry, _| {
+                if let Some(entry_name) = get_entry_name(self, unit, entry) {
+                    names.insert(entry_name);
+                }
+                Ok(false)
+            });
+        });
+
+        let mut ranked: Vec<(usize, String)> = names.into_iter().map(|n| {
+            (edit_distance(name, &n), n)
+        }).collect();
+        ranked.sort();
+        Ok(ranked.into_iter().take(max).map(|(_, n)| n).collect())
+    }
+
+    /// Get a HashMap of all debug info of some type hashed by name. Like
+    /// `get_named_types` declarations are skipped, but only the last
+    /// definition of each name is kept
     fn get_named_types_map<T: Tagged>(&self)
     -> Result<HashMap<String, T>, Error> {
+        self.get_named_types_map_with_progress(None)
+    }
+
+    /// `get_named_types_map`, calling `progress` after each compile unit
+    fn get_named_types_map_with_progress<T: Tagged>(
+        &self, progress: Option<&mut dyn FnMut(ScanProgress)>
+    ) -> Result<HashMap<String, T>, Error> {
         let mut item_locations: HashMap<String, T> = HashMap::new();
         self.borrow_dwarf(|dwarf| {
-            let _ = for_each_die::<T, _>(dwarf, |_unit, entry, loc| {
-                 if let Some(name) = get_entry_name(self, entry) {
+            let _ = for_each_die::<T, _>(dwarf, progress, |unit, entry, loc| {
+                 if let Some(name) = get_entry_name(self, unit, entry) {
                     let typ = T::new(loc);
                     item_locations.insert(name, typ);
                  }
@@ -153,41 +1034,246 @@ where Self: Sized + DwarfContext {
 
     /// Similar to get_named_entries_map but with a more fine grained key for
     /// the hash, this should catch most cases where a struct with the same name
-    /// is defined in multiple places
+    /// is defined in multiple places. Structs without a DW_AT_byte_size or
+    /// whose children can't be read are skipped, and structs with a member
+    /// located by an expression rather than an offset are keyed on their name
+    /// and size alone (with no members)
     fn get_fg_named_structs_map(&self)
+    -> Result<HashMap<StructHashKey, Struct>, Error> {
+        self.get_fg_named_structs_map_with_progress(None)
+    }
+
+    /// `get_fg_named_structs_map`, calling `progress` after each compile unit
+    fn get_fg_named_structs_map_with_progress(
+        &self, progress: Option<&mut dyn FnMut(ScanProgress)>
+    ) -> Result<HashMap<StructHashKey, Struct>, Error> {
+        let mut struct_locations: HashMap<StructHashKey, Struct> = {
+            HashMap::new()
+        };
+        self.borrow_dwarf(|dwarf| {
+            for_each_die::<Struct, _>(dwarf, progress, |unit, entry, loc| {
+                if let Some(name) = unit_entry_name(dwarf, unit, entry) {
+                    let byte_size = match struct_byte_size(entry) {
+                        Ok(byte_size) => byte_size,
+                        Err(_) => return Ok(false)
+                    };
+                    match struct_hash_key(dwarf, unit, name, byte_size, entry) {
+                        Ok(key) => {
+                            struct_locations.insert(key, Struct::new(loc));
+                        },
+                        Err(_) => return Ok(false)
+                    }
+                }
+                Ok(false)
+            })
+        })?;
+        Ok(struct_locations)
+    }
+
+    /// `get_fg_named_structs_map`, calling `progress` with the number of
+    /// compile units scanned and the total after each unit, for callers which
+    /// only need a progress bar rather than a `ScanProgress`
+    fn get_fg_named_structs_map_with_unit_progress(
+        &self, progress: Option<&mut dyn FnMut(usize, usize)>
+    ) -> Result<HashMap<StructHashKey, Struct>, Error> {
+        match progress {
+            Some(progress) => {
+                let mut report = |p: ScanProgress| {
+                    progress(p.cus_done, p.cus_total)
+                };
+                self.get_fg_named_structs_map_with_progress(Some(&mut report))
+            },
+            None => self.get_fg_named_structs_map_with_progress(None)
+        }
+    }
+
+    /// A faster `get_fg_named_structs_map` for binaries which repeat the same
+    /// headers across many compile units: once a struct is keyed, any later
+    /// struct with the same name and size is assumed to be the same
+    /// definition and skipped without reading its members. Definitions which
+    /// only differ in their members are merged, keeping the first one found.
+    /// Structs which can't be keyed are handled as in
+    /// `get_fg_named_structs_map`
+    fn get_fg_named_structs_map_fast(&self)
     -> Result<HashMap<StructHashKey, Struct>, Error> {
         let mut struct_locations: HashMap<StructHashKey, Struct> = {
             HashMap::new()
         };
+        let mut seen: HashSet<(String, usize)> = HashSet::new();
         self.borrow_dwarf(|dwarf| {
-            let _ = for_each_die::<Struct, _>(dwarf, |unit, entry, loc| {
-                if let Some(name) = get_entry_name(self, entry) {
-                    let typ = Struct::new(loc);
-                    let byte_size = typ.u_byte_size(unit)?;
-                    let members: Vec<(String,usize)> = {
-                        typ.u_members(unit)?
-                        .iter().map(|m| -> Result<(String,usize), Error> {
-                            Ok((m.u_name(self, unit).unwrap_or("".to_string()),
-                                m.u_offset(unit)?))
+            for_each_die::<Struct, _>(dwarf, None, |unit, entry, loc| {
+                if let Some(name) = unit_entry_name(dwarf, unit, entry) {
+                    let byte_size = match struct_byte_size(entry) {
+                        Ok(byte_size) => byte_size,
+                        Err(_) => return Ok(false)
+                    };
+                    if !seen.insert((name.clone(), byte_size)) {
+                        return Ok(false)
+                    }
+                    match struct_hash_key(dwarf, unit, name, byte_size, entry) {
+                        Ok(key) => {
+                            struct_locations.insert(key, Struct::new(loc));
+                        },
+                        Err(_) => return Ok(false)
+                    }
+                }
+                Ok(false)
+            })
+        })?;
+        Ok(struct_locations)
+    }
+
+    /// Named structs whose definitions render (with `opts`) to distinct text,
+    /// in the order they are found. Slower than `get_fg_named_structs_map` as
+    /// each struct is rendered, but structs are told apart the way a reader
+    /// of the output would, e.g. member types count while offsets only do
+    /// when `opts` shows them
+    fn unique_structs_by_render(&self, opts: &FormatOptions)
+    -> Result<Vec<Struct>, Error> {
+        let mut structs: Vec<Struct> = Vec::new();
+        self.borrow_dwarf(|dwarf| {
+            let _ = for_each_die::<Struct, _>(dwarf, None, |unit, entry, loc| {
+                if unit_entry_name(dwarf, unit, entry).is_some() {
+                    structs.push(Struct::new(loc));
+                }
+                Ok(false)
+            });
+        });
+
+        let mut rendered: HashSet<String> = HashSet::new();
+        let mut unique: Vec<Struct> = Vec::new();
+        for struc in structs {
+            if rendered.insert(struc.to_string_opts(self, opts)?) {
+                unique.push(struc);
+            }
+        }
+        Ok(unique)
+    }
+
+    /// Names of the structs, unions, enums and classes which are referenced
+    /// (e.g. as pointer targets or member types) but only ever declared in
+    /// this file, i.e. the types needed from elsewhere for a complete header,
+    /// sorted and without duplicates
+    fn undefined_references(&self) -> Result<Vec<String>, Error> {
+        let aggregate = |tag| matches!(tag,
+            gimli::DW_TAG_structure_type | gimli::DW_TAG_union_type |
+            gimli::DW_TAG_enumeration_type | gimli::DW_TAG_class_type
+        );
+
+        let mut referenced: HashSet<gimli::UnitSectionOffset> = HashSet::new();
+        let mut declared = Vec::new();
+        let mut defined: HashSet<(gimli::DwTag, String)> = HashSet::new();
+        self.borrow_dwarf(|dwarf| {
+            for_each_entry(dwarf, |unit, entry, _| {
+                match entry.attr_value(gimli::DW_AT_type) {
+                    Ok(Some(gimli::AttributeValue::UnitRef(offset))) => {
+                        referenced.insert(offset.to_unit_section_offset(unit));
+                    },
+                    Ok(Some(gimli::AttributeValue::DebugInfoRef(offset))) => {
+                        referenced.insert(offset.into());
+                    },
+                    _ => ()
+                };
+
+                if !aggregate(entry.tag()) {
+                    return Ok(false)
+                }
+                let name = match get_entry_name(self, unit, entry) {
+                    Some(name) => name,
+                    None => return Ok(false)
+                };
+                if matches!(entry.attr(gimli::DW_AT_declaration), Ok(Some(_))) {
+                    let offset = entry.offset().to_unit_section_offset(unit);
+                    declared.push((offset, entry.tag(), name));
+                } else {
+                    defined.insert((entry.tag(), name));
+                }
+                Ok(false)
+            })
+        })?;
+
+        let names: BTreeSet<String> = declared.into_iter().filter_map(
+            |(offset, tag, name)| {
+                if !referenced.contains(&offset) {
+                    return None
+                }
+                if defined.contains(&(tag, name.clone())) {
+                    return None
+                }
+                Some(name)
+            }
+        ).collect();
+        Ok(names.into_iter().collect())
+    }
+
+    /// Like get_fg_named_structs_map but for enums, keyed by name, size and
+    /// enumerators
+    fn get_fg_named_enums_map(&self)
+    -> Result<HashMap<EnumHashKey, Enum>, Error> {
+        self.get_fg_named_enums_map_with_progress(None)
+    }
+
+    /// `get_fg_named_enums_map`, calling `progress` after each compile unit
+    fn get_fg_named_enums_map_with_progress(
+        &self, progress: Option<&mut dyn FnMut(ScanProgress)>
+    ) -> Result<HashMap<EnumHashKey, Enum>, Error> {
+        let mut enum_locations: HashMap<EnumHashKey, Enum> = HashMap::new();
+        self.borrow_dwarf(|dwarf| {
+            let _ = for_each_die::<Enum, _>(dwarf, progress,
+                                           |unit, entry, loc| {
+                if let Some(name) = get_entry_name(self, unit, entry) {
+                    let typ = Enum::new(loc);
+                    let byte_size = typ.u_byte_size(self, unit)?;
+                    let enumerators: Vec<(String,i64)> = {
+                        typ.u_enumerators(unit)?
+                        .iter().map(|e| -> Result<(String,i64), Error> {
+                            Ok((e.u_name(self, unit)?, e.u_value(unit)?))
                         }).collect::<Result<Vec<_>, _>>()?
                     };
 
-                    let key = StructHashKey {name, byte_size, members};
-                    struct_locations.insert(key, typ);
+                    let key = EnumHashKey {name, byte_size, enumerators};
+                    enum_locations.insert(key, typ);
                 }
                 Ok(false)
             });
         });
-        Ok(struct_locations)
+        Ok(enum_locations)
     }
 
-    /// Get a vector of all debug info of some type by name
+    /// Like `get_named_types`, but each item is paired with the compile unit
+    /// it is defined in
+    fn types_with_cu<T: Tagged>(&self)
+    -> Result<Vec<(CompileUnit, String, T)>, Error> {
+        let mut items: Vec<(CompileUnit, String, T)> = Vec::new();
+        self.borrow_dwarf(|dwarf| {
+            let _ = for_each_die::<T, _>(dwarf, None, |unit, entry, loc| {
+                let name = match get_entry_name(self, unit, entry) {
+                    Some(name) => name,
+                    None => return Ok(false)
+                };
+                items.push((CompileUnit::from_unit(unit), name, T::new(loc)));
+                Ok(false)
+            });
+        });
+        Ok(items)
+    }
+
+    /// Get a vector of all debug info of some type by name. Only definitions
+    /// are listed, DIEs with DW_AT_declaration (e.g. `struct foo;`) are
+    /// skipped
     fn get_named_types<T: Tagged>(&self)
     -> Result<Vec<(String, T)>, Error> {
+        self.get_named_types_with_progress(None)
+    }
+
+    /// `get_named_types`, calling `progress` after each compile unit
+    fn get_named_types_with_progress<T: Tagged>(
+        &self, progress: Option<&mut dyn FnMut(ScanProgress)>
+    ) -> Result<Vec<(String, T)>, Error> {
         let mut items: Vec<(String, T)> = Vec::new();
         self.borrow_dwarf(|dwarf| {
-            let _ = for_each_die::<T, _>(dwarf, |_, entry, loc| {
-                if let Some(name) = get_entry_name(self, entry) {
+            let _ = for_each_die::<T, _>(dwarf, progress, |unit, entry, loc| {
+                if let Some(name) = get_entry_name(self, unit, entry) {
                     let typ = T::new(loc);
                     items.push((name, typ));
                 }
@@ -196,44 +1282,444 @@ where Self: Sized + DwarfContext {
         });
         Ok(items)
     }
+
+    /// `get_named_types`, calling `progress` with the number of compile units
+    /// scanned and the total after each unit
+    fn get_named_types_with_unit_progress<T: Tagged>(
+        &self, progress: Option<&mut dyn FnMut(usize, usize)>
+    ) -> Result<Vec<(String, T)>, Error> {
+        match progress {
+            Some(progress) => {
+                let mut report = |p: ScanProgress| {
+                    progress(p.cus_done, p.cus_total)
+                };
+                self.get_named_types_with_progress(Some(&mut report))
+            },
+            None => self.get_named_types_with_progress(None)
+        }
+    }
+
+    /// Like `get_named_types`, but anonymous structs, unions and enums are
+    /// listed under the name of the typedef wrapping them (as in `typedef
+    /// struct { ... } foo;`) rather than skipped
+    fn get_named_types_with_typedef_names<T: Tagged>(&self)
+    -> Result<Vec<(String, T)>, Error> {
+        self.get_named_types_with_typedef_names_with_progress(None)
+    }
+
+    /// `get_named_types_with_typedef_names`, calling `progress` after each
+    /// compile unit
+    fn get_named_types_with_typedef_names_with_progress<T: Tagged>(
+        &self, progress: Option<&mut dyn FnMut(ScanProgress)>
+    ) -> Result<Vec<(String, T)>, Error> {
+        let typedefs = match T::tag() {
+            gimli::DW_TAG_structure_type | gimli::DW_TAG_union_type |
+            gimli::DW_TAG_enumeration_type => Some(gimli::DW_TAG_typedef),
+            _ => None
+        };
+        let mut items: Vec<(String, T)> = Vec::new();
+
+        // a typedef can come before or after the type it wraps, so the items
+        // of a unit are only named once the whole unit has been walked
+        let mut header: Option<gimli::UnitSectionOffset> = None;
+        let mut unit_items: Vec<(Option<String>, Location)> = Vec::new();
+        let mut typedef_names: HashMap<gimli::UnitOffset, String> = {
+            HashMap::new()
+        };
+        self.borrow_dwarf(|dwarf| {
+            let _ = for_each_die_also::<T, _>(dwarf, progress, typedefs,
+                                              |unit, entry, loc| {
+                if header != Some(loc.header) {
+                    name_unit_items(&mut items, &mut unit_items,
+                                    &mut typedef_names);
+                    header = Some(loc.header);
+                }
+                if entry.tag() == T::tag() {
+                    let name = get_entry_name(self, unit, entry);
+                    unit_items.push((name, loc));
+                    return Ok(false)
+                }
+                if let Ok(Some(gimli::AttributeValue::UnitRef(offset))) =
+                    entry.attr_value(gimli::DW_AT_type) {
+                    if let Some(name) = unit_entry_name(dwarf, unit, entry) {
+                        typedef_names.entry(offset).or_insert(name);
+                    }
+                }
+                Ok(false)
+            });
+        });
+        name_unit_items(&mut items, &mut unit_items, &mut typedef_names);
+        Ok(items)
+    }
+
+    /// The number of unique structs (as in `get_fg_named_structs_map`) of
+    /// each byte size, ordered by size
+    fn struct_size_histogram(&self) -> Result<BTreeMap<usize, usize>, Error> {
+        let mut histogram: BTreeMap<usize, usize> = BTreeMap::new();
+        for struc in self.get_fg_named_structs_map()?.values() {
+            let byte_size = match struc.byte_size(self) {
+                Ok(byte_size) => byte_size,
+                Err(Error::ByteSizeAttributeNotFound) => continue,
+                Err(e) => return Err(e)
+            };
+            *histogram.entry(byte_size).or_insert(0) += 1;
+        }
+        Ok(histogram)
+    }
+
+    /// Count the compile units, only the unit headers are read so this is
+    /// much cheaper than parsing each unit
+    fn num_compile_units(&self) -> Result<usize, Error> {
+        self.borrow_dwarf(count_units)
+    }
+
+    /// The size of a pointer on the target in bytes, this is the address size
+    /// shared by the compile units, e.g. 4 for a 32-bit target even when
+    /// analyzing it on a 64-bit host. Fails with `Error::MixedAddressSizes`
+    /// when the units disagree (e.g. an image linking 32-bit and 64-bit
+    /// objects), `CompileUnit::address_size` gives the size of each unit
+    fn pointer_size(&self) -> Result<usize, Error> {
+        self.borrow_dwarf(|dwarf| {
+            let mut address_size: Option<u8> = None;
+            let mut unit_headers = dwarf.debug_info.units();
+            loop {
+                let header = match unit_headers.next() {
+                    Ok(Some(header)) => header,
+                    Ok(None) => break,
+                    Err(e) => return Err(Error::CUError(
+                        format!("Failed to read UnitHeader, error: {}", e)
+                    ))
+                };
+                match address_size {
+                    Some(size) if size != header.address_size() => {
+                        return Err(Error::MixedAddressSizes(
+                            size, header.address_size()
+                        ))
+                    },
+                    Some(_) => {},
+                    None => address_size = Some(header.address_size())
+                }
+            }
+            match address_size {
+                Some(size) => Ok(size as usize),
+                None => Err(Error::CUError(
+                    "Failed to find a UnitHeader".to_string()
+                ))
+            }
+        })
+    }
+
+    /// The lowest DWARF version of the compile units, recorded when the file
+    /// was loaded, `None` when there are no units
+    fn min_version(&self) -> Option<u16> {
+        self.recorded_min_version()
+    }
+
+    /// Check that every compile unit uses DWARF 4 or newer, the versions
+    /// this crate is written against. Older units are still parsed on a best
+    /// effort basis, the lowest version found is returned as an
+    /// `Error::UnsupportedVersion` so tools can warn about it
+    fn check_version(&self) -> Result<(), Error> {
+        match self.min_version() {
+            Some(version) if version < 4 => {
+                Err(Error::UnsupportedVersion(version))
+            },
+            _ => Ok(())
+        }
+    }
+
+    /// Call `f` with each parsed compile unit, an escape hatch for running
+    /// gimli-level extraction of attributes/tags this crate doesn't model.
+    /// Units are `gimli::Unit<gimli::EndianSlice<gimli::RunTimeEndian>>`,
+    /// borrowing the loaded sections, so they can't outlive the callback.
+    /// An error returned by `f` stops the iteration and is passed through
+    fn for_each_cu<F>(&self, mut f: F) -> Result<(), Error>
+    where F: FnMut(&gimli::Unit<gimli::EndianSlice<RunTimeEndian>>)
+                   -> Result<(), Error> {
+        self.borrow_dwarf(|dwarf| {
+            let mut unit_headers = dwarf.debug_info.units();
+            loop {
+                let header = match unit_headers.next() {
+                    Ok(Some(header)) => header,
+                    Ok(None) => return Ok(()),
+                    Err(e) => return Err(
+                        Error::CUError(
+                            format!("Failed to read UnitHeader, error: {}", e)
+                        ))
+                };
+                let unit = match dwarf.unit(header) {
+                    Ok(unit) => unit,
+                    Err(e) => return Err(
+                        Error::CUError(
+                            format!("Failed to parse Unit, error: {}", e)
+                        ))
+                };
+                f(&unit)?;
+            }
+        })
+    }
+
+    /// Whether .debug_info has any units, this is false for a stripped binary
+    /// (which still loads) or one built without `-g`
+    fn has_debug_info(&self) -> bool {
+        matches!(self.num_compile_units(), Ok(count) if count > 0)
+    }
+
+    /// Render the given types as a C header, along with the typedefs, enums
+    /// and aggregates they depend on, in an order that compiles
+    fn emit_c_header(&self, types: &[Type], opts: &FormatOptions)
+    -> Result<String, Error> {
+        crate::format::emit_c_header(self, types, opts)
+    }
 }
 
 impl DwarfLookups for Dwarf<'_> {}
 impl DwarfLookups for OwnedDwarf {}
 
-/// Represents owned DWARF data, intended to be used by python bindings
+/// Memoizes lookups of types by name for interactive use (e.g. a REPL), only
+/// the first query of each kind and name scans the DWARF, misses are cached
+/// too. It holds a borrow of the `Dwarf` or `OwnedDwarf` it queries
+pub struct TypeResolver<'d, D> {
+    dwarf: &'d D,
+    cache: HashMap<(gimli::DwTag, String), Option<Location>>,
+}
+
+impl<'d, D> TypeResolver<'d, D>
+where D: DwarfLookups + DwarfContext {
+    pub fn new(dwarf: &'d D) -> Self {
+        Self { dwarf, cache: HashMap::new() }
+    }
+
+    // Look up the first definition named `name` with the tag of `T`, going
+    // through the cache
+    fn by_name<T: Tagged>(&mut self, name: &str) -> Result<Option<T>, Error> {
+        let key = (T::tag(), name.to_string());
+        if let Some(location) = self.cache.get(&key) {
+            return Ok(location.map(T::new))
+        }
+        let found = self.dwarf.lookup_first_of(name, &[T::tag()])?;
+        let location = found.map(|typ| typ.location());
+        self.cache.insert(key, location);
+        Ok(location.map(T::new))
+    }
+
+    /// The struct named `name`, as found by `lookup_type`
+    pub fn struct_by_name(&mut self, name: &str)
+    -> Result<Option<Struct>, Error> {
+        self.by_name(name)
+    }
+
+    /// The union named `name`, as found by `lookup_type`
+    pub fn union_by_name(&mut self, name: &str)
+    -> Result<Option<Union>, Error> {
+        self.by_name(name)
+    }
+
+    /// The enum named `name`, as found by `lookup_type`
+    pub fn enum_by_name(&mut self, name: &str) -> Result<Option<Enum>, Error> {
+        self.by_name(name)
+    }
+
+    /// The typedef named `name`, as found by `lookup_type`
+    pub fn typedef_by_name(&mut self, name: &str)
+    -> Result<Option<Typedef>, Error> {
+        self.by_name(name)
+    }
+
+    /// The base type named `name` (e.g. `unsigned int`), as found by
+    /// `lookup_type`
+    pub fn base_by_name(&mut self, name: &str)
+    -> Result<Option<Base>, Error> {
+        self.by_name(name)
+    }
+
+    /// The number of cached lookups, hits and misses alike
+    pub fn cached(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// The `Dwarf` the resolver queries
+    pub fn dwarf(&self) -> &'d D {
+        self.dwarf
+    }
+}
+
+/// Represents owned DWARF data, the sections are copied out of the file so it
+/// can be moved between threads freely, it is `Send + Sync` so an
+/// `Arc<OwnedDwarf>` can be queried from multiple threads concurrently.
+/// Clones are cheap as the sections and type unit index are shared. Loading
+/// copies every section, `Dwarf` avoids this for uncompressed sections
+#[derive(Clone)]
 pub struct OwnedDwarf {
-    dwarf_vec: gimli::Dwarf<Vec<u8>>,
-    endianness: RunTimeEndian
+    dwarf_vec: Arc<gimli::Dwarf<Vec<u8>>>,
+    endianness: RunTimeEndian,
+    unknown_tag_policy: UnknownTagPolicy,
+    type_units: Arc<OnceLock<TypeUnitIndex>>,
+    section_sizes: Arc<HashMap<gimli::SectionId, usize>>,
+    sup_vec: Option<Arc<gimli::Dwarf<Vec<u8>>>>,
+    debug_altlink: Option<PathBuf>,
+    min_version: Option<u16>
 }
 
 impl<'a> OwnedDwarf {
+    /// Load the DWARF sections of an ELF, Mach-O (object file or dSYM) or
+    /// PE/COFF (e.g. MinGW built) file, sections are copied out of `data`
     pub fn load(data: impl ReadRef<'a>) -> Result<Self, Error> {
         let object = object::File::parse(data)?;
+        Self::from_object(&object)
+    }
 
+    /// Like `load`, but also collect the symbol table of the file without
+    /// parsing it again, see `SymbolTable`
+    pub fn load_with_symbols(data: impl ReadRef<'a>)
+    -> Result<(Self, SymbolTable), Error> {
+        let object = object::File::parse(data)?;
+        Ok((Self::from_object(&object)?, symbol_table(&object)))
+    }
+
+    // Load the DWARF sections of an already parsed file
+    fn from_object<R>(object: &object::File<'a, R>) -> Result<Self, Error>
+    where R: ReadRef<'a> {
         let endianness = if object.is_little_endian() {
             gimli::RunTimeEndian::Little
         } else {
             gimli::RunTimeEndian::Big
         };
 
+        let mut section_sizes = HashMap::new();
         let load_section = |id: gimli::SectionId|
         -> Result<Vec<u8>, gimli::Error> {
-            let data = match object.section_by_name(id.name()) {
-                Some(ref section) => {
-                    section.uncompressed_data()
-                           .unwrap_or_else(|_| Cow::Borrowed(&[][..]))
-                           .into_owned()
-                },
-                None =>Vec::new(),
-            };
+            let data = section_data(object, id).into_owned();
+            section_sizes.insert(id, data.len());
             Ok(data)
         };
 
         // Load all of the sections
-        let dwarf_vec = gimli::Dwarf::load(&load_section).unwrap();
+        let dwarf_vec = gimli::Dwarf::load(load_section).unwrap();
+
+        let mut dwarf = Self{dwarf_vec: Arc::new(dwarf_vec), endianness,
+                         unknown_tag_policy: UnknownTagPolicy::default(),
+                         type_units: Arc::new(OnceLock::new()),
+                         section_sizes: Arc::new(section_sizes),
+                         sup_vec: None,
+                         debug_altlink: debug_altlink(object),
+                         min_version: None};
+        dwarf.borrow_dwarf(check_abbrev)?;
+        dwarf.min_version = dwarf.borrow_dwarf(min_unit_version);
+        Ok(dwarf)
+    }
+
+    /// Like `load`, but fail with `Error::DwarfLoadError` when the file has no
+    /// debug info rather than loading something where every lookup is empty
+    pub fn load_strict(data: impl ReadRef<'a>) -> Result<Self, Error> {
+        let dwarf = Self::load(data)?;
+        if !dwarf.has_debug_info() {
+            return Err(Error::DwarfLoadError("no debug info".to_string()))
+        }
+        Ok(dwarf)
+    }
+
+    /// Open, map and load the file at `path`, along with the dwz
+    /// supplementary file named by its `.gnu_debugaltlink` if it exists.
+    /// Since the sections are copied the file doesn't have to outlive the
+    /// returned value
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let file = match File::open(path.as_ref()) {
+            Ok(file) => file,
+            Err(e) => return Err(Error::DwarfLoadError(
+                format!("Failed to open {:?}, error: {}", path.as_ref(), e)
+            ))
+        };
+        let mmap = match unsafe { Mmap::map(&file) } {
+            Ok(mmap) => mmap,
+            Err(e) => return Err(Error::DwarfLoadError(
+                format!("Failed to map {:?}, error: {}", path.as_ref(), e)
+            ))
+        };
+        let mut dwarf = Self::load(&*mmap)?;
+
+        // the supplementary file is optional, without it only the types
+        // which were moved to it fail to resolve
+        if let Some(altlink) = dwarf.debug_altlink.clone() {
+            let altlink = match path.as_ref().parent() {
+                Some(dir) => dir.join(altlink),
+                None => altlink
+            };
+            if altlink.exists() {
+                dwarf.open_sup(altlink)?;
+            }
+        }
+        Ok(dwarf)
+    }
+
+    /// Open and load the file at `path`, see `from_path`
+    #[deprecated(note = "use from_path")]
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::from_path(path)
+    }
+
+    /// The path of the supplementary file named by `.gnu_debugaltlink`, as
+    /// written by dwz, it may be relative to the directory of the file.
+    /// `from_path` loads it when it exists
+    pub fn debug_altlink(&self) -> Option<&Path> {
+        self.debug_altlink.as_deref()
+    }
+
+    /// Load the supplementary file (see `debug_altlink`) which types and
+    /// strings of this file may be moved to, the types are resolved by
+    /// `get_type` just like those of this file. Clones made before this
+    /// don't see the supplementary file
+    pub fn load_sup<'b>(&mut self, data: impl ReadRef<'b>)
+    -> Result<(), Error> {
+        let object = object::File::parse(data)?;
+        let load_section = |id: gimli::SectionId|
+        -> Result<Vec<u8>, gimli::Error> {
+            Ok(section_data(&object, id).into_owned())
+        };
+        let sup_vec = gimli::Dwarf::load(load_section).unwrap();
+        self.sup_vec = Some(Arc::new(sup_vec));
+        Ok(())
+    }
 
-        Ok(Self{dwarf_vec, endianness})
+    /// Open and load the supplementary file at `path`, see `load_sup`
+    pub fn open_sup(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let file = match File::open(path.as_ref()) {
+            Ok(file) => file,
+            Err(e) => return Err(Error::DwarfLoadError(
+                format!("Failed to open {:?}, error: {}", path.as_ref(), e)
+            ))
+        };
+        let mmap = match unsafe { Mmap::map(&file) } {
+            Ok(mmap) => mmap,
+            Err(e) => return Err(Error::DwarfLoadError(
+                format!("Failed to map {:?}, error: {}", path.as_ref(), e)
+            ))
+        };
+        self.load_sup(&*mmap)
+    }
+
+    /// Load the DWARF sections of the `arch` slice of a universal (fat)
+    /// Mach-O binary
+    pub fn load_arch(data: impl ReadRef<'a>, arch: object::Architecture)
+    -> Result<Self, Error> {
+        Self::load(fat_arch_slice(data, arch)?)
+    }
+
+    /// Set how types with tags that aren't modeled by `Type` are handled
+    pub fn set_unknown_tag_policy(&mut self, policy: UnknownTagPolicy) {
+        self.unknown_tag_policy = policy;
+    }
+
+    /// The size of the loaded (decompressed) data of a debug section, 0 when
+    /// the section is missing or isn't one that is loaded (e.g. .debug_frame)
+    pub fn section_size(&self, id: gimli::SectionId) -> usize {
+        self.section_sizes.get(&id).copied().unwrap_or(0)
+    }
+
+    /// The byte order of the target the file was built for, values read
+    /// from its memory have to be interpreted with it
+    pub fn target_endianness(&self) -> RunTimeEndian {
+        self.endianness
     }
 }
 
@@ -245,9 +1731,26 @@ impl borrowable_dwarf::BorrowableDwarf for OwnedDwarf {
         -> gimli::EndianSlice<'b, gimli::RunTimeEndian> =
         &|section| gimli::EndianSlice::new(section, self.endianness);
 
-        let dwarf = self.dwarf_vec.borrow(borrow_section);
+        let mut dwarf = self.dwarf_vec.borrow(borrow_section);
+        if let Some(sup_vec) = &self.sup_vec {
+            dwarf.sup = Some(Arc::new(sup_vec.borrow(borrow_section)));
+        }
         f(&dwarf)
     }
+
+    fn unknown_tag_policy(&self) -> UnknownTagPolicy {
+        self.unknown_tag_policy
+    }
+
+    fn type_units(&self) -> &TypeUnitIndex {
+        self.type_units.get_or_init(|| {
+            self.borrow_dwarf(build_type_unit_index)
+        })
+    }
+
+    fn recorded_min_version(&self) -> Option<u16> {
+        self.min_version
+    }
 }
 
 impl borrowable_dwarf::BorrowableDwarf for Dwarf<'_> {
@@ -260,6 +1763,20 @@ impl borrowable_dwarf::BorrowableDwarf for Dwarf<'_> {
         let dwarf = self.dwarf_cow.borrow(borrow_section);
         f(&dwarf)
     }
+
+    fn unknown_tag_policy(&self) -> UnknownTagPolicy {
+        self.unknown_tag_policy
+    }
+
+    fn type_units(&self) -> &TypeUnitIndex {
+        self.type_units.get_or_init(|| {
+            self.borrow_dwarf(build_type_unit_index)
+        })
+    }
+
+    fn recorded_min_version(&self) -> Option<u16> {
+        self.min_version
+    }
 }
 
 /// General functions for getting a CU/DIE from either a Dwarf or CU object
@@ -295,15 +1812,7 @@ impl DwarfContext for Dwarf<'_> {
     fn unit_context<F,R>(&self, loc: &Location, f: F) -> Result<R, Error>
     where F: FnOnce(&CU) -> R {
         self.borrow_dwarf(|dwarf| {
-            let debug_info = dwarf.debug_info;
-            let unit_header = match debug_info.header_from_offset(loc.header) {
-                Ok(header) => header,
-                Err(e) => return Err(
-                    Error::CUError(
-                        format!("Failed to seek to UnitHeader, error: {}", e)
-                    ))
-            };
-            let unit = gimli::Unit::new(dwarf, unit_header).unwrap();
+            let unit = located_unit(dwarf, loc.header)?;
             Ok(f(&unit))
         })
     }
@@ -330,15 +1839,7 @@ impl DwarfContext for OwnedDwarf {
     fn unit_context<F,R>(&self, loc: &Location, f: F) -> Result<R, Error>
     where F: FnOnce(&CU) -> R {
         self.borrow_dwarf(|dwarf| {
-            let debug_info = dwarf.debug_info;
-            let unit_header = match debug_info.header_from_offset(loc.header) {
-                Ok(header) => header,
-                Err(e) => return Err(
-                    Error::CUError(
-                        format!("Failed to seek to UnitHeader, error: {}", e)
-                    ))
-            };
-            let unit = gimli::Unit::new(dwarf, unit_header).unwrap();
+            let unit = located_unit(dwarf, loc.header)?;
             Ok(f(&unit))
         })
     }
@@ -1,39 +1,188 @@
 //! Formatting methods for type information.
+use std::collections::HashSet;
+
 use crate::dwarf::borrowable_dwarf::BorrowableDwarf;
 use crate::unit_has_members::UnitHasMembers;
 use crate::unit_inner_type::UnitInnerType;
 use crate::unit_name_type::UnitNamedType;
-use crate::{Member, Error, Type, CU};
+use crate::{Member, Enumerator, Typedef, Subprogram, Variable, Error, Type, CU};
+use crate::Location;
 use crate::dwarf::DwarfContext;
+use crate::types::resolve_type;
+use crate::prelude::*;
+
+/// The indentation of each nesting level of a definition
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Indent {
+    /// A number of spaces per level
+    Spaces(usize),
+    /// A single tab per level
+    Tabs,
+}
+
+impl Default for Indent {
+    fn default() -> Self {
+        Indent::Spaces(4)
+    }
+}
+
+impl Indent {
+    // The indentation of `count` nested levels
+    pub(crate) fn levels(&self, count: usize) -> String {
+        match self {
+            Indent::Spaces(spaces) => " ".repeat(spaces * count),
+            Indent::Tabs => "\t".repeat(count),
+        }
+    }
+}
+
+/// Options controlling how types are rendered to C-style definitions
+#[derive(Clone, Debug)]
+pub struct FormatOptions {
+    /// Values above zero add '/* size | offset */' comments to members
+    pub verbosity: u8,
+
+    /// Leave out compiler generated members such as C++ vtable pointers
+    pub hide_artificial: bool,
+
+    /// Spell boolean base types as `bool` regardless of their name in the
+    /// DWARF (e.g. `_Bool`)
+    pub cpp_bool: bool,
+
+    /// The indentation of members and enumerators, four spaces by default
+    pub indent: Indent,
+
+    /// End definitions with their trailing `;`, turning this off gives just
+    /// the body for embedding in other declarations, true by default
+    pub terminate: bool,
+
+    /// Append the `aligned` attribute of structs, true by default, turning
+    /// this off gives plain C that ignores layout
+    pub emit_attributes: bool,
 
+    /// Along with `emit_attributes`, append `__attribute__((packed))` to
+    /// structs which `Struct::is_packed` infers to be packed so that headers
+    /// reproduce their layout, true by default
+    pub emit_packed: bool,
+
+    /// Spell named structs, unions and enums with a typedef aliasing them in
+    /// the same unit when there is one, e.g. `foo_t` for `struct foo`
+    pub prefer_typedef_names: bool,
+
+    /// In verbose output, mark the members of unions nested inside of other
+    /// types with a `/* union */` comment, as their offsets are shared
+    pub mark_union_members: bool,
+
+    /// End members with their documentation or vendor annotation as a
+    /// `// comment`, see `Member::annotation`
+    pub emit_annotations: bool,
+
+    /// How deeply nested types may be followed before giving up with an
+    /// `Error::RecursionLimitExceeded`, guarding against the cyclic types of
+    /// malformed binaries, 128 by default which fits in the 2 MiB stack of a
+    /// spawned thread
+    pub max_depth: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            verbosity: 0,
+            hide_artificial: false,
+            cpp_bool: false,
+            indent: Indent::default(),
+            terminate: true,
+            emit_attributes: true,
+            emit_packed: true,
+            prefer_typedef_names: false,
+            mark_union_members: false,
+            emit_annotations: false,
+            max_depth: 128,
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Options equivalent to the `verbosity` argument of `to_string_verbose`
+    pub fn with_verbosity(verbosity: u8) -> Self {
+        Self { verbosity, ..Default::default() }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn format_type<D>(dwarf: &D, unit: &CU, member_name: String, typ: Type,
-                      level: usize, tablevel: usize, verbosity: u8,
+                      level: usize, tablevel: usize, opts: &FormatOptions,
                       base_offset: usize)
 -> Result<String, Error>
 where D: DwarfContext + BorrowableDwarf {
+    format_type_at(dwarf, unit, member_name, typ, level, tablevel, opts,
+                   base_offset, 0)
+}
+
+// format_type with the number of types followed to get here, `level` is reset
+// when a pointer to an array is formatted so it can't bound the recursion on
+// its own, the members of nested anonymous types go through format_member and
+// start over at a greater `tablevel`
+#[allow(clippy::too_many_arguments)]
+fn format_type_at<D>(dwarf: &D, unit: &CU, member_name: String, typ: Type,
+                     level: usize, tablevel: usize, opts: &FormatOptions,
+                     base_offset: usize, depth: usize)
+-> Result<String, Error>
+where D: DwarfContext + BorrowableDwarf {
+    if depth + tablevel > opts.max_depth {
+        return Err(Error::RecursionLimitExceeded(typ.location()))
+    }
+
+    // types from a type unit have to be formatted in the context of that unit
+    if typ.location().header != unit.header.offset() {
+        return dwarf.unit_context(&typ.location(), |unit| {
+            format_type_at(dwarf, unit, member_name, typ, level, tablevel, opts,
+                           base_offset, depth+1)
+        })?
+    }
+
     let mut out = String::new();
     match typ {
         Type::Array(a) => {
-            let inner = a.u_get_type(unit)?;
-            let inner_fmt = format_type(dwarf, unit, "".to_string(), inner,
-                                        level+1, tablevel, verbosity,
-                                        base_offset)?;
-            out.push_str(&inner_fmt);
-            if !out.ends_with('*') {
-                out.push(' ');
-            }
-            if level == 0 {
-                out.push_str(&member_name);
-            }
-
             let bound = a.u_get_bound(unit)?;
             let bound_str = {
-                if bound == 0 {
+                if a.u_is_descriptor_based(unit)? {
+                    String::from("[/* descriptor */]")
+                } else if bound == 0 {
                     String::from("[]")
                 } else {
                     format!("[{bound}]")
                 }
             };
+
+            let inner = inner_type(dwarf, unit, &a);
+
+            // arrays of function pointers or pointers to arrays go inside of
+            // the pointer declarator
+            if let Ok(inner) = inner {
+                if is_parenthesized_pointer(dwarf, unit, &inner) {
+                    return format_type_at(dwarf, unit,
+                                          format!("{member_name}{bound_str}"),
+                                          inner, level, tablevel, opts,
+                                          base_offset, depth+1)
+                }
+            }
+
+            let inner_fmt = match inner {
+                Ok(inner) => format_type_at(dwarf, unit, "".to_string(), inner,
+                                            level+1, tablevel, opts,
+                                            base_offset, depth+1)?,
+                Err(Error::TypeAttributeNotFound) => "void".to_string(),
+                Err(Error::UnknownTagError(tag, _)) => unhandled_type(tag),
+                Err(e) => return Err(e)
+            };
+            out.push_str(&inner_fmt);
+            if !out.ends_with('*') {
+                out.push(' ');
+            }
+            if level == 0 {
+                out.push_str(&member_name);
+            }
             out.push_str(&bound_str);
             return Ok(out);
         }
@@ -51,13 +200,15 @@ where D: DwarfContext + BorrowableDwarf {
             let name = t.u_name(dwarf, unit);
             match name {
                 Ok(name) => {
+                    let spelling = {
+                        typedef_alias(dwarf, unit, opts, t.location)?
+                        .unwrap_or(format!("struct {name}"))
+                    };
                     if level == 0 {
-                        out.push_str(
-                            &format!("struct {name} {member_name}")
-                        );
+                        out.push_str(&format!("{spelling} {member_name}"));
                         return Ok(out);
                     }
-                    out.push_str(&format!("struct {name}"));
+                    out.push_str(&spelling);
                     return Ok(out);
                 }
                 Err(Error::NameAttributeNotFound) => {
@@ -66,14 +217,15 @@ where D: DwarfContext + BorrowableDwarf {
                     for memb in t.u_members(unit)?.into_iter() {
                         out.push_str(
                             &format_member(dwarf, unit, memb, tablevel+1,
-                                           verbosity, base_offset)?
+                                           opts, base_offset)?
                         );
                     }
 
-                    for _ in 0..=tablevel {
-                        out.push_str("    ");
-                    }
+                    out.push_str(&opts.indent.levels(tablevel+1));
                     out.push('}');
+                    if level == 0 && !member_name.is_empty() {
+                        out.push_str(&format!(" {member_name}"));
+                    }
                     return Ok(out);
                 }
                 Err(e) => return Err(e)
@@ -82,22 +234,32 @@ where D: DwarfContext + BorrowableDwarf {
         Type::Enum(t) => {
             match t.u_name(dwarf, unit) {
                 Ok(name) => {
+                    let spelling = {
+                        typedef_alias(dwarf, unit, opts, t.location)?
+                        .unwrap_or(format!("enum {name}"))
+                    };
                     if level == 0 {
-                        out.push_str(
-                            &format!("enum {name} {member_name}")
-                        );
+                        out.push_str(&format!("{spelling} {member_name}"));
                         return Ok(out)
                     }
-                    // TODO: print enum members
-                    out.push_str(&format!("enum {name}"));
+                    out.push_str(&spelling);
                 }
                 Err(Error::NameAttributeNotFound) => {
-                    if level == 0 {
-                        out.push_str(&format!("enum {member_name}"));
-                        return Ok(out)
+                    // anonymous enums have to be defined inline
+                    out.push_str("enum {\n");
+                    for enumerator in t.u_enumerators(unit)?.into_iter() {
+                        out.push_str(&opts.indent.levels(tablevel+2));
+                        out.push_str(
+                            &format_enumerator(dwarf, unit, enumerator)?
+                        );
+                    }
+
+                    out.push_str(&opts.indent.levels(tablevel+1));
+                    out.push('}');
+                    if level == 0 && !member_name.is_empty() {
+                        out.push_str(&format!(" {member_name}"));
                     }
-                    // TODO: print enum members
-                    out.push_str("enum");
+                    return Ok(out);
                 }
                 Err(e) => return Err(e)
             }
@@ -106,27 +268,30 @@ where D: DwarfContext + BorrowableDwarf {
             let name = u.u_name(dwarf, unit);
             match name {
                 Ok(name) => {
+                    let spelling = {
+                        typedef_alias(dwarf, unit, opts, u.location)?
+                        .unwrap_or(format!("union {name}"))
+                    };
                     if level == 0 {
-                        out.push_str(
-                            &format!("union {name} {member_name}")
-                        );
+                        out.push_str(&format!("{spelling} {member_name}"));
                         return Ok(out);
                     }
-                    out.push_str(&format!("union {name}"));
+                    out.push_str(&spelling);
                     return Ok(out);
                 }
                 Err(Error::NameAttributeNotFound) => {
                     out.push_str("union {\n");
                     for memb in u.u_members(unit)?.into_iter() {
                         out.push_str(
-                            &format_member(dwarf, unit, memb, tablevel+1,
-                                           verbosity, base_offset)?);
+                            &format_member_in(dwarf, unit, memb, tablevel+1,
+                                              opts, base_offset, true)?);
                     }
 
-                    for _ in 0..=tablevel {
-                        out.push_str("    ");
-                    }
+                    out.push_str(&opts.indent.levels(tablevel+1));
                     out.push('}');
+                    if level == 0 && !member_name.is_empty() {
+                        out.push_str(&format!(" {member_name}"));
+                    }
 
                     return Ok(out);
                 }
@@ -134,7 +299,11 @@ where D: DwarfContext + BorrowableDwarf {
             }
         },
         Type::Base(t) => {
-            let name = t.u_name(dwarf, unit)?;
+            let name = if opts.cpp_bool && t.u_is_bool(unit)? {
+                "bool".to_string()
+            } else {
+                t.u_name(dwarf, unit)?
+            };
             if level == 0 {
                 out.push_str(&format!("{name} {member_name}"));
                 return Ok(out);
@@ -146,54 +315,83 @@ where D: DwarfContext + BorrowableDwarf {
             // just return comma separated arg string
             let params = t.u_get_params(unit)?;
             for pidx in 0..params.len() {
-                let param = params[pidx].u_get_type(unit)?;
                 // recursively convert type to string
-                out.push_str(&format_type(dwarf, unit, "".to_string(),
-                                          param, level+1, tablevel, verbosity,
-                                          base_offset)?);
+                match inner_type(dwarf, unit, &params[pidx]) {
+                    Ok(param) => {
+                        out.push_str(&format_type_at(dwarf, unit,
+                                                     "".to_string(), param,
+                                                     level+1, tablevel, opts,
+                                                     base_offset, depth+1)?);
+                    },
+                    Err(Error::TypeAttributeNotFound) => out.push_str("void"),
+                    Err(Error::UnknownTagError(tag, _)) => {
+                        out.push_str(&unhandled_type(tag))
+                    },
+                    Err(e) => return Err(e)
+                }
                 if pidx != params.len()-1 {
                     out.push_str(", ");
                 }
             };
+
+            // a prototype without parameters is spelled `(void)`, `()` is an
+            // old style declaration which takes any arguments
+            let prototyped = unit.entry_context(&t.location, |entry| {
+                matches!(entry.attr_value(gimli::DW_AT_prototyped),
+                         Ok(Some(gimli::AttributeValue::Flag(true))))
+            })?;
+            if params.is_empty() && prototyped {
+                out.push_str("void");
+            }
         },
         Type::Pointer(p) => {
-            let inner = p.u_get_type(unit);
+            let inner = inner_type(dwarf, unit, &p);
 
             // pointers to subroutines must be handled differently
             if let Ok(Type::Subroutine(subp)) = inner {
 
-                let return_type = match subp.u_get_type(unit) {
-                    Ok(rtype) => format_type(dwarf, unit, "".to_string(), rtype,
-                                             level+1, tablevel, verbosity,
-                                             base_offset)?,
+                let return_type = match inner_type(dwarf, unit, &subp) {
+                    Ok(rtype) => format_type_at(dwarf, unit, "".to_string(),
+                                                rtype, level+1, tablevel, opts,
+                                                base_offset, depth+1)?,
                     Err(Error::TypeAttributeNotFound) => "void".to_string(),
+                    Err(Error::UnknownTagError(tag, _)) => unhandled_type(tag),
                     Err(e) => return Err(e)
                 };
 
                 let argstr = {
-                    format_type(dwarf, unit, "".to_string(),
-                                Type::Subroutine(subp),
-                                level+1, tablevel, verbosity,
-                                base_offset)?
+                    format_type_at(dwarf, unit, "".to_string(),
+                                   Type::Subroutine(subp),
+                                   level+1, tablevel, opts,
+                                   base_offset, depth+1)?
                 };
 
                 out.push_str(
-                    &format!("{return_type} (*{member_name})({argstr})")
+                    &format!("{}(*{member_name})({argstr})",
+                             declarator_prefix(&return_type))
                 );
                 return Ok(out);
             }
 
+            // FORMAT: {type} (*{member_name})[{bound}]
+            if let Ok(Type::Array(arr)) = inner {
+                return format_type_at(dwarf, unit, format!("(*{member_name})"),
+                                      Type::Array(arr), 0, tablevel, opts,
+                                      base_offset, depth+1);
+            }
+
             // FORMAT: {type} *{member_name}
 
             let ptr_type = match inner {
                 Ok(inner) => {
-                    format_type(dwarf, unit, "".to_string(), inner,
-                                level+1, tablevel, verbosity,
-                                base_offset)?
+                    format_type_at(dwarf, unit, "".to_string(), inner,
+                                   level+1, tablevel, opts,
+                                   base_offset, depth+1)?
                 },
                 Err(Error::TypeAttributeNotFound) => {
                     "void".to_string()
                 },
+                Err(Error::UnknownTagError(tag, _)) => unhandled_type(tag),
                 Err(e) => return Err(e)
             };
             out.push_str(&ptr_type);
@@ -211,33 +409,63 @@ where D: DwarfContext + BorrowableDwarf {
             return Ok(out);
         },
         Type::Const(c) => {
-            let inner = c.u_get_type(unit);
+            let inner = inner_type(dwarf, unit, &c);
+
+            // FORMAT: {return type} (*const {member_name})({args})
+            if let Ok(inner) = inner {
+                if is_parenthesized_pointer(dwarf, unit, &inner) {
+                    return format_type_at(dwarf, unit,
+                                          format!("const {member_name}"), inner,
+                                          level, tablevel, opts, base_offset,
+                                          depth+1)
+                }
+            }
+
             match inner {
                 Ok(inner) => {
-                    let inner_fmt = format_type(dwarf, unit, "".to_string(),
-                                                inner, level+1, tablevel,
-                                                verbosity, base_offset)?;
-                    out.push_str(&format!("const {inner_fmt}"));
+                    let inner_fmt = format_type_at(dwarf, unit, "".to_string(),
+                                                   inner, level+1, tablevel,
+                                                   opts, base_offset, depth+1)?;
+                    // a const pointer is written as `{type} *const`
+                    if inner_fmt.ends_with('*') {
+                        out.push_str(&format!("{inner_fmt}const"));
+                    } else {
+                        out.push_str(&format!("const {inner_fmt}"));
+                    }
                 }
                 Err(Error::TypeAttributeNotFound) => {
                     out.push_str("const void");
                 }
+                Err(Error::UnknownTagError(tag, _)) => {
+                    out.push_str(&format!("const {}", unhandled_type(tag)));
+                }
                 Err(e) => return Err(e)
             }
+            if level == 0 {
+                out.push_str(&format!(" {member_name}"));
+            }
         },
         Type::Volatile(c) => {
-            let inner = c.u_get_type(unit)?;
-            let inner_fmt = format_type(dwarf, unit, "".to_string(), inner,
-                                        level+1, tablevel, verbosity,
-                                        base_offset)?;
+            let inner_fmt = match inner_type(dwarf, unit, &c) {
+                Ok(inner) => format_type_at(dwarf, unit, "".to_string(), inner,
+                                            level+1, tablevel, opts,
+                                            base_offset, depth+1)?,
+                Err(Error::TypeAttributeNotFound) => "void".to_string(),
+                Err(Error::UnknownTagError(tag, _)) => unhandled_type(tag),
+                Err(e) => return Err(e)
+            };
             out.push_str(&format!("volatile {inner_fmt}"));
             return Ok(out);
         },
         Type::Restrict(c) => {
-            let inner = c.u_get_type(unit)?;
-            let inner_fmt = format_type(dwarf, unit, "".to_string(), inner,
-                                        level+1, tablevel, verbosity,
-                                        base_offset)?;
+            let inner_fmt = match inner_type(dwarf, unit, &c) {
+                Ok(inner) => format_type_at(dwarf, unit, "".to_string(), inner,
+                                            level+1, tablevel, opts,
+                                            base_offset, depth+1)?,
+                Err(Error::TypeAttributeNotFound) => "void".to_string(),
+                Err(Error::UnknownTagError(tag, _)) => unhandled_type(tag),
+                Err(e) => return Err(e)
+            };
             out.push_str(&format!("{inner_fmt} restrict"));
             return Ok(out);
         }
@@ -245,11 +473,55 @@ where D: DwarfContext + BorrowableDwarf {
     Ok(out)
 }
 
+// The spelling of a type followed by a declarator, a space separates them
+// unless the type ends with a pointer, e.g. `int ` but `char *`
+fn declarator_prefix(typ: &str) -> String {
+    match typ.ends_with('*') {
+        true => typ.to_string(),
+        false => format!("{typ} ")
+    }
+}
+
+// The C spelling of a type without a declarator name, e.g. `struct foo *`
+pub(crate) fn format_type_name<D>(dwarf: &D, unit: &CU, typ: Type)
+-> Result<String, Error>
+where D: DwarfContext + BorrowableDwarf {
+    let opts = FormatOptions::default();
+    let formatted = format_type(dwarf, unit, "".to_string(), typ, 0, 0, &opts,
+                                0)?;
+    Ok(formatted.trim_end().to_string())
+}
+
 pub fn format_member<D>(dwarf: &D, unit: &CU, member: Member, tablevel: usize,
-                        verbosity: u8, base_offset: usize)
+                        opts: &FormatOptions, base_offset: usize)
 -> Result<String, Error>
 where D: DwarfContext + BorrowableDwarf {
-    let mtype = member.u_get_type(unit)?;
+    format_member_in(dwarf, unit, member, tablevel, opts, base_offset, false)
+}
+
+// Format a member, `in_union` is set for the members of a nested union which
+// get the `mark_union_members` comment
+fn format_member_in<D>(dwarf: &D, unit: &CU, member: Member, tablevel: usize,
+                       opts: &FormatOptions, base_offset: usize,
+                       in_union: bool)
+-> Result<String, Error>
+where D: DwarfContext + BorrowableDwarf {
+    if opts.hide_artificial && member.u_is_artificial(unit)? {
+        return Ok(String::new());
+    }
+
+    // the type is None when it is void, was skipped by the unknown tag policy
+    // or has a tag that isn't modeled, none of these have a size
+    let mut placeholder = "void".to_string();
+    let mtype = match inner_type(dwarf, unit, &member) {
+        Ok(mtype) => Some(mtype),
+        Err(Error::TypeAttributeNotFound) => None,
+        Err(Error::UnknownTagError(tag, _)) => {
+            placeholder = unhandled_type(tag);
+            None
+        },
+        Err(e) => return Err(e)
+    };
     let name = match member.u_name(dwarf, unit) {
         Ok(name) => name,
         Err(Error::NameAttributeNotFound) => {
@@ -263,9 +535,7 @@ where D: DwarfContext + BorrowableDwarf {
     };
 
     let mut formatted = String::new();
-    for _ in 0..=tablevel {
-        formatted.push_str("    ");
-    }
+    formatted.push_str(&opts.indent.levels(tablevel+1));
 
     let memb_offset = match member.u_offset(unit) {
         Ok(memb_offset) => memb_offset,
@@ -275,9 +545,16 @@ where D: DwarfContext + BorrowableDwarf {
     };
     let offset = base_offset + memb_offset;
 
-    formatted.push_str(
-        &format_type(dwarf, unit, name, mtype, 0, tablevel, verbosity, offset)?
-    );
+    if member.u_is_mutable(unit)? {
+        formatted.push_str("mutable ");
+    }
+
+    match mtype {
+        Some(mtype) => formatted.push_str(
+            &format_type(dwarf, unit, name, mtype, 0, tablevel, opts, offset)?
+        ),
+        None => formatted.push_str(&format!("{placeholder} {name}"))
+    }
 
     match member.u_bit_size(unit) {
         Ok(bitsz) => {
@@ -289,7 +566,7 @@ where D: DwarfContext + BorrowableDwarf {
 
     formatted.push(';');
 
-    if verbosity > 0 {
+    if opts.verbosity > 0 {
         // generic padding based on last newline in formatted string
         let last_newline = formatted.rfind('\n').map(|idx| idx+1).unwrap_or(0);
 
@@ -299,12 +576,468 @@ where D: DwarfContext + BorrowableDwarf {
             formatted.push(' ');
         }
 
-        let bytesz = member.u_byte_size(unit)?;
+        let bytesz = match mtype {
+            Some(_) => match member.u_byte_size(dwarf, unit) {
+                Ok(bytesz) => bytesz.to_string(),
+                Err(Error::NonContiguousArrayError(_)) => "?".to_string(),
+                Err(e) => return Err(e)
+            },
+            None => "?".to_string()
+        };
         formatted.push_str(&format!("\t/* {bytesz: >4} | \
                                           {offset: >4} */"));
+        if in_union && opts.mark_union_members {
+            formatted.push_str(" /* union */");
+        }
+    }
+
+    if opts.emit_annotations {
+        if let Some(annotation) = member.u_annotation(dwarf, unit)? {
+            let annotation = annotation.replace('\n', " ");
+            formatted.push_str(&format!(" // {annotation}"));
+        }
     }
 
     formatted.push('\n');
 
     Ok(formatted)
 }
+
+// The C spelling of the inner type of e.g. a member, a missing type is void
+pub(crate) fn format_inner_type_name<D, T>(dwarf: &D, unit: &CU, typ: &T)
+-> Result<String, Error>
+where D: DwarfContext + BorrowableDwarf, T: UnitInnerType {
+    match inner_type(dwarf, unit, typ) {
+        Ok(inner) => format_type_name(dwarf, unit, inner),
+        Err(Error::TypeAttributeNotFound) => Ok("void".to_string()),
+        Err(Error::UnknownTagError(tag, _)) => Ok(unhandled_type(tag)),
+        Err(e) => Err(e)
+    }
+}
+
+// The name of a typedef in `unit` which directly aliases the type at
+// `location` when `opts` asks for typedef names, the first one declared wins
+fn typedef_alias<D>(dwarf: &D, unit: &CU, opts: &FormatOptions,
+                    location: Location)
+-> Result<Option<String>, Error>
+where D: DwarfContext + BorrowableDwarf {
+    if !opts.prefer_typedef_names {
+        return Ok(None)
+    }
+    let mut entries = unit.entries();
+    while let Ok(Some((_, entry))) = entries.next_dfs() {
+        if entry.tag() != gimli::DW_TAG_typedef {
+            continue;
+        }
+        let aliased = match entry.attr_value(gimli::DW_AT_type) {
+            Ok(Some(gimli::AttributeValue::UnitRef(offset))) => offset,
+            _ => continue
+        };
+        if aliased == location.offset {
+            let typedef = Typedef {
+                location: Location { header: location.header,
+                                     offset: entry.offset() }
+            };
+            return Ok(Some(typedef.u_name(dwarf, unit)?))
+        }
+    }
+    Ok(None)
+}
+
+// A visible placeholder for a type with a tag that isn't modeled by `Type`, so
+// that declarations using it aren't silently dropped from the output
+fn unhandled_type(tag: gimli::DwTag) -> String {
+    format!("/* unhandled: {tag} */")
+}
+
+// Whether `typ` is a pointer directly to a subroutine or an array, the
+// declarator of these has to be placed inside of the pointer syntax
+fn is_parenthesized_pointer<D>(dwarf: &D, unit: &CU, typ: &Type) -> bool
+where D: DwarfContext + BorrowableDwarf {
+    match typ {
+        Type::Pointer(p) => {
+            matches!(inner_type(dwarf, unit, p),
+                     Ok(Type::Subroutine(_)) | Ok(Type::Array(_)))
+        },
+        _ => false
+    }
+}
+
+// Get the inner type of `typ`, following references into type units, types
+// with tags that aren't modeled by `Type` are resolved according to the
+// unknown tag policy of `dwarf`
+fn inner_type<D, T>(dwarf: &D, unit: &CU, typ: &T) -> Result<Type, Error>
+where D: DwarfContext + BorrowableDwarf, T: UnitInnerType {
+    resolve_type(dwarf, typ.u_get_type(unit))
+}
+
+// The declaration of a method, e.g. `int area(int)`, the artificial `this`
+// parameter is left out
+pub(crate) fn format_method_signature<D>(dwarf: &D, unit: &CU,
+                                         method: Subprogram)
+-> Result<String, Error>
+where D: DwarfContext + BorrowableDwarf {
+    let opts = FormatOptions::default();
+    let format_inner = |typ: Result<Type, Error>| match typ {
+        Ok(typ) => format_type(dwarf, unit, "".to_string(), typ, 1, 0, &opts,
+                               0),
+        Err(Error::TypeAttributeNotFound) => Ok("void".to_string()),
+        Err(Error::UnknownTagError(tag, _)) => Ok(unhandled_type(tag)),
+        Err(e) => Err(e)
+    };
+
+    let return_type = format_inner(inner_type(dwarf, unit, &method))?;
+    let name = method.u_name(dwarf, unit)?;
+
+    let mut params: Vec<String> = vec![];
+    for param in method.u_get_params(unit)? {
+        if param.u_is_artificial(unit)? {
+            continue;
+        }
+        params.push(format_inner(inner_type(dwarf, unit, &param))?);
+    }
+    Ok(format!("{}{name}({})", declarator_prefix(&return_type),
+               params.join(", ")))
+}
+
+// Format the declaration of a function with its parameter names, parameters
+// are formatted like members so an array parameter keeps its bound, e.g.
+// `int sum(int vals[4], ...)`
+pub(crate) fn format_prototype<D>(dwarf: &D, unit: &CU, func: Subprogram)
+-> Result<String, Error>
+where D: DwarfContext + BorrowableDwarf {
+    let opts = FormatOptions::default();
+    let return_type = match inner_type(dwarf, unit, &func) {
+        Ok(rtype) => format_type(dwarf, unit, "".to_string(), rtype, 1, 0,
+                                 &opts, 0)?,
+        Err(Error::TypeAttributeNotFound) => "void".to_string(),
+        Err(Error::UnknownTagError(tag, _)) => unhandled_type(tag),
+        Err(e) => return Err(e)
+    };
+    let name = func.u_name(dwarf, unit)?;
+
+    let mut params: Vec<String> = vec![];
+    for param in func.u_get_params(unit)? {
+        if param.u_is_artificial(unit)? {
+            continue;
+        }
+        let pname = match param.u_name(dwarf, unit) {
+            Ok(pname) => pname,
+            Err(Error::NameAttributeNotFound) => "".to_string(),
+            Err(e) => return Err(e)
+        };
+        let decl = match inner_type(dwarf, unit, &param) {
+            Ok(ptype) => format_type(dwarf, unit, pname, ptype, 0, 0, &opts,
+                                     0)?,
+            Err(Error::TypeAttributeNotFound) => format!("void {pname}"),
+            Err(Error::UnknownTagError(tag, _)) => {
+                format!("{} {pname}", unhandled_type(tag))
+            },
+            Err(e) => return Err(e)
+        };
+        params.push(decl.trim_end().to_string());
+    }
+    if func.u_is_variadic(unit)? {
+        params.push("...".to_string());
+    }
+    if params.is_empty() {
+        params.push("void".to_string());
+    }
+    Ok(format!("{}{name}({})", declarator_prefix(&return_type),
+               params.join(", ")))
+}
+
+// Format a variable as a C declaration, variables which are only declared in
+// the unit (DW_AT_declaration) are marked extern
+pub(crate) fn format_variable<D>(dwarf: &D, unit: &CU, var: Variable)
+-> Result<String, Error>
+where D: DwarfContext + BorrowableDwarf {
+    let opts = FormatOptions::default();
+    let name = var.u_name(dwarf, unit)?;
+    let decl = match inner_type(dwarf, unit, &var) {
+        Ok(vtype) => format_type(dwarf, unit, name, vtype, 0, 0, &opts, 0)?,
+        Err(Error::TypeAttributeNotFound) => format!("void {name}"),
+        Err(Error::UnknownTagError(tag, _)) => {
+            format!("{} {name}", unhandled_type(tag))
+        },
+        Err(e) => return Err(e)
+    };
+
+    let is_declaration = unit.entry_context(&var.location, |entry| {
+        matches!(entry.attr_value(gimli::DW_AT_declaration),
+                 Ok(Some(gimli::AttributeValue::Flag(true))))
+    })?;
+    match is_declaration {
+        true => Ok(format!("extern {decl};")),
+        false => Ok(format!("{decl};"))
+    }
+}
+
+pub fn format_enumerator<D>(dwarf: &D, unit: &CU, enumerator: Enumerator)
+-> Result<String, Error>
+where D: DwarfContext + BorrowableDwarf {
+    let name = enumerator.u_name(dwarf, unit)?;
+    let value = enumerator.u_value(unit)?;
+    Ok(format!("{name} = {value},\n"))
+}
+
+// Format a typedef as a declaration, the name has to be placed inside the
+// declarator for function pointers and arrays so format_type handles those
+pub(crate) fn format_typedef<D>(dwarf: &D, typedef: Typedef,
+                                opts: &FormatOptions)
+-> Result<String, Error>
+where D: DwarfContext + BorrowableDwarf {
+    let name = typedef.name(dwarf)?;
+
+    // the aliased type can't be spelled with the typedef being defined
+    let opts = &FormatOptions { prefer_typedef_names: false, ..opts.clone() };
+
+    // strip the trailing semicolon of an aggregate definition
+    let body = |def: String| def.trim_end_matches(';').to_string();
+
+    let decl = match typedef.get_type(dwarf) {
+        Ok(Type::Struct(s)) if s.name(dwarf).is_err() => {
+            format!("{} {name}", body(s.to_string_opts(dwarf, opts)?))
+        },
+        Ok(Type::Union(u)) if u.name(dwarf).is_err() => {
+            format!("{} {name}", body(u.to_string_opts(dwarf, opts)?))
+        },
+        Ok(Type::Enum(e)) if e.name(dwarf).is_err() => {
+            format!("{} {name}", body(e.to_string_opts(dwarf, opts)?))
+        },
+        Ok(Type::Subroutine(subp)) => {
+            dwarf.unit_context(&typedef.location, |unit| {
+                let return_type = match inner_type(dwarf, unit, &subp) {
+                    Ok(rtype) => format_type(dwarf, unit, "".to_string(),
+                                             rtype, 1, 0, opts, 0)?,
+                    Err(Error::TypeAttributeNotFound) => "void".to_string(),
+                    Err(Error::UnknownTagError(tag, _)) => unhandled_type(tag),
+                    Err(e) => return Err(e)
+                };
+                let argstr = format_type(dwarf, unit, "".to_string(),
+                                         Type::Subroutine(subp), 1, 0, opts,
+                                         0)?;
+                Ok(format!("{}{name}({argstr})",
+                           declarator_prefix(&return_type)))
+            })??
+        },
+        Ok(inner) => {
+            dwarf.unit_context(&typedef.location, |unit| {
+                format_type(dwarf, unit, name.clone(), inner, 0, 0, opts, 0)
+            })??
+        },
+        Err(Error::TypeAttributeNotFound) => format!("void {name}"),
+        Err(Error::UnknownTagError(tag, _)) => {
+            format!("{} {name}", unhandled_type(tag))
+        },
+        Err(e) => return Err(e)
+    };
+    match opts.terminate {
+        true => Ok(format!("typedef {decl};")),
+        false => Ok(format!("typedef {decl}"))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum HeaderKind {
+    Struct,
+    Union,
+    Enum,
+    Typedef,
+}
+
+// Tracks the state of a header being built, definitions are keyed by kind and
+// name so that types defined in multiple CUs are only emitted once
+struct HeaderBuilder<'a, D> {
+    dwarf: &'a D,
+    opts: &'a FormatOptions,
+    visiting: HashSet<(HeaderKind, String)>,
+    emitted: HashSet<(HeaderKind, String)>,
+    declared: HashSet<(HeaderKind, String)>,
+    declarations: Vec<String>,
+    definitions: Vec<String>,
+}
+
+impl<'a, D> HeaderBuilder<'a, D>
+where D: DwarfContext + BorrowableDwarf {
+    fn new(dwarf: &'a D, opts: &'a FormatOptions) -> Self {
+        Self {
+            dwarf,
+            opts,
+            visiting: HashSet::new(),
+            emitted: HashSet::new(),
+            declared: HashSet::new(),
+            declarations: Vec::new(),
+            definitions: Vec::new(),
+        }
+    }
+
+    fn declare(&mut self, kind: HeaderKind, name: &str) {
+        if !self.declared.insert((kind, name.to_string())) {
+            return
+        }
+        match kind {
+            HeaderKind::Struct => {
+                self.declarations.push(format!("struct {name};"))
+            },
+            HeaderKind::Union => {
+                self.declarations.push(format!("union {name};"))
+            },
+            // enums and typedefs can't be forward declared
+            _ => {}
+        }
+    }
+
+    // Pull in whatever `typ` depends on, a type used by value needs a
+    // complete definition while a type behind a pointer only needs a forward
+    // declaration
+    fn require(&mut self, typ: Type, by_value: bool) -> Result<(), Error> {
+        let dwarf = self.dwarf;
+        match typ {
+            Type::Struct(s) => match s.name(dwarf) {
+                Ok(name) if by_value => {
+                    self.define(HeaderKind::Struct, name, typ)
+                },
+                Ok(name) => {
+                    self.declare(HeaderKind::Struct, &name);
+                    Ok(())
+                },
+                // anonymous structs are defined inline
+                Err(Error::NameAttributeNotFound) => {
+                    for memb in s.members(dwarf)? {
+                        self.require_inner(memb.get_type(dwarf), true)?;
+                    }
+                    Ok(())
+                },
+                Err(e) => Err(e)
+            },
+            Type::Union(u) => match u.name(dwarf) {
+                Ok(name) if by_value => {
+                    self.define(HeaderKind::Union, name, typ)
+                },
+                Ok(name) => {
+                    self.declare(HeaderKind::Union, &name);
+                    Ok(())
+                },
+                Err(Error::NameAttributeNotFound) => {
+                    for memb in u.members(dwarf)? {
+                        self.require_inner(memb.get_type(dwarf), true)?;
+                    }
+                    Ok(())
+                },
+                Err(e) => Err(e)
+            },
+            Type::Enum(e) => match e.name(dwarf) {
+                Ok(name) => self.define(HeaderKind::Enum, name, typ),
+                Err(Error::NameAttributeNotFound) => Ok(()),
+                Err(e) => Err(e)
+            },
+            Type::Typedef(t) => {
+                let name = t.name(dwarf)?;
+                self.define(HeaderKind::Typedef, name, typ)?;
+                if by_value {
+                    self.require_inner(t.get_type(dwarf), true)?;
+                }
+                Ok(())
+            },
+            Type::Pointer(p) => self.require_inner(p.get_type(dwarf), false),
+            // arrays of incomplete types are never valid
+            Type::Array(a) => self.require_inner(a.get_type(dwarf), true),
+            Type::Const(c) => self.require_inner(c.get_type(dwarf), by_value),
+            Type::Volatile(v) => {
+                self.require_inner(v.get_type(dwarf), by_value)
+            },
+            Type::Restrict(r) => {
+                self.require_inner(r.get_type(dwarf), by_value)
+            },
+            Type::Subroutine(subp) => {
+                self.require_inner(subp.get_type(dwarf), false)?;
+                for param in subp.get_params(dwarf)? {
+                    self.require_inner(param.get_type(dwarf), false)?;
+                }
+                Ok(())
+            },
+            Type::Base(_) => Ok(())
+        }
+    }
+
+    fn require_inner(&mut self, typ: Result<Type, Error>, by_value: bool)
+    -> Result<(), Error> {
+        match typ {
+            Ok(typ) => self.require(typ, by_value),
+            // void
+            Err(Error::TypeAttributeNotFound) => Ok(()),
+            // rendered as a placeholder, there is nothing to define
+            Err(Error::UnknownTagError(..)) => Ok(()),
+            Err(e) => Err(e)
+        }
+    }
+
+    fn define(&mut self, kind: HeaderKind, name: String, typ: Type)
+    -> Result<(), Error> {
+        let key = (kind, name);
+        if self.emitted.contains(&key) || self.visiting.contains(&key) {
+            return Ok(())
+        }
+        self.declare(kind, &key.1);
+        self.visiting.insert(key.clone());
+
+        let dwarf = self.dwarf;
+        let definition = match typ {
+            Type::Struct(s) => {
+                for memb in s.members(dwarf)? {
+                    self.require_inner(memb.get_type(dwarf), true)?;
+                }
+                s.to_string_opts(dwarf, self.opts)?
+            },
+            Type::Union(u) => {
+                for memb in u.members(dwarf)? {
+                    self.require_inner(memb.get_type(dwarf), true)?;
+                }
+                u.to_string_opts(dwarf, self.opts)?
+            },
+            Type::Enum(e) => e.to_string_opts(dwarf, self.opts)?,
+            Type::Typedef(t) => {
+                self.require_inner(t.get_type(dwarf), false)?;
+                format_typedef(dwarf, t, self.opts)?
+            },
+            _ => unreachable!("only aggregates and typedefs are defined")
+        };
+
+        self.visiting.remove(&key);
+        self.emitted.insert(key);
+        self.definitions.push(definition);
+        Ok(())
+    }
+
+    fn finish(self) -> String {
+        let mut out = String::new();
+        for decl in self.declarations.iter() {
+            out.push_str(decl);
+            out.push('\n');
+        }
+        for definition in self.definitions.iter() {
+            out.push('\n');
+            out.push_str(definition);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Render `types` as a C header, types they depend on are pulled in and
+/// emitted first, structs and unions are forward declared so that cyclic
+/// references through pointers resolve
+pub fn emit_c_header<D>(dwarf: &D, types: &[Type], opts: &FormatOptions)
+-> Result<String, Error>
+where D: DwarfContext + BorrowableDwarf {
+    // a header is only valid C with every definition terminated, typedefs
+    // are only emitted on demand so they can't replace tag names
+    let opts = FormatOptions { terminate: true, prefer_typedef_names: false,
+                               ..opts.clone() };
+    let mut builder = HeaderBuilder::new(dwarf, &opts);
+    for typ in types.iter() {
+        builder.require(*typ, true)?;
+    }
+    Ok(builder.finish())
+}
@@ -14,6 +14,11 @@ pub mod types;
 pub mod dwarf;
 
 pub use dwarf::Dwarf;
+pub use dwarf::OwnedDwarf;
+pub use dwarf::TypeResolver;
+pub use dwarf::SymbolTable;
+pub use format::FormatOptions;
+pub use format::Indent;
 pub use types::*;
 
 #[cfg(feature = "python")]
@@ -24,6 +29,7 @@ pub mod prelude {
     pub use crate::types::NamedType;
     pub use crate::types::InnerType;
     pub use crate::types::HasMembers;
+    pub use crate::types::AnyType;
     pub use crate::dwarf::DwarfContext;
     pub use crate::dwarf::DwarfLookups;
 }
@@ -50,6 +56,32 @@ pub enum Error {
     #[error("failed due to unimplemented functionality")]
     UnimplementedError(String),
 
+    #[error("encountered a type with an unhandled tag: {0}")]
+    UnknownTagError(gimli::DwTag, types::Location),
+
+    #[error("the elements of an array have an unknown or zero size")]
+    UnknownElementSizeError(types::Location),
+
+    #[error("an array is accessed through a descriptor and is not \
+             contiguous")]
+    NonContiguousArrayError(types::Location),
+
+    #[error("element {1} of a bit-packed array does not start on a byte")]
+    UnalignedElementError(types::Location, usize),
+
+    #[error("DWARF version {0} is older than the supported versions 4 and 5, \
+             results may be incomplete, rebuild with -gdwarf-4 or newer")]
+    UnsupportedVersion(u16),
+
+    #[error("types are nested too deeply to be formatted")]
+    RecursionLimitExceeded(types::Location),
+
+    #[error("no type unit has the type signature {0:?}")]
+    TypeSignatureNotFound(gimli::DebugTypeSignature),
+
+    #[error("compile units have different address sizes, {0} and {1} bytes")]
+    MixedAddressSizes(u8, u8),
+
     // Non-Fatal
     #[error("failure when attempting to find a Name Attribute")]
     NameAttributeNotFound,
@@ -57,15 +89,55 @@ pub enum Error {
     #[error("failure when attempting to find a Type Attribute")]
     TypeAttributeNotFound,
 
+    #[error("failure when attempting to find a LinkageName Attribute")]
+    LinkageNameAttributeNotFound,
+
     #[error("failure when attempting to find a ByteSize Attribute")]
     ByteSizeAttributeNotFound,
 
     #[error("failure when attempting to find a BitSize Attribute")]
     BitSizeAttributeNotFound,
 
+    #[error("failure when attempting to find an Encoding Attribute")]
+    EncodingAttributeNotFound,
+
     #[error("failure when attempting to find a MemberLocation Attribute")]
     MemberLocationAttributeNotFound,
 
     #[error("failure when attempting to find an Alignment Attribute")]
     AlignmentAttributeNotFound,
+
+    #[error("failure when attempting to find a ConstValue Attribute")]
+    ConstValueAttributeNotFound,
+
+    #[error("failure when attempting to find a CompDir Attribute")]
+    CompDirAttributeNotFound,
+
+    #[error("failure when attempting to find a Producer Attribute")]
+    ProducerAttributeNotFound,
+
+    #[error("failure when attempting to find a DeclFile Attribute")]
+    DeclFileAttributeNotFound,
+
+    #[error("failure when attempting to find a DeclLine Attribute")]
+    DeclLineAttributeNotFound,
+
+    #[error("failure when attempting to find a member named {0}")]
+    MemberNotFound(String),
+
+    #[error("member {0} is at offset {2}, expected {1}")]
+    LayoutMismatch(String, usize, usize),
+
+    #[error("a value of {1} bytes is too short for a type of {0} bytes")]
+    ValueTooShort(usize, usize),
+
+    #[error("failure when attempting to find a type named {0}")]
+    TypeNotFound(String),
+
+    #[error("type is defined in the type unit with signature {0:?}")]
+    TypeUnitReference(gimli::DebugTypeSignature),
+
+    #[error("type is defined at {0:?} in a supplementary file which isn't \
+             loaded")]
+    SupplementaryReference(gimli::DebugInfoOffset),
 }
@@ -1,9 +1,10 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 use dwat::prelude::*;
 use memmap2::Mmap;
 use std::fs::File;
 use dwat::Dwarf;
+use dwat::dwarf::ScanProgress;
 
 #[derive(Parser)]
 struct CmdArgs {
@@ -11,6 +12,14 @@ struct CmdArgs {
     commands: Commands
 }
 
+/// The kinds of types that can be dumped
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum Kind {
+    #[default]
+    Struct,
+    Enum,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Find and display a single struct
@@ -28,7 +37,7 @@ enum Commands {
                                      fields.")]
         verbose: bool,
     },
-    /// Find and display all structs
+    /// Find and display all structs or enums
     Dump {
         /// Path to the DWARF file
         #[clap(help = "The path to the file containing DWARF info.")]
@@ -43,7 +52,16 @@ enum Commands {
         #[clap(long, action, help = "Find unique structs by name only, faster \
                                      but misses cases where multiple structs \
                                      are declared with the same name")]
-        fast: bool
+        fast: bool,
+
+        /// Report how many compile units have been scanned on stderr
+        #[clap(long, action, help = "Prints scan progress to stderr.")]
+        progress: bool,
+
+        /// The kind of types to dump
+        #[clap(long, value_enum, default_value_t, help = "The kind of types \
+                                                          to dump.")]
+        kind: Kind
     },
 }
 
@@ -56,6 +74,9 @@ fn main() -> anyhow::Result<()> {
             let mmap = &*unsafe { Mmap::map(&file) }?;
 
             let dwarf = Dwarf::load(mmap)?;
+            if let Err(e) = dwarf.check_version() {
+                eprintln!("warning: {e}");
+            }
 
             let verbosity: u8 = verbose.into();
 
@@ -65,26 +86,77 @@ fn main() -> anyhow::Result<()> {
                 std::process::exit(0);
             } else {
                 println!("Could not find struct: {name}");
+                let suggestions = dwarf.suggest_names::<dwat::Struct>(&name, 3)?;
+                if !suggestions.is_empty() {
+                    println!("Did you mean: {}", suggestions.join(", "));
+                }
                 std::process::exit(1);
             }
         },
-        Commands::Dump { dwarf_file, verbose, fast } => {
+        Commands::Dump { dwarf_file, verbose, fast, progress, kind } => {
             let file = File::open(dwarf_file)?;
             let mmap = unsafe { Mmap::map(&file) }?;
 
             let dwarf = Dwarf::load(&*mmap)?;
+            if let Err(e) = dwarf.check_version() {
+                eprintln!("warning: {e}");
+            }
 
             let verbosity: u8 = verbose.into();
 
-            if fast {
-                let map = dwarf.get_named_types_map::<dwat::Struct>()?;
-                for struc in map.values() {
-                    println!("{}", struc.to_string_verbose(&dwarf, verbosity)?)
+            let items = match kind {
+                Kind::Struct => "structs",
+                Kind::Enum => "enums",
+            };
+            let mut report = |p: ScanProgress| {
+                eprint!("\rscanned {}/{} compile units, {} {items}",
+                        p.cus_done, p.cus_total, p.items_found);
+                if p.cus_done == p.cus_total {
+                    eprintln!();
                 }
-            } else {
-                let map = dwarf.get_fg_named_structs_map()?;
-                for struc in map.values() {
-                    println!("{}", struc.to_string_verbose(&dwarf, verbosity)?)
+            };
+            let report: Option<&mut dyn FnMut(ScanProgress)> = {
+                if progress { Some(&mut report) } else { None }
+            };
+
+            match (kind, fast) {
+                (Kind::Struct, true) => {
+                    let map = {
+                        dwarf.get_named_types_map_with_progress::<dwat::Struct>(
+                            report
+                        )?
+                    };
+                    for struc in map.values() {
+                        println!("{}", struc.to_string_verbose(&dwarf,
+                                                               verbosity)?)
+                    }
+                },
+                (Kind::Struct, false) => {
+                    let map = {
+                        dwarf.get_fg_named_structs_map_with_progress(report)?
+                    };
+                    for struc in map.values() {
+                        println!("{}", struc.to_string_verbose(&dwarf,
+                                                               verbosity)?)
+                    }
+                },
+                (Kind::Enum, true) => {
+                    let map = {
+                        dwarf.get_named_types_map_with_progress::<dwat::Enum>(
+                            report
+                        )?
+                    };
+                    for enu in map.values() {
+                        println!("{}", enu.to_string(&dwarf)?)
+                    }
+                },
+                (Kind::Enum, false) => {
+                    let map = {
+                        dwarf.get_fg_named_enums_map_with_progress(report)?
+                    };
+                    for enu in map.values() {
+                        println!("{}", enu.to_string(&dwarf)?)
+                    }
                 }
             };
             std::process::exit(0)
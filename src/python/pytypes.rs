@@ -179,6 +179,15 @@ pub(crate) fn to_py_object(py: Python<'_>, typ: crate::Type, dwarf: &Dwarf)
     }
 }
 
+// Errors for attributes which are present but can't be decoded, e.g. a member
+// location given as a DWARF expression
+macro_rules! undecodable {
+    () => {
+        Error::UnimplementedError(_) | Error::NonContiguousArrayError(_) |
+        Error::UnknownElementSizeError(_) | Error::UnknownTagError(..)
+    };
+}
+
 macro_rules! attr_getter {
     ($self:ident, $method:ident, $error:pat) => {
         match $self.inner.$method(&*$self.dwarf.inner) {
@@ -503,6 +512,18 @@ impl Restrict {
 
 #[pymethods]
 impl Parameter {
+    /// The name of the parameter, parameters of subroutine types are unnamed
+    #[getter]
+    pub fn name(&self) -> PyResult<Option<String>> {
+        attr_getter!(self, name, Error::NameAttributeNotFound)
+    }
+
+    /// Whether the parameter has a (C++) default argument
+    #[getter]
+    pub fn is_optional(&self) -> PyResult<bool> {
+        Ok(self.inner.has_default_value(&*self.dwarf.inner)?)
+    }
+
     /// Retrieves the backing type of the parameter
     pub fn r#type(&self, py: Python<'_>) -> PyResult<Option<PyObject>> {
         let dwarf = &*self.dwarf.inner;
@@ -522,22 +543,41 @@ impl Member {
         attr_getter!(self, name, Error::NameAttributeNotFound)
     }
 
-    /// The size of this member in bytes
+    /// The size of this member in bytes, None when it is unknown or can't be
+    /// decoded (e.g. an array accessed through a descriptor)
     #[getter]
     pub fn byte_size(&self) -> PyResult<Option<usize>> {
-        attr_getter!(self, byte_size, Error::ByteSizeAttributeNotFound)
+        attr_getter!(self, byte_size,
+                     Error::ByteSizeAttributeNotFound | undecodable!())
     }
 
     /// The size of this member in bits (only present for bitfields)
     #[getter]
     pub fn bit_size(&self) -> PyResult<Option<usize>> {
-        attr_getter!(self, bit_size, Error::BitSizeAttributeNotFound)
+        attr_getter!(self, bit_size,
+                     Error::BitSizeAttributeNotFound | undecodable!())
     }
 
-    /// The offset of this member from the start of the data type
+    /// The offset of this member from the start of the data type, None can
+    /// also mean the location is present but can't be decoded, which
+    /// `has_location` tells apart
     #[getter]
     pub fn offset(&self) -> PyResult<Option<usize>> {
-        attr_getter!(self, offset, Error::MemberLocationAttributeNotFound)
+        attr_getter!(self, offset,
+                     Error::MemberLocationAttributeNotFound | undecodable!())
+    }
+
+    /// Whether the member has a location attribute, even one `offset` can't
+    /// decode
+    #[getter]
+    pub fn has_location(&self) -> PyResult<bool> {
+        Ok(self.inner.has_location(&*self.dwarf.inner)?)
+    }
+
+    /// The C spelling of the member's type, e.g. 'struct foo *'
+    #[getter]
+    pub fn type_name(&self) -> PyResult<String> {
+        Ok(self.inner.type_name(&*self.dwarf.inner)?)
     }
 
     /// Retrieves the backing type of the member
@@ -1,5 +1,8 @@
 //! Interfaces representing DWARF type information
 
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
 use gimli::{RunTimeEndian, DebugStrOffset};
 use gimli::AttributeValue;
 
@@ -7,20 +10,26 @@ use crate::dwarf::borrowable_dwarf::BorrowableDwarf;
 use crate::types::unit_has_members::UnitHasMembers;
 use crate::types::unit_inner_type::UnitInnerType;
 use crate::types::unit_name_type::UnitNamedType;
-use crate::format::format_member;
-use crate::dwarf::DwarfContext;
+use crate::format::{format_member, format_enumerator, FormatOptions};
+use crate::format::{format_type_name, format_inner_type_name, format_typedef};
+use crate::format::{format_method_signature, format_prototype};
+use crate::format::format_variable;
+use crate::format::format_type;
+use crate::dwarf::{DwarfContext, is_sup_unit};
 use crate::Error;
 
 // Abbreviations for some lengthy gimli types
 pub(crate) type R<'a> = gimli::EndianSlice<'a, RunTimeEndian>;
+#[allow(clippy::upper_case_acronyms)]
 pub(crate) type DIE<'a> = gimli::DebuggingInformationEntry<'a,'a,R<'a>,usize>;
+#[allow(clippy::upper_case_acronyms)]
 pub(crate) type CU<'a> = gimli::Unit<R<'a>, usize>;
 pub(crate) type GimliDwarf<'a> = gimli::Dwarf<R<'a>>;
 
 /// Represents a location of some type/tag in the DWARF information
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Location {
-    pub header: gimli::DebugInfoOffset,
+    pub header: gimli::UnitSectionOffset,
     pub offset: gimli::UnitOffset,
 }
 
@@ -96,6 +105,19 @@ pub struct FormalParameter {
     pub location: Location,
 }
 
+/// Represents a function, its type is the return type, the location uniquely
+/// identifies a function and so can be used as a key when indexing them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Subprogram {
+    pub location: Location,
+}
+
+/// Represents a compile unit, its name is the path of the primary source file
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CompileUnit {
+    pub location: Location,
+}
+
 /// Represents a variable declaration
 #[derive(Clone, Copy, Debug)]
 pub struct Variable {
@@ -108,6 +130,235 @@ pub struct Member {
     pub location: Location,
 }
 
+/// Represents the variant part of a struct (DW_TAG_variant_part), i.e. the
+/// alternatives of a Rust enum or Ada variant record selected by a
+/// discriminant
+#[derive(Clone, Copy, Debug)]
+pub struct VariantPart {
+    pub location: Location,
+}
+
+/// Represents a single alternative of a variant part (DW_TAG_variant), its
+/// members are the fields present when it is selected
+#[derive(Clone, Copy, Debug)]
+pub struct Variant {
+    pub location: Location,
+}
+
+/// The position of a member within its datatype down to the bit, bits are
+/// numbered in the target's bit order, starting from the least significant
+/// bit on little endian targets
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitLayout {
+    /// The offset of the byte containing the first bit of the member
+    pub byte_offset: usize,
+
+    /// The offset of the first bit of the member within that byte
+    pub bit_offset: usize,
+
+    /// The size of the member in bits
+    pub bit_size: usize,
+}
+
+/// A scalar at the bottom of a flattened struct, see `Struct::flatten_scalars`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScalarField {
+    /// The dotted path to the scalar, e.g. `a.b[2].c`
+    pub path: String,
+
+    /// The offset in bytes from the start of the outermost struct
+    pub offset: usize,
+
+    /// The C spelling of the scalar's type
+    pub type_name: String,
+
+    /// The size of the scalar in bytes
+    pub size: usize,
+}
+
+/// A virtual method of a C++ struct along with its slot in the vtable
+#[derive(Clone, Debug)]
+pub struct VtableEntry {
+    /// The index of the method in the vtable
+    pub slot: usize,
+
+    /// The name of the method
+    pub name: String,
+
+    /// The declaration of the method without `this`, e.g. `int area(int)`
+    pub signature: String,
+
+    /// The method itself
+    pub method: Subprogram,
+}
+
+/// The prototype of a function, see `Subprogram::signature`
+#[derive(Clone, Debug)]
+pub struct FunctionSignature {
+    /// The name of the function
+    pub name: String,
+
+    /// The type returned by the function, None for void functions
+    pub return_type: Option<Type>,
+
+    /// The name, if any, and type of each parameter in order, including the
+    /// artificial `this` of C++ methods
+    pub params: Vec<(Option<String>, Type)>,
+
+    /// Whether the function takes variable arguments (`...`)
+    pub is_variadic: bool,
+}
+
+/// An owned copy of a `FunctionSignature` which doesn't borrow the DWARF,
+/// types are referred to by their C spelling
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignatureSnapshot {
+    /// The name of the function
+    pub name: String,
+
+    /// The type returned by the function, None for void functions
+    pub return_type: Option<String>,
+
+    /// The name, if any, and type of each parameter in order
+    pub params: Vec<(Option<String>, String)>,
+
+    /// Whether the function takes variable arguments (`...`)
+    pub is_variadic: bool,
+}
+
+/// An owned copy of a struct or union member, see `TypeSnapshot`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemberSnapshot {
+    /// The name of the member, None for anonymous members
+    pub name: Option<String>,
+
+    /// The C spelling of the member's type
+    pub type_name: String,
+
+    /// The position of the member from the start of the datatype in bits
+    pub bit_offset: usize,
+
+    /// The width of the member if it is a bitfield
+    pub bit_size: Option<usize>,
+}
+
+/// An owned copy of a type which doesn't borrow the DWARF, so it can outlive
+/// it (e.g. in a cache), other types are referred to by their C spelling so
+/// nested aggregates aren't expanded
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypeSnapshot {
+    Struct {
+        name: Option<String>,
+        byte_size: usize,
+        members: Vec<MemberSnapshot>,
+    },
+    Union {
+        name: Option<String>,
+        byte_size: usize,
+        members: Vec<MemberSnapshot>,
+    },
+    Enum {
+        name: Option<String>,
+        byte_size: usize,
+        enumerators: Vec<(String, i64)>,
+    },
+    Array {
+        element: String,
+        bound: usize,
+        byte_size: usize,
+    },
+    Pointer {
+        pointee: String,
+        byte_size: usize,
+    },
+    Subroutine {
+        return_type: String,
+        params: Vec<String>,
+    },
+    Typedef {
+        name: String,
+        target: String,
+    },
+    Base {
+        name: String,
+        byte_size: usize,
+    },
+    Const {
+        inner: String,
+    },
+    Volatile {
+        inner: String,
+    },
+    Restrict {
+        inner: String,
+    },
+}
+
+/// The geometry of one dimension (DW_TAG_subrange_type) of an array
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dimension {
+    /// The index of the first element, the language default (0 for C, 1 for
+    /// Fortran) when the subrange has no DW_AT_lower_bound, this can be
+    /// negative in Fortran
+    pub lower_bound: i64,
+
+    /// The number of elements, None when it isn't a constant (e.g. flexible
+    /// array members or Fortran assumed-shape arrays)
+    pub count: Option<usize>,
+
+    /// The distance between elements in bytes, only present when it differs
+    /// from the element size
+    pub byte_stride: Option<usize>,
+
+    /// The distance between elements in bits, for bit-packed arrays (e.g. an
+    /// Ada array of enums with a component size of 2)
+    pub bit_stride: Option<usize>,
+}
+
+/// Represents one dimension of an array (DW_TAG_subrange_type), see
+/// `Dimension` for the resolved geometry
+#[derive(Clone, Copy, Debug)]
+pub struct Subrange {
+    pub location: Location,
+}
+
+/// Represents a single named value of an enum
+#[derive(Clone, Copy, Debug)]
+pub struct Enumerator {
+    pub location: Location,
+}
+
+/// How types with DWARF tags not modeled by `Type` are handled
+#[derive(Clone, Copy, Debug, Default)]
+pub enum UnknownTagPolicy {
+    /// Return `Error::UnknownTagError`, the formatters render the type as an
+    /// `/* unhandled: <tag> */` placeholder
+    #[default]
+    Error,
+    /// Treat the type as absent, it is formatted as void
+    Skip,
+    /// Map the tag to a `Type`, returning None behaves like `Skip`
+    Custom(fn(gimli::DwTag, Location) -> Option<Type>),
+}
+
+impl UnknownTagPolicy {
+    // Apply the policy to the result of resolving some type
+    pub(crate) fn apply(&self, typ: Result<Type, Error>)
+    -> Result<Type, Error> {
+        match (self, typ) {
+            (UnknownTagPolicy::Skip, Err(Error::UnknownTagError(..))) => {
+                Err(Error::TypeAttributeNotFound)
+            },
+            (UnknownTagPolicy::Custom(f),
+             Err(Error::UnknownTagError(tag, location))) => {
+                f(tag, location).ok_or(Error::TypeAttributeNotFound)
+            },
+            (_, typ) => typ
+        }
+    }
+}
+
 /// Enum of supported types which may be returned by get_type()
 #[derive(Clone, Copy, Debug)]
 pub enum Type {
@@ -124,14 +375,360 @@ pub enum Type {
     Restrict(Restrict),
 }
 
+/// Broad classification of a type, see `Type::category`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypeCategory {
+    /// Base types, enums and pointers
+    Scalar,
+    /// Structs, unions and arrays
+    Aggregate,
+    /// Subroutine types
+    Function,
+    /// Typedefs or cv-qualified types with no inner type
+    Void,
+}
+
 impl Type {
-    fn u_byte_size(&self, unit: &CU) -> Result<usize, Error> {
+    pub(crate) fn location(&self) -> Location {
+        match self {
+            Type::Struct(t) => t.location,
+            Type::Array(t) => t.location,
+            Type::Enum(t) => t.location,
+            Type::Pointer(t) => t.location,
+            Type::Subroutine(t) => t.location,
+            Type::Typedef(t) => t.location,
+            Type::Union(t) => t.location,
+            Type::Base(t) => t.location,
+            Type::Const(t) => t.location,
+            Type::Volatile(t) => t.location,
+            Type::Restrict(t) => t.location,
+        }
+    }
+
+    /// The DWARF tag the variant models, e.g. `DW_TAG_structure_type` for
+    /// `Type::Struct`, nothing is read from the DWARF
+    pub fn tag(&self) -> gimli::DwTag {
+        match self {
+            Type::Struct(_) => Struct::tag(),
+            Type::Array(_) => Array::tag(),
+            Type::Enum(_) => Enum::tag(),
+            Type::Pointer(_) => Pointer::tag(),
+            Type::Subroutine(_) => Subroutine::tag(),
+            Type::Typedef(_) => Typedef::tag(),
+            Type::Union(_) => Union::tag(),
+            Type::Base(_) => Base::tag(),
+            Type::Const(_) => Const::tag(),
+            Type::Volatile(_) => Volatile::tag(),
+            Type::Restrict(_) => Restrict::tag(),
+        }
+    }
+
+    /// The name of the DWARF tag the variant models, e.g.
+    /// `"DW_TAG_structure_type"` for `Type::Struct`
+    pub fn tag_name(&self) -> &'static str {
+        // every modeled tag is a standard one
+        self.tag().static_string().unwrap_or("DW_TAG_unknown")
+    }
+
+    /// A short name for the kind of the variant, e.g. `"struct"`
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Type::Struct(_) => "struct",
+            Type::Array(_) => "array",
+            Type::Enum(_) => "enum",
+            Type::Pointer(_) => "pointer",
+            Type::Subroutine(_) => "subroutine",
+            Type::Typedef(_) => "typedef",
+            Type::Union(_) => "union",
+            Type::Base(_) => "base",
+            Type::Const(_) => "const",
+            Type::Volatile(_) => "volatile",
+            Type::Restrict(_) => "restrict",
+        }
+    }
+
+    /// Get the inner `Struct` if this is a `Type::Struct`
+    pub fn as_struct(&self) -> Option<Struct> {
+        match self {
+            Type::Struct(t) => Some(*t),
+            _ => None
+        }
+    }
+
+    /// Get the inner `Array` if this is a `Type::Array`
+    pub fn as_array(&self) -> Option<Array> {
+        match self {
+            Type::Array(t) => Some(*t),
+            _ => None
+        }
+    }
+
+    /// Get the inner `Enum` if this is a `Type::Enum`
+    pub fn as_enum(&self) -> Option<Enum> {
+        match self {
+            Type::Enum(t) => Some(*t),
+            _ => None
+        }
+    }
+
+    /// Get the inner `Pointer` if this is a `Type::Pointer`
+    pub fn as_pointer(&self) -> Option<Pointer> {
+        match self {
+            Type::Pointer(t) => Some(*t),
+            _ => None
+        }
+    }
+
+    /// Get the inner `Subroutine` if this is a `Type::Subroutine`
+    pub fn as_subroutine(&self) -> Option<Subroutine> {
+        match self {
+            Type::Subroutine(t) => Some(*t),
+            _ => None
+        }
+    }
+
+    /// Get the inner `Typedef` if this is a `Type::Typedef`
+    pub fn as_typedef(&self) -> Option<Typedef> {
+        match self {
+            Type::Typedef(t) => Some(*t),
+            _ => None
+        }
+    }
+
+    /// Get the inner `Union` if this is a `Type::Union`
+    pub fn as_union(&self) -> Option<Union> {
+        match self {
+            Type::Union(t) => Some(*t),
+            _ => None
+        }
+    }
+
+    /// Get the inner `Base` if this is a `Type::Base`
+    pub fn as_base(&self) -> Option<Base> {
+        match self {
+            Type::Base(t) => Some(*t),
+            _ => None
+        }
+    }
+
+    /// Get the inner `Const` if this is a `Type::Const`
+    pub fn as_const(&self) -> Option<Const> {
+        match self {
+            Type::Const(t) => Some(*t),
+            _ => None
+        }
+    }
+
+    /// Get the inner `Volatile` if this is a `Type::Volatile`
+    pub fn as_volatile(&self) -> Option<Volatile> {
+        match self {
+            Type::Volatile(t) => Some(*t),
+            _ => None
+        }
+    }
+
+    /// Get the inner `Restrict` if this is a `Type::Restrict`
+    pub fn as_restrict(&self) -> Option<Restrict> {
+        match self {
+            Type::Restrict(t) => Some(*t),
+            _ => None
+        }
+    }
+
+    /// Follow typedefs and qualifiers down to the underlying type and get it
+    /// if it is a `Type::Struct`, `Type::Union` or `Type::Enum`, typedefs of
+    /// void and other kinds of types are None
+    pub fn as_aggregate<D>(&self, dwarf: &D) -> Result<Option<Type>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let mut typ = *self;
+        loop {
+            let inner = match typ {
+                Type::Typedef(t) => t.get_type(dwarf),
+                Type::Const(t) => t.get_type(dwarf),
+                Type::Volatile(t) => t.get_type(dwarf),
+                Type::Restrict(t) => t.get_type(dwarf),
+                Type::Struct(_) | Type::Union(_) | Type::Enum(_) => {
+                    return Ok(Some(typ))
+                },
+                _ => return Ok(None)
+            };
+            typ = match inner {
+                Ok(inner) => inner,
+                Err(Error::TypeAttributeNotFound) => return Ok(None),
+                Err(e) => return Err(e)
+            };
+        }
+    }
+
+    // Follow typedefs and cv-qualifiers down to the underlying type, which may
+    // be in another unit (see `u_unit_of`)
+    pub(crate) fn u_strip_cv_typedefs<D>(self, dwarf: &D, unit: &CU)
+    -> Result<Type, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let inner = match self {
+            Type::Typedef(t) => u_follow_type(dwarf, unit, &t)?,
+            Type::Const(t) => u_follow_type(dwarf, unit, &t)?,
+            Type::Volatile(t) => u_follow_type(dwarf, unit, &t)?,
+            Type::Restrict(t) => u_follow_type(dwarf, unit, &t)?,
+            typ => return Ok(typ)
+        };
+        u_unit_of(dwarf, unit, inner.location(), |unit| {
+            inner.u_strip_cv_typedefs(dwarf, unit)
+        })
+    }
+
+    pub(crate) fn u_category<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<TypeCategory, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let typ = match self.u_strip_cv_typedefs(dwarf, unit) {
+            Ok(typ) => typ,
+            Err(Error::TypeAttributeNotFound) => return Ok(TypeCategory::Void),
+            Err(e) => return Err(e)
+        };
+        let category = match typ {
+            Type::Base(_) | Type::Enum(_) | Type::Pointer(_) => {
+                TypeCategory::Scalar
+            },
+            Type::Struct(_) | Type::Union(_) | Type::Array(_) => {
+                TypeCategory::Aggregate
+            },
+            Type::Subroutine(_) => TypeCategory::Function,
+            // stripped above
+            Type::Typedef(_) | Type::Const(_) | Type::Volatile(_) |
+            Type::Restrict(_) => unreachable!()
+        };
+        Ok(category)
+    }
+
+    /// Classify the type after stripping typedefs and cv-qualifiers
+    pub fn category<D>(&self, dwarf: &D) -> Result<TypeCategory, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_category(dwarf, unit)
+        })?
+    }
+
+    pub(crate) fn u_is_function_pointer<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<bool, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let ptr = match self.u_strip_cv_typedefs(dwarf, unit) {
+            Ok(Type::Pointer(ptr)) => ptr,
+            Ok(_) | Err(Error::TypeAttributeNotFound) => return Ok(false),
+            Err(e) => return Err(e)
+        };
+        let pointee = u_unit_of(dwarf, unit, ptr.location(), |unit| {
+            u_with_type(dwarf, unit, &ptr, |pointee, unit| {
+                pointee.u_strip_cv_typedefs(dwarf, unit)
+            })
+        });
+        match pointee {
+            Ok(Type::Subroutine(_)) => Ok(true),
+            Ok(_) | Err(Error::TypeAttributeNotFound) => Ok(false),
+            Err(e) => Err(e)
+        }
+    }
+
+    /// Whether the type is a pointer to a function after stripping typedefs
+    /// and cv-qualifiers
+    pub fn is_function_pointer<D>(&self, dwarf: &D) -> Result<bool, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_is_function_pointer(dwarf, unit)
+        })?
+    }
+
+    pub(crate) fn u_snapshot<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<TypeSnapshot, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let snapshot = match *self {
+            Type::Struct(struc) => TypeSnapshot::Struct {
+                name: optional_name(struc.u_name(dwarf, unit))?,
+                byte_size: struc.u_byte_size(unit)?,
+                members: u_snapshot_members(dwarf, unit, &struc)?
+            },
+            Type::Union(uni) => TypeSnapshot::Union {
+                name: optional_name(uni.u_name(dwarf, unit))?,
+                byte_size: uni.u_byte_size(dwarf, unit)?,
+                members: u_snapshot_members(dwarf, unit, &uni)?
+            },
+            Type::Enum(enu) => TypeSnapshot::Enum {
+                name: optional_name(enu.u_name(dwarf, unit))?,
+                byte_size: enu.u_byte_size(dwarf, unit)?,
+                enumerators: enu.u_enumerators(unit)?.iter().map(|e| {
+                    Ok((e.u_name(dwarf, unit)?, e.u_value(unit)?))
+                }).collect::<Result<Vec<_>, Error>>()?
+            },
+            Type::Array(arr) => TypeSnapshot::Array {
+                element: format_inner_type_name(dwarf, unit, &arr)?,
+                bound: arr.u_get_bound(unit)?,
+                byte_size: arr.u_byte_size(dwarf, unit)?
+            },
+            Type::Pointer(ptr) => TypeSnapshot::Pointer {
+                pointee: format_inner_type_name(dwarf, unit, &ptr)?,
+                byte_size: ptr.u_byte_size(unit)?
+            },
+            Type::Subroutine(sub) => TypeSnapshot::Subroutine {
+                return_type: format_inner_type_name(dwarf, unit, &sub)?,
+                params: sub.u_get_params(unit)?.iter().map(|param| {
+                    format_inner_type_name(dwarf, unit, param)
+                }).collect::<Result<Vec<_>, Error>>()?
+            },
+            Type::Typedef(typedef) => TypeSnapshot::Typedef {
+                name: typedef.u_name(dwarf, unit)?,
+                target: format_inner_type_name(dwarf, unit, &typedef)?
+            },
+            Type::Base(base) => TypeSnapshot::Base {
+                name: base.u_name(dwarf, unit)?,
+                byte_size: base.u_byte_size(unit)?
+            },
+            Type::Const(cons) => TypeSnapshot::Const {
+                inner: format_inner_type_name(dwarf, unit, &cons)?
+            },
+            Type::Volatile(vol) => TypeSnapshot::Volatile {
+                inner: format_inner_type_name(dwarf, unit, &vol)?
+            },
+            Type::Restrict(res) => TypeSnapshot::Restrict {
+                inner: format_inner_type_name(dwarf, unit, &res)?
+            },
+        };
+        Ok(snapshot)
+    }
+
+    /// Copy the information of the type into an owned `TypeSnapshot`
+    pub fn snapshot<D>(&self, dwarf: &D) -> Result<TypeSnapshot, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_snapshot(dwarf, unit)
+        })?
+    }
+
+    /// The C spelling of the type, e.g. `struct foo *` or `unsigned int [4]`
+    pub fn type_name<D>(&self, dwarf: &D) -> Result<String, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location(), |unit| {
+            format_type_name(dwarf, unit, *self)
+        })?
+    }
+
+    /// Format the type as it is spelled when nested in another type, e.g. a
+    /// pointer's pointee or a member's type, named structs, unions and enums
+    /// are not expanded into their definitions
+    pub fn to_string<D>(&self, dwarf: &D) -> Result<String, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location(), |unit| {
+            let opts = FormatOptions::default();
+            format_type(dwarf, unit, "".to_string(), *self, 1, 0, &opts, 0)
+        })?
+    }
+
+    fn u_byte_size<D>(&self, dwarf: &D, unit: &CU) -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
         match self {
             Type::Struct(struc) => {
                 struc.u_byte_size(unit)
             },
             Type::Array(arr) => {
-                arr.u_byte_size(unit)
+                arr.u_byte_size(dwarf, unit)
             }
             Type::Pointer(ptr) => {
                 ptr.u_byte_size(unit)
@@ -140,22 +737,22 @@ impl Type {
                 base.u_byte_size(unit)
             }
             Type::Union(uni) => {
-                uni.u_byte_size(unit)
+                uni.u_byte_size(dwarf, unit)
             }
             Type::Enum(enu) => {
-                enu.u_byte_size(unit)
+                enu.u_byte_size(dwarf, unit)
             }
             Type::Typedef(typedef) => {
-                typedef.u_byte_size(unit)
+                typedef.u_byte_size(dwarf, unit)
             }
             Type::Const(cons) => {
-                cons.u_byte_size(unit)
+                cons.u_byte_size(dwarf, unit)
             }
             Type::Volatile(vol) => {
-                vol.u_byte_size(unit)
+                vol.u_byte_size(dwarf, unit)
             }
             Type::Restrict(vol) => {
-                vol.u_byte_size(unit)
+                vol.u_byte_size(dwarf, unit)
             }
             // --- Unsized ---
             Type::Subroutine(_) => {
@@ -164,8 +761,77 @@ impl Type {
         }
     }
 
-    pub fn byte_size<D>(&self, dwarf: &D) -> Result<usize, Error>
+    pub(crate) fn u_alignment(&self, unit: &CU) -> Result<usize, Error> {
+        let alignment = unit.entry_context(&self.location(), |entry| {
+            get_entry_alignment(entry)
+        })?;
+
+        if let Some(alignment) = alignment {
+            return Ok(alignment)
+        }
+
+        Err(Error::AlignmentAttributeNotFound)
+    }
+
+    // The alignment the type has in memory, from DW_AT_alignment when set and
+    // otherwise inferred assuming scalars are aligned to their size but not
+    // beyond the address size (e.g. `long long` on i386), this errs on the
+    // low side so that `u_is_packed` doesn't flag naturally aligned layouts
+    pub(crate) fn u_natural_alignment<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        match self.u_alignment(unit) {
+            Ok(alignment) => return Ok(alignment),
+            Err(Error::AlignmentAttributeNotFound) => {},
+            Err(e) => return Err(e)
+        }
+
+        let address_size = unit.header.encoding().address_size as usize;
+        let alignment = match self {
+            Type::Base(base) => {
+                let byte_size = base.u_byte_size(unit)?;
+                // complex numbers are aligned like their real part
+                let byte_size = match base.u_encoding(unit) {
+                    Ok(gimli::DW_ATE_complex_float) => byte_size / 2,
+                    Ok(_) | Err(Error::EncodingAttributeNotFound) => byte_size,
+                    Err(e) => return Err(e)
+                };
+                byte_size.min(address_size)
+            },
+            Type::Pointer(_) | Type::Enum(_) => {
+                self.u_byte_size(dwarf, unit)?.min(address_size)
+            },
+            Type::Array(t) => u_inner_alignment(dwarf, unit, t)?,
+            Type::Typedef(t) => u_inner_alignment(dwarf, unit, t)?,
+            Type::Const(t) => u_inner_alignment(dwarf, unit, t)?,
+            Type::Volatile(t) => u_inner_alignment(dwarf, unit, t)?,
+            Type::Restrict(t) => u_inner_alignment(dwarf, unit, t)?,
+            Type::Struct(t) => {
+                if t.u_is_packed(dwarf, unit)? {
+                    return Ok(1)
+                }
+                u_max_member_alignment(dwarf, unit, t.u_members(unit)?)?
+            },
+            Type::Union(t) => {
+                u_max_member_alignment(dwarf, unit, t.u_members(unit)?)?
+            },
+            Type::Subroutine(_) => 1
+        };
+        Ok(alignment.max(1))
+    }
+
+    /// The DW_AT_alignment of the type, this is only present when the
+    /// alignment was set explicitly (e.g. with _Alignas), typedefs are not
+    /// followed since they can carry their own alignment
+    pub fn alignment<D>(&self, dwarf: &D) -> Result<usize, Error>
     where D: DwarfContext {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_alignment(unit)
+        })?
+    }
+
+    pub fn byte_size<D>(&self, dwarf: &D) -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
         match self {
             Type::Struct(struc) => {
                 struc.byte_size(dwarf)
@@ -203,44 +869,232 @@ impl Type {
             }
         }
     }
-}
-
-// Try to retrieve a string from the debug_str section for a given offset
-pub(crate) fn from_dbg_str_ref<D>(dwarf: &D, str_ref: DebugStrOffset<usize>)
--> Option<String>
-where D: DwarfContext + BorrowableDwarf {
-    dwarf.borrow_dwarf(|dwarf| {
-        if let Ok(str_ref) = dwarf.debug_str.get_str(str_ref) {
-            let str_ref = str_ref.to_string_lossy();
-            return Some(str_ref.to_string());
-        }
-        None
-    })
-}
 
-// Try to retrieve the name attribute as a string for a DIE if one exists
-pub(crate) fn get_entry_name<D>(dwarf: &D, entry: &DIE) -> Option<String>
-where D: DwarfContext + BorrowableDwarf {
-    let mut attrs = entry.attrs();
-    while let Ok(Some(attr)) = &attrs.next() {
-        if attr.name() == gimli::DW_AT_name {
-            match attr.value() {
-                gimli::AttributeValue::String(str) => {
-                    if let Ok(str) = str.to_string() {
-                        return Some(str.to_string())
-                    }
-                }
-                gimli::AttributeValue::DebugStrRef(strref) => {
-                    return from_dbg_str_ref(dwarf, strref)
+    pub(crate) fn u_bit_size<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        match self {
+            // narrow base types (e.g. _BitInt(3)) and enums can carry their
+            // exact width
+            Type::Base(_) | Type::Enum(_) => {
+                let bit_size = unit.entry_context(&self.location(), |entry| {
+                    get_entry_bit_size(entry)
+                })?;
+                match bit_size {
+                    Some(bit_size) => Ok(bit_size),
+                    None => Ok(self.u_byte_size(dwarf, unit)? * 8)
                 }
-                _ => { }
-            };
+            },
+            Type::Typedef(tdef) => u_inner_bit_size(dwarf, unit, tdef),
+            Type::Const(cons) => u_inner_bit_size(dwarf, unit, cons),
+            Type::Volatile(vol) => u_inner_bit_size(dwarf, unit, vol),
+            Type::Restrict(res) => u_inner_bit_size(dwarf, unit, res),
+            Type::Array(array) => array.u_bit_size(dwarf, unit),
+            _ => Ok(self.u_byte_size(dwarf, unit)? * 8)
         }
     }
-    None
-}
 
-// // Try to retrieve a string from the debug_str section for a given offset
+    /// The exact width of the type in bits, this is the DW_AT_bit_size of
+    /// base types and enums when present and `byte_size * 8` otherwise,
+    /// qualifiers and typedefs are followed. The width of a bitfield is on
+    /// the member, see `Member::bit_size`
+    pub fn bit_size<D>(&self, dwarf: &D) -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_bit_size(dwarf, unit)
+        })?
+    }
+}
+
+/// An object safe view of a type, so that types of different kinds can be
+/// stored together, e.g. as `Vec<Box<dyn AnyType<Dwarf>>>`
+///
+/// All kinds support every method, but `name` fails with
+/// `NameAttributeNotFound` for anonymous types and pointers and `byte_size`
+/// fails for subroutines. `to_string` renders structs, unions and enums as
+/// their definition, typedefs as a typedef declaration and any other kind as
+/// its C spelling (see `Type::type_name`)
+pub trait AnyType<D> {
+    /// The name of the type
+    fn name(&self, dwarf: &D) -> Result<String, Error>;
+
+    /// The size of the type in bytes
+    fn byte_size(&self, dwarf: &D) -> Result<usize, Error>;
+
+    /// The DWARF tag of the type, e.g. DW_TAG_structure_type
+    fn kind(&self) -> gimli::DwTag;
+
+    /// Format the type as C
+    fn to_string(&self, dwarf: &D) -> Result<String, Error>;
+}
+
+impl<D> AnyType<D> for Type
+where D: DwarfContext + BorrowableDwarf {
+    fn name(&self, dwarf: &D) -> Result<String, Error> {
+        let name = dwarf.unit_context(&self.location(), |unit| {
+            unit.entry_context(&self.location(), |entry| {
+                get_entry_name(dwarf, unit, entry)
+            })
+        })??;
+        name.ok_or(Error::NameAttributeNotFound)
+    }
+
+    fn byte_size(&self, dwarf: &D) -> Result<usize, Error> {
+        Type::byte_size(self, dwarf)
+    }
+
+    fn kind(&self) -> gimli::DwTag {
+        self.tag()
+    }
+
+    fn to_string(&self, dwarf: &D) -> Result<String, Error> {
+        match self {
+            Type::Struct(struc) => struc.to_string(dwarf),
+            Type::Union(uni) => uni.to_string(dwarf),
+            Type::Enum(enu) => enu.to_string(dwarf),
+            Type::Typedef(typedef) => {
+                format_typedef(dwarf, *typedef, &FormatOptions::default())
+            },
+            _ => self.type_name(dwarf)
+        }
+    }
+}
+
+// A missing name is expected for anonymous types and members
+fn optional_name(name: Result<String, Error>) -> Result<Option<String>, Error> {
+    match name {
+        Ok(name) => Ok(Some(name)),
+        Err(Error::NameAttributeNotFound) => Ok(None),
+        Err(e) => Err(e)
+    }
+}
+
+fn u_snapshot_members<D, T>(dwarf: &D, unit: &CU, typ: &T)
+-> Result<Vec<MemberSnapshot>, Error>
+where D: DwarfContext + BorrowableDwarf, T: UnitHasMembers {
+    let mut snapshots: Vec<MemberSnapshot> = vec![];
+    for member in typ.u_members(unit)? {
+        let bit_size = match member.u_bit_size(unit) {
+            Ok(bit_size) => Some(bit_size),
+            Err(Error::BitSizeAttributeNotFound) => None,
+            Err(e) => return Err(e)
+        };
+        snapshots.push(MemberSnapshot {
+            name: optional_name(member.u_name(dwarf, unit))?,
+            type_name: format_inner_type_name(dwarf, unit, &member)?,
+            bit_offset: member.u_data_bit_offset(dwarf, unit)?,
+            bit_size
+        });
+    }
+    Ok(snapshots)
+}
+
+// Try to retrieve a string from the debug_str section of the supplementary
+// file for a given offset
+fn from_sup_dbg_str_ref<D>(dwarf: &D, str_ref: DebugStrOffset<usize>)
+-> Option<String>
+where D: DwarfContext + BorrowableDwarf {
+    dwarf.borrow_dwarf(|dwarf| {
+        if let Ok(str_ref) = dwarf.sup()?.debug_str.get_str(str_ref) {
+            let str_ref = str_ref.to_string_lossy();
+            return Some(str_ref.to_string());
+        }
+        None
+    })
+}
+
+// Try to retrieve a string from the debug_str section for a given offset, the
+// units of the supplementary file use its own debug_str section
+pub(crate) fn from_dbg_str_ref<D>(dwarf: &D, unit: &CU,
+                                  str_ref: DebugStrOffset<usize>)
+-> Option<String>
+where D: DwarfContext + BorrowableDwarf {
+    dwarf.borrow_dwarf(|dwarf| {
+        let debug_str = match is_sup_unit(dwarf, unit) {
+            true => &dwarf.sup()?.debug_str,
+            false => &dwarf.debug_str
+        };
+        if let Ok(str_ref) = debug_str.get_str(str_ref) {
+            let str_ref = str_ref.to_string_lossy();
+            return Some(str_ref.to_string());
+        }
+        None
+    })
+}
+
+// Try to retrieve a string from the debug_line_str section for a given offset,
+// DWARF 5 uses this section for the names of compile units and source files
+fn from_dbg_line_str_ref<D>(dwarf: &D,
+                            str_ref: gimli::DebugLineStrOffset<usize>)
+-> Option<String>
+where D: DwarfContext + BorrowableDwarf {
+    dwarf.borrow_dwarf(|dwarf| {
+        if let Ok(str_ref) = dwarf.debug_line_str.get_str(str_ref) {
+            let str_ref = str_ref.to_string_lossy();
+            return Some(str_ref.to_string());
+        }
+        None
+    })
+}
+
+// Try to retrieve the name attribute as a string for a DIE if one exists
+pub(crate) fn get_entry_name<D>(dwarf: &D, unit: &CU, entry: &DIE)
+-> Option<String>
+where D: DwarfContext + BorrowableDwarf {
+    get_entry_string(dwarf, unit, entry, gimli::DW_AT_name)
+}
+
+// Get a string attribute of an entry, either inline, in .debug_str or in
+// .debug_line_str, the .debug_str of the supplementary file is used for its
+// own entries and for DW_FORM_GNU_strp_alt references
+fn get_entry_string<D>(dwarf: &D, unit: &CU, entry: &DIE, name: gimli::DwAt)
+-> Option<String>
+where D: DwarfContext + BorrowableDwarf {
+    let mut attrs = entry.attrs();
+    while let Ok(Some(attr)) = &attrs.next() {
+        if attr.name() == name {
+            match attr.value() {
+                gimli::AttributeValue::String(str) => {
+                    if let Ok(str) = str.to_string() {
+                        return Some(str.to_string())
+                    }
+                }
+                gimli::AttributeValue::DebugStrRef(strref) => {
+                    return from_dbg_str_ref(dwarf, unit, strref)
+                }
+                gimli::AttributeValue::DebugStrRefSup(strref) => {
+                    return from_sup_dbg_str_ref(dwarf, strref)
+                }
+                gimli::AttributeValue::DebugLineStrRef(strref) => {
+                    return from_dbg_line_str_ref(dwarf, strref)
+                }
+                _ => { }
+            };
+        }
+    }
+    None
+}
+
+// Get the mangled name of an entry, definitions that are split from their
+// declaration only carry the name on the DW_AT_specification entry
+pub(crate) fn get_entry_linkage_name<D>(dwarf: &D, unit: &CU, entry: &DIE)
+-> Option<String>
+where D: DwarfContext + BorrowableDwarf {
+    for attr in [gimli::DW_AT_linkage_name, gimli::DW_AT_MIPS_linkage_name] {
+        if let Some(name) = get_entry_string(dwarf, unit, entry, attr) {
+            return Some(name)
+        }
+    }
+    if let Ok(Some(AttributeValue::UnitRef(offset))) =
+        entry.attr_value(gimli::DW_AT_specification) {
+        if let Ok(spec) = unit.entry(offset) {
+            return get_entry_linkage_name(dwarf, unit, &spec)
+        }
+    }
+    None
+}
+
+// // Try to retrieve a string from the debug_str section for a given offset
 // pub(crate) fn owned_from_dbg_str_ref(dwarf: &OwnedDwarf, str_ref: DebugStrOffset<usize>)
 // -> Option<String> {
 //     dwarf.borrow_dwarf(|dwarf| {
@@ -285,13 +1139,32 @@ pub(crate) mod unit_name_type {
         fn u_name<D>(&self, dwarf: &D, unit: &CU) -> Result<String, Error>
         where D: DwarfContext + BorrowableDwarf {
             if let Some(name) = unit.entry_context(&self.location(), |entry| {
-                get_entry_name(dwarf, entry)
+                get_entry_name(dwarf, unit, entry)
             })? {
                 Ok(name)
             } else {
                 Err(Error::NameAttributeNotFound)
             }
         }
+
+        fn u_is_artificial(&self, unit: &CU) -> Result<bool, Error> {
+            unit.entry_context(&self.location(), |entry| {
+                matches!(entry.attr_value(gimli::DW_AT_artificial),
+                         Ok(Some(AttributeValue::Flag(true))))
+            })
+        }
+
+        fn u_linkage_name<D>(&self, dwarf: &D, unit: &CU)
+        -> Result<String, Error>
+        where D: DwarfContext + BorrowableDwarf {
+            if let Some(name) = unit.entry_context(&self.location(), |entry| {
+                get_entry_linkage_name(dwarf, unit, entry)
+            })? {
+                Ok(name)
+            } else {
+                Err(Error::LinkageNameAttributeNotFound)
+            }
+        }
     }
 }
 
@@ -302,6 +1175,24 @@ pub trait NamedType : unit_name_type::UnitNamedType {
             self.u_name(dwarf, unit)
         })?
     }
+
+    /// Whether the entry was generated by the compiler rather than declared
+    /// in the source, e.g. the C++ `this` parameter or a vtable pointer
+    fn is_artificial<D>(&self, dwarf: &D) -> Result<bool, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_is_artificial(unit)
+        })?
+    }
+
+    /// The mangled name (DW_AT_linkage_name), generally only present on C++
+    /// functions and variables
+    fn linkage_name<D>(&self, dwarf: &D) -> Result<String, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_linkage_name(dwarf, unit)
+        })?
+    }
 }
 
 macro_rules! impl_named_type {
@@ -326,7 +1217,11 @@ impl_named_type!(Const);
 impl_named_type!(Volatile);
 impl_named_type!(Restrict);
 impl_named_type!(Variable);
+impl_named_type!(Subprogram);
+impl_named_type!(FormalParameter);
 impl_named_type!(Member);
+impl_named_type!(Enumerator);
+impl_named_type!(CompileUnit);
 
 
 /// This trait specifies that a type is associated with some DWARF tag
@@ -361,6 +1256,10 @@ impl_tagged_type!(Const, gimli::DW_TAG_const_type);
 impl_tagged_type!(Volatile, gimli::DW_TAG_volatile_type);
 impl_tagged_type!(Restrict, gimli::DW_TAG_restrict_type);
 impl_tagged_type!(Variable, gimli::DW_TAG_variable);
+impl_tagged_type!(Subprogram, gimli::DW_TAG_subprogram);
+impl_tagged_type!(Enumerator, gimli::DW_TAG_enumerator);
+impl_tagged_type!(Subrange, gimli::DW_TAG_subrange_type);
+impl_tagged_type!(CompileUnit, gimli::DW_TAG_compile_unit);
 
 
 /// force UnitInnerType trait to be private
@@ -386,6 +1285,17 @@ pub(crate) mod unit_inner_type {
                                 entry_to_type(type_loc, entry)
                             })?
                         }
+                        // the type lives in another unit, only resolvable
+                        // with access to the whole Dwarf
+                        if let AttributeValue::DebugTypesRef(signature) =
+                        attr.value() {
+                            return Err(Error::TypeUnitReference(signature))
+                        }
+                        // or in the supplementary file
+                        if let AttributeValue::DebugInfoRefSup(offset) =
+                        attr.value() {
+                            return Err(Error::SupplementaryReference(offset))
+                        }
                     };
                 };
                 Err(Error::TypeAttributeNotFound)
@@ -398,10 +1308,68 @@ pub(crate) mod unit_inner_type {
 pub trait InnerType : unit_inner_type::UnitInnerType {
     fn get_type<D>(&self, dwarf: &D) -> Result<Type, Error>
     where D: DwarfContext + BorrowableDwarf {
-        dwarf.unit_context(&self.location().clone(), |unit| {
+        let typ = dwarf.unit_context(&self.location().clone(), |unit| {
             self.u_get_type(unit)
-        })?
+        })?;
+        resolve_type(dwarf, typ)
+    }
+}
+
+// Follow a reference into a type unit or the supplementary file and then apply
+// the unknown tag policy of `dwarf` to the result of a `u_get_type` call
+pub(crate) fn resolve_type<D>(dwarf: &D, typ: Result<Type, Error>)
+-> Result<Type, Error>
+where D: DwarfContext + BorrowableDwarf {
+    dwarf.unknown_tag_policy().apply(follow_type_reference(dwarf, typ))
+}
+
+// Follow a reference into a type unit or the supplementary file left by a
+// `u_get_type` call, other results are passed through
+fn follow_type_reference<D>(dwarf: &D, typ: Result<Type, Error>)
+-> Result<Type, Error>
+where D: DwarfContext + BorrowableDwarf {
+    let type_loc = match typ {
+        Err(Error::TypeUnitReference(signature)) => {
+            dwarf.resolve_type_signature(signature)?
+        },
+        Err(Error::SupplementaryReference(offset)) => {
+            dwarf.resolve_sup_reference(offset)?
+        },
+        typ => return typ
+    };
+    dwarf.entry_context(&type_loc, |entry| {
+        entry_to_type(type_loc, entry)
+    })?
+}
+
+// Get the type `inner` refers to from within `unit`, references into a type
+// unit or the supplementary file are followed so the type may be in another
+// unit, query it through `u_unit_of`
+pub(crate) fn u_follow_type<D, T>(dwarf: &D, unit: &CU, inner: &T)
+-> Result<Type, Error>
+where D: DwarfContext + BorrowableDwarf, T: UnitInnerType {
+    follow_type_reference(dwarf, inner.u_get_type(unit))
+}
+
+// Call `f` with the unit containing `location`, which is `unit` itself unless
+// the location was reached by following a reference out of it
+pub(crate) fn u_unit_of<D, F, R>(dwarf: &D, unit: &CU, location: Location,
+                                 f: F)
+-> Result<R, Error>
+where D: DwarfContext, F: FnOnce(&CU) -> Result<R, Error> {
+    if location.header == unit.header.offset() {
+        return f(unit)
     }
+    dwarf.unit_context(&location, f)?
+}
+
+// Call `f` with the type `inner` refers to and the unit containing it
+pub(crate) fn u_with_type<D, T, F, R>(dwarf: &D, unit: &CU, inner: &T, f: F)
+-> Result<R, Error>
+where D: DwarfContext + BorrowableDwarf, T: UnitInnerType,
+      F: FnOnce(Type, &CU) -> Result<R, Error> {
+    let typ = u_follow_type(dwarf, unit, inner)?;
+    u_unit_of(dwarf, unit, typ.location(), |unit| f(typ, unit))
 }
 
 macro_rules! impl_inner_type {
@@ -422,12 +1390,62 @@ impl_inner_type!(FormalParameter);
 impl_inner_type!(Subroutine);
 impl_inner_type!(Pointer);
 impl_inner_type!(Variable);
+impl_inner_type!(Subprogram);
 impl_inner_type!(Typedef);
 impl_inner_type!(Array);
 impl_inner_type!(Enum);
 impl_inner_type!(Member);
 
 
+// The byte order of the section data backing the unit containing `location`
+fn unit_endianness(unit: &CU, location: Location) -> RunTimeEndian {
+    match unit.header.range_from(location.offset..) {
+        Ok(reader) => gimli::Reader::endian(&reader),
+        Err(_) => RunTimeEndian::default()
+    }
+}
+
+// a constant array bound, which may be signed (e.g. Fortran's a(-2:2))
+fn get_entry_bound(entry: &DIE, name: gimli::DwAt) -> Option<i64> {
+    match entry.attr_value(name) {
+        Ok(Some(AttributeValue::Sdata(value))) => Some(value),
+        Ok(Some(value)) => value.udata_value().map(|v| v as i64),
+        _ => None
+    }
+}
+
+// an unsigned constant attribute
+fn get_entry_udata(entry: &DIE, name: gimli::DwAt) -> Option<u64> {
+    match entry.attr_value(name) {
+        Ok(Some(value)) => value.udata_value(),
+        _ => None
+    }
+}
+
+// The lower bound of array subranges without a DW_AT_lower_bound, this
+// depends on the language of the unit
+fn unit_default_lower_bound(unit: &CU) -> i64 {
+    let mut entries = unit.entries();
+    let language = match entries.next_dfs() {
+        Ok(Some((_, entry))) => entry.attr_value(gimli::DW_AT_language),
+        _ => return 0
+    };
+    let language = match language {
+        Ok(Some(AttributeValue::Language(language))) => language,
+        _ => return 0
+    };
+    match language {
+        gimli::DW_LANG_Ada83 | gimli::DW_LANG_Ada95 |
+        gimli::DW_LANG_Cobol74 | gimli::DW_LANG_Cobol85 |
+        gimli::DW_LANG_Fortran77 | gimli::DW_LANG_Fortran90 |
+        gimli::DW_LANG_Fortran95 | gimli::DW_LANG_Fortran03 |
+        gimli::DW_LANG_Fortran08 | gimli::DW_LANG_Modula2 |
+        gimli::DW_LANG_Modula3 | gimli::DW_LANG_Pascal83 |
+        gimli::DW_LANG_PLI | gimli::DW_LANG_Julia => 1,
+        _ => 0
+    }
+}
+
 fn get_entry_bit_size(entry: &DIE) -> Option<usize> {
     let mut attrs = entry.attrs();
     while let Ok(Some(attr)) = &attrs.next() {
@@ -448,6 +1466,62 @@ fn get_entry_byte_size(entry: &DIE) -> Option<usize> {
     None
 }
 
+// Try to retrieve the constant value attribute if one exists, gimli leaves
+// fixed size forms as-is since their signedness is ambiguous, so they are
+// treated as unsigned here
+fn get_entry_const_value(entry: &DIE) -> Option<i64> {
+    get_entry_constant(entry, gimli::DW_AT_const_value)
+}
+
+// Read an integer constant attribute, unsigned values are reinterpreted
+fn get_entry_constant(entry: &DIE, name: gimli::DwAt) -> Option<i64> {
+    let mut attrs = entry.attrs();
+    while let Ok(Some(attr)) = &attrs.next() {
+        if attr.name() == name {
+            return match attr.value() {
+                AttributeValue::Sdata(v) => Some(v),
+                AttributeValue::Udata(v) => Some(v as i64),
+                AttributeValue::Data1(v) => Some(v as i64),
+                AttributeValue::Data2(v) => Some(v as i64),
+                AttributeValue::Data4(v) => Some(v as i64),
+                AttributeValue::Data8(v) => Some(v as i64),
+                _ => None
+            }
+        }
+    }
+    None
+}
+
+// The locations of the children of the entry at `location` with `tag`
+fn u_child_locations(unit: &CU, location: Location, tag: gimli::DwTag)
+-> Result<Vec<Location>, Error> {
+    let mut tree = match unit.entries_tree(Some(location.offset)) {
+        Ok(tree) => tree,
+        _ => return Err(Error::DIEError(
+            format!("Failed to seek to DIE at {:?}", location)
+        ))
+    };
+    let root = match tree.root() {
+        Ok(root) => root,
+        _ => return Err(Error::DIEError(
+            format!("Failed to find next DIE at {:?}", location)
+        ))
+    };
+
+    let mut locations: Vec<Location> = vec![];
+    let mut children = root.children();
+    while let Ok(Some(child)) = children.next() {
+        let entry = child.entry();
+        if entry.tag() == tag {
+            locations.push(Location {
+                header: location.header,
+                offset: entry.offset()
+            });
+        }
+    }
+    Ok(locations)
+}
+
 // Try to retrieve the alignment attribute if one exists, alignment was added
 // in DWARF 5 but gcc will inlcude it even for -gdwarf-4
 fn get_entry_alignment(entry: &DIE) -> Option<usize> {
@@ -462,37 +1536,9 @@ fn get_entry_alignment(entry: &DIE) -> Option<usize> {
 
 
 impl Subroutine {
-    fn location(&self) -> Location {
-        self.location
-    }
-
     pub(crate) fn u_get_params(&self, unit: &CU)
     -> Result<Vec<FormalParameter>, Error> {
-        let mut params: Vec<FormalParameter> = vec![];
-        let mut entries = {
-            match unit.entries_at_offset(self.location.offset) {
-                Ok(entries) => entries,
-                _ => return Err(Error::DIEError(
-                   format!("Failed to seek to DIE at {:?}", self.location())
-                ))
-            }
-        };
-        if entries.next_dfs().is_err() {
-            return Err(Error::DIEError(
-               format!("Failed to find next DIE at {:?}", self.location())
-            ))
-        }
-        while let Ok(Some((_, entry))) = entries.next_dfs() {
-            if entry.tag() != gimli::DW_TAG_formal_parameter {
-                break;
-            }
-            let location = Location {
-                header: self.location.header,
-                offset: entry.offset(),
-            };
-            params.push(FormalParameter { location });
-        };
-        Ok(params)
+        u_formal_parameters(unit, self.location)
     }
 
     pub fn get_params<D: DwarfContext>(&self, dwarf: &D)
@@ -503,83 +1549,819 @@ impl Subroutine {
     }
 }
 
-fn entry_to_type(location: Location, entry: &DIE) -> Result<Type, Error> {
-    let tag = match entry.tag() {
-        gimli::DW_TAG_array_type => {
-            Type::Array(Array{location})
-        },
-        gimli::DW_TAG_enumeration_type => {
-            Type::Enum(Enum{location})
-        },
-        gimli::DW_TAG_pointer_type => {
-            Type::Pointer(Pointer{location})
-        },
-        gimli::DW_TAG_structure_type => {
-            Type::Struct(Struct{location})
-        },
-        gimli::DW_TAG_subroutine_type => {
-            Type::Subroutine(Subroutine{location})
-        },
-        gimli::DW_TAG_typedef => {
-            Type::Typedef(Typedef{location})
-        },
-        gimli::DW_TAG_union_type => {
-            Type::Union(Union{location})
-        },
-        gimli::DW_TAG_base_type => {
-            Type::Base(Base{location})
-        },
-        gimli::DW_TAG_const_type => {
-            Type::Const(Const{location})
-        },
-        gimli::DW_TAG_volatile_type => {
-            Type::Volatile(Volatile{location})
-        },
-        gimli::DW_TAG_restrict_type => {
-            Type::Restrict(Restrict{location})
-        },
-        _ => {
-            return Err(Error::UnimplementedError(
-                    "entry_to_type, unhandled dwarf type".to_string()
-            ));
-        }
-    };
-    Ok(tag)
-}
+impl CompileUnit {
+    // The compile unit whose root entry heads `unit`
+    pub(crate) fn from_unit(unit: &CU) -> Self {
+        let location = Location {
+            header: unit.header.offset(),
+            offset: gimli::UnitOffset(unit.header.header_size()),
+        };
+        Self { location }
+    }
 
-impl Member {
-    pub(crate) fn u_bit_size(&self, unit: &CU) -> Result<usize, Error> {
-        let bit_size = unit.entry_context(&self.location, |entry| {
-            get_entry_bit_size(entry)
-        })?;
-        if let Some(bit_size) = bit_size {
-            Ok(bit_size)
-        } else {
-            Err(Error::BitSizeAttributeNotFound)
+    pub(crate) fn u_comp_dir(&self, unit: &CU) -> Result<String, Error> {
+        match &unit.comp_dir {
+            Some(comp_dir) => Ok(comp_dir.to_string_lossy().into_owned()),
+            None => Err(Error::CompDirAttributeNotFound)
         }
     }
 
-    pub fn bit_size<D>(&self, dwarf: &D) -> Result<usize, Error>
+    /// The directory the unit was compiled in, the DW_AT_comp_dir
+    pub fn comp_dir<D>(&self, dwarf: &D) -> Result<String, Error>
     where D: DwarfContext {
         dwarf.unit_context(&self.location, |unit| {
-            self.u_bit_size(unit)
+            self.u_comp_dir(unit)
         })?
     }
 
-    pub(crate) fn u_byte_size(&self, unit: &CU) -> Result<usize, Error> {
-        let inner = self.u_get_type(unit)?;
-        inner.u_byte_size(unit)
+    pub(crate) fn u_producer<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<String, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let producer = unit.entry_context(&self.location, |entry| {
+            get_entry_string(dwarf, unit, entry, gimli::DW_AT_producer)
+        })?;
+        producer.ok_or(Error::ProducerAttributeNotFound)
     }
 
-    pub fn byte_size<D>(&self, dwarf: &D) -> Result<usize, Error>
-    where D: DwarfContext {
+    /// The DW_AT_producer of the unit, the compiler that built it, e.g.
+    /// `GNU C17 13.2.0 -mtune=generic -march=x86-64 -g -O2`
+    pub fn producer<D>(&self, dwarf: &D) -> Result<String, Error>
+    where D: DwarfContext + BorrowableDwarf {
         dwarf.unit_context(&self.location, |unit| {
-            self.u_byte_size(unit)
+            self.u_producer(dwarf, unit)
         })?
     }
 
-    pub(crate) fn u_member_location(&self, unit: &CU) -> Result<usize, Error> {
-        let member_location = unit.entry_context(&self.location, |entry| {
+    pub(crate) fn u_low_pc(&self, unit: &CU) -> Result<Option<u64>, Error> {
+        let has_low_pc = unit.entry_context(&self.location, |entry| {
+            matches!(entry.attr_value(gimli::DW_AT_low_pc), Ok(Some(_)))
+        })?;
+        // gimli already resolved the base address, including DW_FORM_addrx
+        match has_low_pc {
+            true => Ok(Some(unit.low_pc)),
+            false => Ok(None)
+        }
+    }
+
+    /// The DW_AT_low_pc of the unit, the base address that range lists and
+    /// location lists in it are relative to, None for units without code
+    pub fn low_pc<D>(&self, dwarf: &D) -> Result<Option<u64>, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_low_pc(unit)
+        })?
+    }
+
+    /// The size of an address in the unit in bytes, see `pointer_size` for
+    /// the size shared by every unit
+    pub fn address_size<D>(&self, dwarf: &D) -> Result<usize, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            unit.header.address_size() as usize
+        })
+    }
+
+    /// The command line options recorded in the producer, the tokens of it
+    /// which start with `-`. GCC records the options by default, clang only
+    /// with `-grecord-command-line`
+    pub fn producer_flags<D>(&self, dwarf: &D) -> Result<Vec<String>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let producer = self.producer(dwarf)?;
+        Ok(producer.split_whitespace()
+                   .filter(|token| token.starts_with('-'))
+                   .map(|token| token.to_string())
+                   .collect())
+    }
+
+    pub(crate) fn u_source_files<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<Vec<String>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let comp_dir = match self.u_comp_dir(unit) {
+            Ok(comp_dir) => Some(comp_dir),
+            Err(Error::CompDirAttributeNotFound) => None,
+            Err(e) => return Err(e)
+        };
+        let header = match &unit.line_program {
+            Some(program) => program.header(),
+            None => return Ok(vec![])
+        };
+
+        let mut files: Vec<String> = vec![];
+        for file in header.file_names() {
+            let path = match u_file_entry_path(dwarf, unit, header, file) {
+                Some(path) => path,
+                None => continue
+            };
+            let path = match &comp_dir {
+                Some(comp_dir) if !path.starts_with('/') => {
+                    format!("{comp_dir}/{path}")
+                },
+                _ => path
+            };
+            // DWARF 5 lists the primary source file twice
+            if !files.contains(&path) {
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+
+    /// The paths of every file in the unit's line program, relative paths are
+    /// made absolute by joining them to the `comp_dir`
+    pub fn source_files<D>(&self, dwarf: &D) -> Result<Vec<String>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_source_files(dwarf, unit)
+        })?
+    }
+}
+
+impl Variable {
+    pub(crate) fn u_const_value_bytes(&self, unit: &CU)
+    -> Result<Option<Vec<u8>>, Error> {
+        unit.entry_context(&self.location, |entry| {
+            match entry.attr_value(gimli::DW_AT_const_value) {
+                Ok(Some(AttributeValue::Block(block))) => {
+                    Some(block.to_vec())
+                },
+                _ => None
+            }
+        })
+    }
+
+    /// The raw bytes of a block form DW_AT_const_value, as used for constants
+    /// that aren't integers (e.g. structs, arrays and floats), these are laid
+    /// out according to the variable's type, `None` if the constant value
+    /// isn't a block
+    pub fn const_value_bytes<D>(&self, dwarf: &D)
+    -> Result<Option<Vec<u8>>, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_const_value_bytes(unit)
+        })?
+    }
+
+    /// Format the variable as a C declaration, e.g. `int counts[4];`, it is
+    /// marked `extern` when the unit only declares it
+    pub fn to_string<D>(&self, dwarf: &D) -> Result<String, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            format_variable(dwarf, unit, *self)
+        })?
+    }
+}
+
+impl FunctionSignature {
+    /// Copy the signature into an owned `SignatureSnapshot`, which can be
+    /// serialized with the `serde` feature
+    pub fn snapshot<D>(&self, dwarf: &D) -> Result<SignatureSnapshot, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let return_type = match self.return_type {
+            Some(return_type) => Some(return_type.type_name(dwarf)?),
+            None => None
+        };
+        let params = self.params.iter().map(|(name, typ)| {
+            Ok((name.clone(), typ.type_name(dwarf)?))
+        }).collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(SignatureSnapshot {
+            name: self.name.clone(),
+            return_type,
+            params,
+            is_variadic: self.is_variadic
+        })
+    }
+}
+
+impl Subprogram {
+    pub(crate) fn u_get_params(&self, unit: &CU)
+    -> Result<Vec<FormalParameter>, Error> {
+        u_formal_parameters(unit, self.location)
+    }
+
+    /// The parameters of the function, unlike those of a Subroutine type
+    /// these are usually named
+    pub fn get_params<D: DwarfContext>(&self, dwarf: &D)
+    -> Result<Vec<FormalParameter>, Error> {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_get_params(unit)
+        })?
+    }
+
+    pub(crate) fn u_decl_file<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<String, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        u_decl_file(dwarf, unit, self.location)
+    }
+
+    /// The path of the source file the function is declared in
+    pub fn decl_file<D>(&self, dwarf: &D) -> Result<String, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_decl_file(dwarf, unit)
+        })?
+    }
+
+    pub(crate) fn u_decl_line(&self, unit: &CU) -> Result<usize, Error> {
+        u_decl_line(unit, self.location)
+    }
+
+    pub(crate) fn u_virtuality(&self, unit: &CU)
+    -> Result<gimli::DwVirtuality, Error> {
+        unit.entry_context(&self.location, |entry| {
+            match entry.attr_value(gimli::DW_AT_virtuality) {
+                Ok(Some(AttributeValue::Virtuality(virtuality))) => virtuality,
+                _ => gimli::DW_VIRTUALITY_none
+            }
+        })
+    }
+
+    /// Whether a C++ method is DW_VIRTUALITY_virtual or
+    /// DW_VIRTUALITY_pure_virtual, DW_VIRTUALITY_none otherwise
+    pub fn virtuality<D>(&self, dwarf: &D)
+    -> Result<gimli::DwVirtuality, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_virtuality(unit)
+        })?
+    }
+
+    pub(crate) fn u_is_variadic(&self, unit: &CU) -> Result<bool, Error> {
+        let mut tree = match unit.entries_tree(Some(self.location.offset)) {
+            Ok(tree) => tree,
+            _ => return Err(Error::DIEError(
+                format!("Failed to seek to DIE at {:?}", self.location)
+            ))
+        };
+        let root = match tree.root() {
+            Ok(root) => root,
+            _ => return Err(Error::DIEError(
+                format!("Failed to find next DIE at {:?}", self.location)
+            ))
+        };
+        let mut children = root.children();
+        while let Ok(Some(child)) = children.next() {
+            if child.entry().tag() == gimli::DW_TAG_unspecified_parameters {
+                return Ok(true)
+            }
+        }
+        Ok(false)
+    }
+
+    /// Whether the function takes variable arguments, i.e. its parameters
+    /// end with DW_TAG_unspecified_parameters
+    pub fn is_variadic<D>(&self, dwarf: &D) -> Result<bool, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_is_variadic(unit)
+        })?
+    }
+
+    pub(crate) fn u_signature<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<FunctionSignature, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let return_type = match resolve_type(dwarf, self.u_get_type(unit)) {
+            Ok(return_type) => Some(return_type),
+            Err(Error::TypeAttributeNotFound) => None,
+            Err(e) => return Err(e)
+        };
+
+        let mut params: Vec<(Option<String>, Type)> = vec![];
+        for param in self.u_get_params(unit)? {
+            let name = optional_name(param.u_name(dwarf, unit))?;
+            params.push((name, resolve_type(dwarf, param.u_get_type(unit))?));
+        }
+
+        Ok(FunctionSignature {
+            name: self.u_name(dwarf, unit)?,
+            return_type,
+            params,
+            is_variadic: self.u_is_variadic(unit)?
+        })
+    }
+
+    /// The return type, parameters and variadicness of the function
+    pub fn signature<D>(&self, dwarf: &D) -> Result<FunctionSignature, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_signature(dwarf, unit)
+        })?
+    }
+
+    /// The C declaration of the function including parameter names, e.g.
+    /// `int sum(int vals[4], ...)`, array parameters keep their bound when
+    /// the DWARF types them as arrays
+    pub fn prototype<D>(&self, dwarf: &D) -> Result<String, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            format_prototype(dwarf, unit, *self)
+        })?
+    }
+
+    // The parameter holding `this`, named by DW_AT_object_pointer or otherwise
+    // the first parameter when it is artificial
+    fn u_object_pointer(&self, unit: &CU)
+    -> Result<Option<FormalParameter>, Error> {
+        let object_pointer = unit.entry_context(&self.location, |entry| {
+            match entry.attr_value(gimli::DW_AT_object_pointer) {
+                Ok(Some(AttributeValue::UnitRef(offset))) => Some(offset),
+                _ => None
+            }
+        })?;
+        if let Some(offset) = object_pointer {
+            let location = Location { header: self.location.header, offset };
+            return Ok(Some(FormalParameter { location }))
+        }
+
+        match self.u_get_params(unit)?.first() {
+            Some(param) if param.u_is_artificial(unit)? => Ok(Some(*param)),
+            _ => Ok(None)
+        }
+    }
+
+    pub(crate) fn u_is_method(&self, unit: &CU) -> Result<bool, Error> {
+        Ok(self.u_object_pointer(unit)?.is_some())
+    }
+
+    /// Whether the function is a non-static C++ member function, i.e. it
+    /// takes an implicit `this`
+    pub fn is_method<D>(&self, dwarf: &D) -> Result<bool, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_is_method(unit)
+        })?
+    }
+
+    pub(crate) fn u_this_type<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<Option<Type>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let this = match self.u_object_pointer(unit)? {
+            Some(this) => this,
+            None => return Ok(None)
+        };
+        let this_type = resolve_type(dwarf, this.u_get_type(unit))?;
+        let ptr = u_unit_of(dwarf, unit, this_type.location(), |unit| {
+            this_type.u_strip_cv_typedefs(dwarf, unit)
+        })?;
+        let ptr = match ptr {
+            Type::Pointer(ptr) => ptr,
+            _ => return Ok(None)
+        };
+        u_unit_of(dwarf, unit, ptr.location(), |unit| {
+            let class = resolve_type(dwarf, ptr.u_get_type(unit))?;
+            u_unit_of(dwarf, unit, class.location(), |unit| {
+                Ok(Some(class.u_strip_cv_typedefs(dwarf, unit)?))
+            })
+        })
+    }
+
+    /// The class `this` points to for C++ member functions, with any
+    /// cv-qualifiers of `this` (e.g. of const methods) stripped
+    pub fn this_type<D>(&self, dwarf: &D) -> Result<Option<Type>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_this_type(dwarf, unit)
+        })?
+    }
+
+    // The vtable slot of a virtual method, DW_AT_vtable_elem_location is an
+    // expression which gcc and clang emit as a single DW_OP_constu
+    pub(crate) fn u_vtable_slot(&self, unit: &CU)
+    -> Result<Option<usize>, Error> {
+        unit.entry_context(&self.location, |entry| {
+            let slot = entry.attr_value(gimli::DW_AT_vtable_elem_location);
+            let expr = match slot {
+                Ok(Some(AttributeValue::Exprloc(expr))) => expr,
+                Ok(Some(AttributeValue::Block(data))) => {
+                    gimli::Expression(data)
+                },
+                Ok(Some(value)) => {
+                    return value.udata_value().map(|slot| slot as usize)
+                },
+                _ => return None
+            };
+            match expr.operations(unit.encoding()).next() {
+                Ok(Some(gimli::Operation::UnsignedConstant { value })) => {
+                    Some(value as usize)
+                },
+                _ => None
+            }
+        })
+    }
+
+    /// The line of the source file the function is declared on
+    pub fn decl_line<D>(&self, dwarf: &D) -> Result<usize, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_decl_line(unit)
+        })?
+    }
+}
+
+// The DW_AT_decl_line of the DIE at `location`
+fn u_decl_line(unit: &CU, location: Location) -> Result<usize, Error> {
+    let line = unit.entry_context(&location, |entry| {
+        match entry.attr_value(gimli::DW_AT_decl_line) {
+            Ok(Some(value)) => value.udata_value(),
+            _ => None
+        }
+    })?;
+    match line {
+        Some(line) => Ok(line as usize),
+        None => Err(Error::DeclLineAttributeNotFound)
+    }
+}
+
+// The path of the file named by the DW_AT_decl_file of the DIE at `location`,
+// the index is resolved through the file table of the unit's line program
+fn u_decl_file<D>(dwarf: &D, unit: &CU, location: Location)
+-> Result<String, Error>
+where D: DwarfContext + BorrowableDwarf {
+    let index = unit.entry_context(&location, |entry| {
+        match entry.attr_value(gimli::DW_AT_decl_file) {
+            Ok(Some(AttributeValue::FileIndex(index))) => Some(index),
+            Ok(Some(value)) => value.udata_value(),
+            _ => None
+        }
+    })?;
+    let index = match index {
+        Some(index) => index,
+        None => return Err(Error::DeclFileAttributeNotFound)
+    };
+
+    let header = match &unit.line_program {
+        Some(program) => program.header(),
+        None => return Err(Error::DeclFileAttributeNotFound)
+    };
+    let file = match header.file(index) {
+        Some(file) => file,
+        None => return Err(Error::DeclFileAttributeNotFound)
+    };
+    match u_file_entry_path(dwarf, unit, header, file) {
+        Some(path) => Ok(path),
+        None => Err(Error::DeclFileAttributeNotFound)
+    }
+}
+
+// The path of an entry in the file table of a line program, prefixed with the
+// entry's include directory unless the path is already absolute
+fn u_file_entry_path<D>(dwarf: &D, unit: &CU,
+                        header: &gimli::LineProgramHeader<R>,
+                        file: &gimli::FileEntry<R>)
+-> Option<String>
+where D: DwarfContext + BorrowableDwarf {
+    dwarf.borrow_dwarf(|gimli_dwarf| {
+        let to_string = |value| -> Option<String> {
+            let value = gimli_dwarf.attr_string(unit, value).ok()?;
+            Some(value.to_string_lossy().into_owned())
+        };
+        let name = to_string(file.path_name())?;
+        if name.starts_with('/') {
+            return Some(name)
+        }
+        match file.directory(header).and_then(to_string) {
+            Some(dir) => Some(format!("{dir}/{name}")),
+            None => Some(name)
+        }
+    })
+}
+
+// Collect the formal parameters that are the leading children of the DIE at
+// `location`
+fn u_formal_parameters(unit: &CU, location: Location)
+-> Result<Vec<FormalParameter>, Error> {
+    let mut params: Vec<FormalParameter> = vec![];
+    let mut entries = {
+        match unit.entries_at_offset(location.offset) {
+            Ok(entries) => entries,
+            _ => return Err(Error::DIEError(
+               format!("Failed to seek to DIE at {:?}", location)
+            ))
+        }
+    };
+    if entries.next_dfs().is_err() {
+        return Err(Error::DIEError(
+           format!("Failed to find next DIE at {:?}", location)
+        ))
+    }
+    while let Ok(Some((_, entry))) = entries.next_dfs() {
+        if entry.tag() != gimli::DW_TAG_formal_parameter {
+            break;
+        }
+        let location = Location {
+            header: location.header,
+            offset: entry.offset(),
+        };
+        params.push(FormalParameter { location });
+    };
+    Ok(params)
+}
+
+impl FormalParameter {
+    pub(crate) fn u_has_default_value(&self, unit: &CU)
+    -> Result<bool, Error> {
+        unit.entry_context(&self.location, |entry| {
+            matches!(entry.attr(gimli::DW_AT_default_value), Ok(Some(_)))
+        })
+    }
+
+    /// Whether the parameter has a (C++) default argument
+    pub fn has_default_value<D>(&self, dwarf: &D) -> Result<bool, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_has_default_value(unit)
+        })?
+    }
+}
+
+pub(crate) fn entry_to_type(location: Location, entry: &DIE) -> Result<Type, Error> {
+    let tag = match entry.tag() {
+        gimli::DW_TAG_array_type => {
+            Type::Array(Array{location})
+        },
+        gimli::DW_TAG_enumeration_type => {
+            Type::Enum(Enum{location})
+        },
+        gimli::DW_TAG_pointer_type => {
+            Type::Pointer(Pointer{location})
+        },
+        gimli::DW_TAG_structure_type => {
+            Type::Struct(Struct{location})
+        },
+        gimli::DW_TAG_subroutine_type => {
+            Type::Subroutine(Subroutine{location})
+        },
+        gimli::DW_TAG_typedef => {
+            Type::Typedef(Typedef{location})
+        },
+        gimli::DW_TAG_union_type => {
+            Type::Union(Union{location})
+        },
+        gimli::DW_TAG_base_type => {
+            Type::Base(Base{location})
+        },
+        gimli::DW_TAG_const_type => {
+            Type::Const(Const{location})
+        },
+        gimli::DW_TAG_volatile_type => {
+            Type::Volatile(Volatile{location})
+        },
+        gimli::DW_TAG_restrict_type => {
+            Type::Restrict(Restrict{location})
+        },
+        tag => return Err(Error::UnknownTagError(tag, location))
+    };
+    Ok(tag)
+}
+
+// Find the member called `name` in the aggregate `typ`, descending into
+// anonymous struct/union members, the returned bit offset is that of the
+// member's parent relative to `typ`
+fn u_find_member<D>(dwarf: &D, unit: &CU, typ: Type, name: &str)
+-> Result<Option<(Member, usize)>, Error>
+where D: DwarfContext + BorrowableDwarf {
+    let stripped = typ.u_strip_cv_typedefs(dwarf, unit)?;
+    u_unit_of(dwarf, unit, stripped.location(), |unit| {
+        let members = match stripped {
+            Type::Struct(s) => s.u_members(unit)?,
+            Type::Union(u) => u.u_members(unit)?,
+            _ => return Ok(None)
+        };
+        u_find_member_in(dwarf, unit, members, name)
+    })
+}
+
+// Like u_find_member, searching the given members of an aggregate
+fn u_find_member_in<D>(dwarf: &D, unit: &CU, members: Vec<Member>,
+                       name: &str)
+-> Result<Option<(Member, usize)>, Error>
+where D: DwarfContext + BorrowableDwarf {
+    for member in members.into_iter() {
+        match member.u_name(dwarf, unit) {
+            Ok(member_name) => {
+                if member_name == name {
+                    return Ok(Some((member, 0)));
+                }
+            },
+            Err(Error::NameAttributeNotFound) => {
+                let found = u_with_type(dwarf, unit, &member, |inner, unit| {
+                    u_find_member(dwarf, unit, inner, name)
+                })?;
+                if let Some((found, offset)) = found {
+                    let offset = {
+                        member.u_data_bit_offset(dwarf, unit)? + offset
+                    };
+                    return Ok(Some((found, offset)));
+                }
+            },
+            Err(e) => return Err(e)
+        }
+    }
+    Ok(None)
+}
+
+// The deepest nesting of aggregates followed by `flat_offset_table`
+const MAX_FLATTEN_DEPTH: usize = 64;
+
+// Append a `(dotted_name, absolute_offset, size)` entry for every leaf member
+// of the aggregate `typ` to `table`, `visited` holds the aggregates currently
+// being descended into so that malformed self-containing types terminate
+#[allow(clippy::too_many_arguments)]
+fn u_flatten_members<D>(dwarf: &D, unit: &CU, typ: Type, prefix: &str,
+                        base_offset: usize, depth: usize,
+                        visited: &mut HashSet<Location>,
+                        table: &mut Vec<(String, usize, usize)>)
+-> Result<(), Error>
+where D: DwarfContext + BorrowableDwarf {
+    let members = match typ {
+        Type::Struct(s) => s.u_members(unit)?,
+        Type::Union(u) => u.u_members(unit)?,
+        _ => return Ok(())
+    };
+    for member in members.into_iter() {
+        // anonymous members don't add a component to the path
+        let path = match member.u_name(dwarf, unit) {
+            Ok(name) if prefix.is_empty() => name,
+            Ok(name) => format!("{prefix}.{name}"),
+            Err(Error::NameAttributeNotFound) => prefix.to_string(),
+            Err(e) => return Err(e)
+        };
+        let offset = base_offset + member.u_data_bit_offset(dwarf, unit)? / 8;
+
+        let inner = u_with_type(dwarf, unit, &member, |inner, unit| {
+            inner.u_strip_cv_typedefs(dwarf, unit)
+        });
+        let inner = match inner {
+            Ok(inner) => Some(inner),
+            Err(Error::TypeAttributeNotFound) => None,
+            Err(e) => return Err(e)
+        };
+        if let Some(inner @ (Type::Struct(_) | Type::Union(_))) = inner {
+            let location = inner.location();
+            if depth < MAX_FLATTEN_DEPTH && visited.insert(location) {
+                u_unit_of(dwarf, unit, location, |unit| {
+                    u_flatten_members(dwarf, unit, inner, &path, offset,
+                                      depth+1, visited, table)
+                })?;
+                visited.remove(&location);
+                continue;
+            }
+        }
+
+        let size = match member.u_byte_size(dwarf, unit) {
+            Ok(size) => size,
+            Err(Error::TypeAttributeNotFound) => 0,
+            Err(e) => return Err(e)
+        };
+        table.push((path, offset, size));
+    }
+    Ok(())
+}
+
+// Append a `ScalarField` for every scalar making up a value of type `typ` at
+// `offset` named `path` to `fields`, pointers are scalars so cycles only occur
+// in malformed self-containing types which `visited` guards against
+#[allow(clippy::too_many_arguments)]
+fn u_flatten_scalars<D>(dwarf: &D, unit: &CU, typ: Type, path: &str,
+                        offset: usize, expand_arrays: bool, depth: usize,
+                        visited: &mut HashSet<Location>,
+                        fields: &mut Vec<ScalarField>)
+-> Result<(), Error>
+where D: DwarfContext + BorrowableDwarf {
+    let stripped = typ.u_strip_cv_typedefs(dwarf, unit)?;
+    let expanded = u_unit_of(dwarf, unit, stripped.location(), |unit| {
+        match stripped {
+            Type::Struct(_) | Type::Union(_) => {
+                let location = stripped.location();
+                if depth < MAX_FLATTEN_DEPTH && visited.insert(location) {
+                    let members = match stripped {
+                        Type::Struct(s) => s.u_members(unit)?,
+                        Type::Union(u) => u.u_members(unit)?,
+                        _ => unreachable!()
+                    };
+                    for member in members.into_iter() {
+                        // anonymous members don't add a component to the path
+                        let path = match member.u_name(dwarf, unit) {
+                            Ok(name) if path.is_empty() => name,
+                            Ok(name) => format!("{path}.{name}"),
+                            Err(Error::NameAttributeNotFound) => {
+                                path.to_string()
+                            },
+                            Err(e) => return Err(e)
+                        };
+                        let offset = {
+                            offset + member.u_data_bit_offset(dwarf, unit)? / 8
+                        };
+                        let inner = match u_follow_type(dwarf, unit, &member) {
+                            Ok(inner) => inner,
+                            Err(Error::TypeAttributeNotFound) => continue,
+                            Err(e) => return Err(e)
+                        };
+                        u_unit_of(dwarf, unit, inner.location(), |unit| {
+                            u_flatten_scalars(dwarf, unit, inner, &path,
+                                              offset, expand_arrays, depth+1,
+                                              visited, fields)
+                        })?;
+                    }
+                    visited.remove(&location);
+                    return Ok(true)
+                }
+            },
+            Type::Array(array) if expand_arrays => {
+                let shape: Option<Vec<(i64, usize)>> = {
+                    array.u_dimensions(unit)?.iter().map(|dimension| {
+                        dimension.count.map(|count| {
+                            (dimension.lower_bound, count)
+                        })
+                    }).collect()
+                };
+                // arrays of unknown length are listed as a whole
+                if let Some(shape) = shape {
+                    let inner = u_follow_type(dwarf, unit, &array)?;
+                    let total: usize = {
+                        shape.iter().map(|(_, count)| count).product()
+                    };
+                    for index in 0..total {
+                        let mut subscripts = String::new();
+                        let mut rest = index;
+                        for (lower_bound, count) in shape.iter().rev() {
+                            let subscript = lower_bound + (rest % count) as i64;
+                            subscripts.insert_str(0,
+                                                  &format!("[{subscript}]"));
+                            rest /= count;
+                        }
+                        let element_offset = {
+                            array.u_element_offset(dwarf, unit, index)?
+                        };
+                        u_unit_of(dwarf, unit, inner.location(), |unit| {
+                            u_flatten_scalars(dwarf, unit, inner,
+                                              &format!("{path}{subscripts}"),
+                                              offset + element_offset,
+                                              expand_arrays, depth+1, visited,
+                                              fields)
+                        })?;
+                    }
+                    return Ok(true)
+                }
+            },
+            _ => ()
+        };
+        Ok(false)
+    })?;
+    if expanded {
+        return Ok(())
+    }
+
+    let size = match typ.u_byte_size(dwarf, unit) {
+        Ok(size) => size,
+        Err(Error::ByteSizeAttributeNotFound) => 0,
+        Err(e) => return Err(e)
+    };
+    fields.push(ScalarField {
+        path: path.to_string(),
+        offset,
+        type_name: format_type_name(dwarf, unit, typ)?,
+        size
+    });
+    Ok(())
+}
+
+impl Member {
+    pub(crate) fn u_bit_size(&self, unit: &CU) -> Result<usize, Error> {
+        let bit_size = unit.entry_context(&self.location, |entry| {
+            get_entry_bit_size(entry)
+        })?;
+        if let Some(bit_size) = bit_size {
+            Ok(bit_size)
+        } else {
+            Err(Error::BitSizeAttributeNotFound)
+        }
+    }
+
+    pub fn bit_size<D>(&self, dwarf: &D) -> Result<usize, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_bit_size(unit)
+        })?
+    }
+
+    pub(crate) fn u_byte_size<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        u_with_type(dwarf, unit, self, |inner, unit| {
+            inner.u_byte_size(dwarf, unit)
+        })
+    }
+
+    pub fn byte_size<D>(&self, dwarf: &D) -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_byte_size(dwarf, unit)
+        })?
+    }
+
+    pub(crate) fn u_member_location(&self, unit: &CU) -> Result<usize, Error> {
+        let member_location = unit.entry_context(&self.location, |entry| {
             let mut attrs = entry.attrs();
             while let Ok(Some(attr)) = &attrs.next() {
                 if attr.name() == gimli::DW_AT_data_member_location {
@@ -598,6 +2380,15 @@ impl Member {
         }
     }
 
+    /// The C spelling of the member's type, e.g. `struct foo *` or
+    /// `unsigned int [4]`
+    pub fn type_name<D>(&self, dwarf: &D) -> Result<String, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            format_inner_type_name(dwarf, unit, self)
+        })?
+    }
+
     /// The byte offset of the member from the start of the datatype
     pub fn member_location<D>(&self, dwarf: &D) -> Result<usize, Error>
     where D: DwarfContext {
@@ -615,6 +2406,198 @@ impl Member {
     where D: DwarfContext {
         self.member_location(dwarf)
     }
+
+    pub(crate) fn u_has_location(&self, unit: &CU) -> Result<bool, Error> {
+        unit.entry_context(&self.location, |entry| {
+            matches!(entry.attr(gimli::DW_AT_data_member_location),
+                     Ok(Some(_))) ||
+            matches!(entry.attr(gimli::DW_AT_data_bit_offset), Ok(Some(_)))
+        })
+    }
+
+    /// Whether the member has a location attribute at all, `offset` fails
+    /// for one which isn't a constant (e.g. a DWARF expression)
+    pub fn has_location<D>(&self, dwarf: &D) -> Result<bool, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_has_location(unit)
+        })?
+    }
+
+    pub(crate) fn u_annotation<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<Option<String>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        unit.entry_context(&self.location, |entry| {
+            let description = {
+                get_entry_string(dwarf, unit, entry, gimli::DW_AT_description)
+            };
+            if description.is_some() {
+                return description
+            }
+
+            // the GNU thread safety attributes name the lock guarding the
+            // member, spelled as the annotation was in the source
+            let vendor = [
+                (gimli::DW_AT_GNU_guarded_by, "guarded_by"),
+                (gimli::DW_AT_GNU_pt_guarded_by, "pt_guarded_by"),
+            ];
+            for (attr, spelling) in vendor {
+                if let Some(lock) = get_entry_string(dwarf, unit, entry, attr) {
+                    return Some(format!("{spelling}({lock})"))
+                }
+            }
+            None
+        })
+    }
+
+    /// The documentation attached to the member as a DW_AT_description, as
+    /// injected by some IDL compilers and firmware build systems, or failing
+    /// that its GNU vendor annotation (`DW_AT_GNU_guarded_by` or
+    /// `DW_AT_GNU_pt_guarded_by`, e.g. `guarded_by(lock)`), None for the usual
+    /// undocumented member
+    pub fn annotation<D>(&self, dwarf: &D) -> Result<Option<String>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_annotation(dwarf, unit)
+        })?
+    }
+
+    // The position of the member from the start of the datatype in bits,
+    // DWARF 5 bitfields only carry a DW_AT_data_bit_offset, DWARF 4 bitfields
+    // carry a DW_AT_bit_offset counted from the most significant bit of their
+    // storage unit, and union members carry no location at all
+    pub(crate) fn u_data_bit_offset<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let (data_bit_offset, bit_offset, storage_size) = {
+            unit.entry_context(&self.location, |entry| {
+                let mut data_bit_offset = None;
+                let mut bit_offset = None;
+                let mut attrs = entry.attrs();
+                while let Ok(Some(attr)) = &attrs.next() {
+                    match attr.name() {
+                        gimli::DW_AT_data_bit_offset => {
+                            data_bit_offset = attr.udata_value();
+                        },
+                        gimli::DW_AT_bit_offset => {
+                            bit_offset = attr.udata_value();
+                        },
+                        _ => {}
+                    }
+                }
+                (data_bit_offset, bit_offset, get_entry_byte_size(entry))
+            })?
+        };
+
+        if let Some(data_bit_offset) = data_bit_offset {
+            return Ok(data_bit_offset as usize)
+        }
+
+        let location = match self.u_member_location(unit) {
+            Ok(offset) => offset * 8,
+            Err(Error::MemberLocationAttributeNotFound) => 0,
+            Err(e) => return Err(e)
+        };
+
+        let bit_offset = match bit_offset {
+            Some(bit_offset) => bit_offset as usize,
+            None => return Ok(location)
+        };
+
+        // DW_AT_bit_offset numbers bits from the most significant bit, which
+        // is the first bit in memory only on big endian targets
+        if unit_endianness(unit, self.location) == RunTimeEndian::Big {
+            return Ok(location + bit_offset)
+        }
+
+        let storage_bits = match storage_size {
+            Some(size) => size * 8,
+            None => self.u_byte_size(dwarf, unit)? * 8
+        };
+        let bit_size = self.u_bit_size(unit)?;
+        Ok(location + storage_bits - bit_offset - bit_size)
+    }
+
+    /// The offset of the member from the start of the datatype in bits, this
+    /// accounts for both DWARF 5 (DW_AT_data_bit_offset) and DWARF 4
+    /// (DW_AT_bit_offset) style bitfields
+    pub fn data_bit_offset<D>(&self, dwarf: &D) -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_data_bit_offset(dwarf, unit)
+        })?
+    }
+
+    pub(crate) fn u_bit_layout<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<BitLayout, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let position = self.u_data_bit_offset(dwarf, unit)?;
+        let bit_size = match self.u_bit_size(unit) {
+            Ok(bit_size) => bit_size,
+            Err(Error::BitSizeAttributeNotFound) => {
+                self.u_byte_size(dwarf, unit)? * 8
+            },
+            Err(e) => return Err(e)
+        };
+        Ok(BitLayout {
+            byte_offset: position / 8,
+            bit_offset: position % 8,
+            bit_size
+        })
+    }
+
+    /// The exact position and size of the member, for members which are not
+    /// bitfields the bit offset is zero and the bit size covers every byte
+    pub fn bit_layout<D>(&self, dwarf: &D) -> Result<BitLayout, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_bit_layout(dwarf, unit)
+        })?
+    }
+
+    pub(crate) fn u_is_mutable(&self, unit: &CU) -> Result<bool, Error> {
+        unit.entry_context(&self.location, |entry| {
+            matches!(entry.attr_value(gimli::DW_AT_mutable),
+                     Ok(Some(AttributeValue::Flag(true))))
+        })
+    }
+
+    /// Whether the member was declared `mutable` (C++ only)
+    pub fn is_mutable<D>(&self, dwarf: &D) -> Result<bool, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_is_mutable(unit)
+        })?
+    }
+
+    /// Compare the position of two members within the same datatype
+    pub fn cmp_by_offset<D>(&self, other: &Member, dwarf: &D)
+    -> Result<Ordering, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let position = dwarf.unit_context(&self.location, |unit| {
+            self.u_data_bit_offset(dwarf, unit)
+        })??;
+        let other_position = dwarf.unit_context(&other.location, |unit| {
+            other.u_data_bit_offset(dwarf, unit)
+        })??;
+        Ok(position.cmp(&other_position))
+    }
+}
+
+/// Sort members by their position within the datatype, members which share a
+/// position (e.g. union members) keep their relative order
+pub fn sort_members_by_offset<D>(dwarf: &D, members: &mut Vec<Member>)
+-> Result<(), Error>
+where D: DwarfContext + BorrowableDwarf {
+    let mut keyed = members.iter().map(|member| {
+        dwarf.unit_context(&member.location, |unit| {
+            Ok((member.u_data_bit_offset(dwarf, unit)?, *member))
+        })?
+    }).collect::<Result<Vec<(usize, Member)>, Error>>()?;
+
+    keyed.sort_by_key(|(position, _)| *position);
+    *members = keyed.into_iter().map(|(_, member)| member).collect();
+    Ok(())
 }
 
 /// prevent UnitHasMembers trait from being usable outside of the library
@@ -627,30 +2610,49 @@ pub(crate) mod unit_has_members {
 
         fn u_members(&self, unit: &CU) -> Result<Vec<Member>, Error> {
             let mut members: Vec<Member> = Vec::new();
-            let mut entries = {
-                match unit.entries_at_offset(self.location().offset) {
-                    Ok(entries) => entries,
+            self.u_for_each_member(unit, |member| members.push(member))?;
+            Ok(members)
+        }
+
+        fn u_member_count(&self, unit: &CU) -> Result<usize, Error> {
+            let mut count = 0;
+            self.u_for_each_member(unit, |_| count += 1)?;
+            Ok(count)
+        }
+
+        fn u_for_each_member<F>(&self, unit: &CU, mut f: F)
+        -> Result<(), Error>
+        where F: FnMut(Member) {
+            let mut tree = {
+                match unit.entries_tree(Some(self.location().offset)) {
+                    Ok(tree) => tree,
                     _ => return Err(Error::DIEError(
                        format!("Failed to seek to DIE at {:?}", self.location())
                     ))
                 }
             };
-            if entries.next_dfs().is_err() {
-                return Err(Error::DIEError(
+            let root = match tree.root() {
+                Ok(root) => root,
+                _ => return Err(Error::DIEError(
                     format!("Failed to find next DIE at {:?}", self.location())
                 ))
-            }
-            while let Ok(Some((_, entry))) = entries.next_dfs() {
+            };
+
+            // C++ types can interleave methods, nested types and static
+            // members with the data members, so visit every child
+            let mut children = root.children();
+            while let Ok(Some(child)) = children.next() {
+                let entry = child.entry();
                 if entry.tag() != gimli::DW_TAG_member {
-                    break;
+                    continue;
                 }
                 let location = Location {
                     header: self.location().header,
                     offset: entry.offset(),
                 };
-                members.push(Member { location });
+                f(Member { location });
             };
-            Ok(members)
+            Ok(())
         }
     }
 }
@@ -663,6 +2665,108 @@ pub trait HasMembers : unit_has_members::UnitHasMembers {
             self.u_members(unit)
         })?
     }
+
+    /// Count the members/fields of this type without collecting them
+    fn member_count<D>(&self, dwarf: &D) -> Result<usize, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_member_count(unit)
+        })?
+    }
+
+    /// Get the members/fields for which `pred` holds, the predicate is applied
+    /// while walking the members and its first error is returned
+    fn members_where<D, P>(&self, dwarf: &D, pred: P)
+    -> Result<Vec<Member>, Error>
+    where D: DwarfContext, P: Fn(&Member, &D) -> Result<bool, Error> {
+        dwarf.unit_context(&self.location(), |unit| {
+            let mut members: Vec<Member> = Vec::new();
+            let mut error: Option<Error> = None;
+            self.u_for_each_member(unit, |member| {
+                if error.is_some() {
+                    return
+                }
+                match pred(&member, dwarf) {
+                    Ok(true) => members.push(member),
+                    Ok(false) => (),
+                    Err(e) => error = Some(e)
+                }
+            })?;
+            match error {
+                Some(e) => Err(e),
+                None => Ok(members)
+            }
+        })?
+    }
+
+    /// Find the member called `name`, descending into anonymous struct and
+    /// union members, e.g. to select the variant of a tagged union
+    fn member_by_name<D>(&self, dwarf: &D, name: &str)
+    -> Result<Option<Member>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location(), |unit| {
+            let found = u_find_member_in(dwarf, unit, self.u_members(unit)?,
+                                         name)?;
+            Ok(found.map(|(member, _)| member))
+        })?
+    }
+}
+
+impl VariantPart {
+    pub(crate) fn u_discriminant(&self, unit: &CU)
+    -> Result<Option<Member>, Error> {
+        let offset = unit.entry_context(&self.location, |entry| {
+            match entry.attr_value(gimli::DW_AT_discr) {
+                Ok(Some(AttributeValue::UnitRef(offset))) => Some(offset),
+                _ => None
+            }
+        })?;
+        Ok(offset.map(|offset| {
+            let location = Location { header: self.location.header, offset };
+            Member { location }
+        }))
+    }
+
+    /// The member holding the discriminant which selects the variant, None
+    /// when there is no DW_AT_discr (e.g. an enum with a single variant)
+    pub fn discriminant<D>(&self, dwarf: &D) -> Result<Option<Member>, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_discriminant(unit)
+        })?
+    }
+
+    pub(crate) fn u_variants(&self, unit: &CU) -> Result<Vec<Variant>, Error> {
+        let locations = u_child_locations(unit, self.location,
+                                          gimli::DW_TAG_variant)?;
+        Ok(locations.into_iter().map(|location| Variant { location }).collect())
+    }
+
+    /// The alternatives of the variant part, in the order they were declared
+    pub fn variants<D>(&self, dwarf: &D) -> Result<Vec<Variant>, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_variants(unit)
+        })?
+    }
+}
+
+impl Variant {
+    pub(crate) fn u_discr_value(&self, unit: &CU)
+    -> Result<Option<i64>, Error> {
+        unit.entry_context(&self.location, |entry| {
+            get_entry_constant(entry, gimli::DW_AT_discr_value)
+        })
+    }
+
+    /// The discriminant value selecting this variant, None for the default
+    /// variant (e.g. the dataful variant of a niche optimized Rust enum)
+    pub fn discr_value<D>(&self, dwarf: &D) -> Result<Option<i64>, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_discr_value(unit)
+        })?
+    }
 }
 
 impl unit_has_members::UnitHasMembers for Struct {
@@ -671,9 +2775,13 @@ impl unit_has_members::UnitHasMembers for Struct {
 impl unit_has_members::UnitHasMembers for Union {
     fn location(&self) -> Location { self.location }
 }
+impl unit_has_members::UnitHasMembers for Variant {
+    fn location(&self) -> Location { self.location }
+}
 
 impl HasMembers for Struct { }
 impl HasMembers for Union { }
+impl HasMembers for Variant { }
 
 
 /// A summary of alignment data for a Struct, used to determine packed and
@@ -688,97 +2796,521 @@ pub struct AlignmentStats {
     /// The sum of unused bytes from holes in the struct
     pub sum_holes: usize,
 
-    /// The sum of the sizes of members in the struct
-    pub sum_member_size: usize,
+    /// The sum of the sizes of members in the struct
+    pub sum_member_size: usize,
+
+    /// The amount of trailing unused bytes
+    pub padding: usize,
+
+    /// The number of times a member was aligned with less than its natural
+    /// alignment, e.g. an 32-bit int was not 4-byte aligned
+    /// (this is currently innacurate, unsure how natural size should be
+    /// determined for structs, potentially needs to be done recursively)
+    pub nr_unnat_alignment: usize,
+}
+
+// Whether a member name reads as filler, e.g. `__pad0`, `padding` or
+// `reserved2`
+fn is_padding_name(name: &str) -> bool {
+    let name = name.trim_start_matches('_').to_lowercase();
+    let name = name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '_');
+    matches!(name, "pad" | "padding" | "reserved" | "unused" | "fill" |
+                   "filler" | "spare")
+}
+
+impl Struct {
+    fn location(&self) -> Location {
+        self.location
+    }
+
+    pub(crate) fn u_explicit_padding_members<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<Vec<Member>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let mut padding: Vec<Member> = vec![];
+        for member in self.u_members(unit)? {
+            let is_padding = match member.u_name(dwarf, unit) {
+                Ok(name) => is_padding_name(&name),
+                // anonymous structs and unions hold real members
+                Err(Error::NameAttributeNotFound) => {
+                    let typ = resolve_type(dwarf, member.u_get_type(unit));
+                    let typ = typ.and_then(|t| {
+                        u_unit_of(dwarf, unit, t.location(), |unit| {
+                            t.u_strip_cv_typedefs(dwarf, unit)
+                        })
+                    });
+                    match typ {
+                        Ok(Type::Struct(_)) | Ok(Type::Union(_)) => false,
+                        Ok(_) | Err(Error::TypeAttributeNotFound) => true,
+                        Err(e) => return Err(e)
+                    }
+                },
+                Err(e) => return Err(e)
+            };
+            if is_padding {
+                padding.push(member);
+            }
+        }
+        Ok(padding)
+    }
+
+    /// Members which only pad the struct: anonymous members which aren't
+    /// structs or unions and members named like filler (`__pad0`, `padding`,
+    /// `reserved1`, `unused`, ...). This is a heuristic on names, unlike
+    /// `alignment_stats` which only accounts for implicit holes
+    pub fn explicit_padding_members<D>(&self, dwarf: &D)
+    -> Result<Vec<Member>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_explicit_padding_members(dwarf, unit)
+        })?
+    }
+
+    pub fn alignment_stats<D>(&self, dwarf: &D)
+    -> Result<AlignmentStats, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let mut nr_holes: usize = 0;
+        let mut hole_positions: Vec<(usize, usize)> = Vec::new();
+        let mut sum_holes: usize = 0;
+        let mut sum_member_size: usize = 0;
+        let mut nr_unnat_alignment: usize = 0;
+
+        let mut prev_offset: usize = 0;
+        let mut prev_size: usize = 0;
+        for (idx, member) in self.members(dwarf)?.into_iter().enumerate() {
+            let curr_offset = member.offset(dwarf)?;
+            let curr_size = member.byte_size(dwarf)?;
+
+            sum_member_size += curr_size;
+
+            // nothing to do for the first member
+            if prev_offset == 0 {
+                prev_offset = curr_offset;
+                prev_size = curr_size;
+                continue
+            }
+
+            // array alignment is based on the entry type size
+            let byte_size_single = match member.get_type(dwarf)? {
+                Type::Array(arr) => arr.entry_size(dwarf)?,
+                _ => curr_size
+            };
+
+            // size zero members don't matter
+            if curr_size == 0 || byte_size_single == 0 {
+                continue
+            }
+
+            // calc padding between end of prev type
+            let hole_sz = curr_offset - (prev_size + prev_offset);
+            sum_holes += hole_sz;
+
+            if hole_sz > 0 {
+                nr_holes += 1;
+                hole_positions.push((idx, hole_sz));
+            }
+
+            // if the size is divisible byte the type size, it is naturally
+            // aligned, otherwise some packing likely occurred
+            if curr_offset % byte_size_single != 0 {
+                nr_unnat_alignment += 1;
+            }
+
+            prev_offset = curr_offset;
+            prev_size = curr_size;
+        }
+
+        let byte_size = self.byte_size(dwarf)?;
+
+        // check the distance to the end of the struct for padding
+        let padding = byte_size - (prev_size + prev_offset);
+
+        Ok(AlignmentStats { nr_holes, sum_holes, hole_positions, padding,
+                            sum_member_size, nr_unnat_alignment })
+    }
+
+    pub fn to_string_verbose<D>(&self, dwarf: &D, verbosity: u8)
+    -> Result<String, Error>
+    where D: BorrowableDwarf + DwarfContext {
+        self.to_string_opts(dwarf, &FormatOptions::with_verbosity(verbosity))
+    }
+
+    /// Format the struct as a C-style definition according to `opts`
+    pub fn to_string_opts<D>(&self, dwarf: &D, opts: &FormatOptions)
+    -> Result<String, Error>
+    where D: BorrowableDwarf + DwarfContext {
+        let mut repr = String::new();
+        dwarf.unit_context(&self.location, |unit| {
+            match self.u_name(dwarf, unit) {
+                Ok(name) => repr.push_str(&format!("struct {} {{\n", name)),
+                Err(Error::NameAttributeNotFound) => {
+                    repr.push_str("struct {\n")
+                },
+                Err(e) => return Err(e)
+            };
+            let members = self.u_members(unit)?;
+            for member in members.into_iter() {
+                let tab_level = 0;
+                let base_offset = 0;
+                repr.push_str(&format_member(dwarf, unit, member, tab_level,
+                                             opts, base_offset)?);
+            }
+
+            if opts.verbosity > 0 {
+                let bytesz = self.u_byte_size(unit)?;
+                repr.push_str(&format!("\n{}/* total size: {} */\n",
+                                       opts.indent.levels(1), bytesz));
+            }
+            repr.push('}');
+
+            if opts.emit_attributes {
+                // packing is only a hint, don't fail formatting when it can't
+                // be inferred
+                if opts.emit_packed &&
+                   matches!(self.u_is_packed(dwarf, unit), Ok(true)) {
+                    repr.push_str(" __attribute__((packed))");
+                }
+
+                let alignment = match self.u_alignment(unit) {
+                    Ok(alignment) => Some(alignment),
+                    Err(Error::AlignmentAttributeNotFound) => None,
+                    Err(e) => return Err(e)
+                };
+
+                if let Some(alignment) = alignment {
+                    repr.push_str(
+                        &format!(" __attribute((__aligned__({})))", alignment)
+                    )
+                }
+            }
+
+            if opts.terminate {
+                repr.push(';');
+            }
+
+            Ok(())
+        })??;
+        Ok(repr)
+    }
+
+    pub fn to_string<D>(&self, dwarf: &D) -> Result<String, Error>
+    where D: BorrowableDwarf + DwarfContext {
+        self.to_string_verbose(dwarf, 0)
+    }
+
+    pub(crate) fn u_byte_size(&self, unit: &CU) -> Result<usize, Error> {
+        let entry_size = unit.entry_context(&self.location(), |entry| {
+            get_entry_byte_size(entry)
+        })?;
+
+        if let Some(entry_size) = entry_size {
+            return Ok(entry_size)
+        }
+
+        // This should(?) be unreachable
+        Err(Error::ByteSizeAttributeNotFound)
+    }
+
+    pub fn byte_size<D>(&self, dwarf: &D) -> Result<usize, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_byte_size(unit)
+        })?
+    }
+
+    pub(crate) fn u_alignment(&self, unit: &CU) -> Result<usize, Error> {
+        let alignment = unit.entry_context(&self.location(), |entry| {
+            get_entry_alignment(entry)
+        })?;
+
+        if let Some(alignment) = alignment {
+            return Ok(alignment)
+        }
+
+        Err(Error::AlignmentAttributeNotFound)
+    }
+
+    pub fn alignment<D>(&self, dwarf: &D) -> Result<usize, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_alignment(unit)
+        })?
+    }
+
+    pub(crate) fn u_is_packed<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<bool, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let mut max_alignment = 1;
+        for member in self.u_members(unit)?.into_iter() {
+            let alignment = u_inner_alignment(dwarf, unit, &member)?;
+            max_alignment = max_alignment.max(alignment);
+
+            // bitfields share their storage unit, so their offset says
+            // nothing about packing
+            match member.u_bit_size(unit) {
+                Ok(_) => continue,
+                Err(Error::BitSizeAttributeNotFound) => {},
+                Err(e) => return Err(e)
+            }
+
+            if member.u_member_location(unit)? % alignment != 0 {
+                return Ok(true)
+            }
+        }
+        Ok(self.u_byte_size(unit)? % max_alignment != 0)
+    }
+
+    /// Infer whether the struct was declared with __attribute__((packed)),
+    /// DWARF doesn't record packing so this checks for members placed at
+    /// offsets their type's alignment wouldn't allow, or a size that isn't a
+    /// multiple of the struct's alignment. Alignments are inferred
+    /// conservatively (never above the address size), so only layouts that
+    /// no natural alignment could produce are reported. Packed structs that
+    /// happen to have a naturally aligned layout are not detected, but their
+    /// layout matches that of an unpacked struct anyway
+    pub fn is_packed<D>(&self, dwarf: &D) -> Result<bool, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_is_packed(dwarf, unit)
+        })?
+    }
+
+    pub(crate) fn u_methods(&self, unit: &CU)
+    -> Result<Vec<Subprogram>, Error> {
+        let mut tree = {
+            match unit.entries_tree(Some(self.location.offset)) {
+                Ok(tree) => tree,
+                _ => return Err(Error::DIEError(
+                   format!("Failed to seek to DIE at {:?}", self.location())
+                ))
+            }
+        };
+        let root = match tree.root() {
+            Ok(root) => root,
+            _ => return Err(Error::DIEError(
+                format!("Failed to find next DIE at {:?}", self.location())
+            ))
+        };
+
+        let mut methods: Vec<Subprogram> = vec![];
+        let mut children = root.children();
+        while let Ok(Some(child)) = children.next() {
+            let entry = child.entry();
+            if entry.tag() != gimli::DW_TAG_subprogram {
+                continue;
+            }
+            let location = Location {
+                header: self.location.header,
+                offset: entry.offset(),
+            };
+            methods.push(Subprogram { location });
+        }
+        Ok(methods)
+    }
 
-    /// The amount of trailing unused bytes
-    pub padding: usize,
+    pub(crate) fn u_variant_part(&self, unit: &CU)
+    -> Result<Option<VariantPart>, Error> {
+        let parts = u_child_locations(unit, self.location,
+                                      gimli::DW_TAG_variant_part)?;
+        Ok(parts.first().map(|location| VariantPart { location: *location }))
+    }
 
-    /// The number of times a member was aligned with less than its natural
-    /// alignment, e.g. an 32-bit int was not 4-byte aligned
-    /// (this is currently innacurate, unsure how natural size should be
-    /// determined for structs, potentially needs to be done recursively)
-    pub nr_unnat_alignment: usize,
-}
+    /// The variant part of the struct, present for Rust enums and Ada variant
+    /// records, which otherwise look like a struct with no members
+    pub fn variant_part<D>(&self, dwarf: &D)
+    -> Result<Option<VariantPart>, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_variant_part(unit)
+        })?
+    }
 
-impl Struct {
-    fn location(&self) -> Location {
-        self.location
+    /// The member functions declared in a C++ struct, including compiler
+    /// generated ones such as constructors
+    pub fn methods<D>(&self, dwarf: &D) -> Result<Vec<Subprogram>, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_methods(unit)
+        })?
     }
 
-    pub fn alignment_stats<D>(&self, dwarf: &D)
-    -> Result<AlignmentStats, Error>
+    pub(crate) fn u_vtable<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<Vec<VtableEntry>, Error>
     where D: DwarfContext + BorrowableDwarf {
-        let mut nr_holes: usize = 0;
-        let mut hole_positions: Vec<(usize, usize)> = Vec::new();
-        let mut sum_holes: usize = 0;
-        let mut sum_member_size: usize = 0;
-        let mut nr_unnat_alignment: usize = 0;
+        let mut vtable: Vec<VtableEntry> = vec![];
+        for method in self.u_methods(unit)? {
+            let slot = match method.u_vtable_slot(unit)? {
+                Some(slot) => slot,
+                None => continue
+            };
+            let name = method.u_name(dwarf, unit)?;
+            let signature = format_method_signature(dwarf, unit, method)?;
+            vtable.push(VtableEntry { slot, name, signature, method });
+        }
+        vtable.sort_by_key(|entry| entry.slot);
+        Ok(vtable)
+    }
 
-        let mut prev_offset: usize = 0;
-        let mut prev_size: usize = 0;
-        for (idx, member) in self.members(dwarf)?.into_iter().enumerate() {
-            let curr_offset = member.offset(dwarf)?;
-            let curr_size = member.byte_size(dwarf)?;
+    /// The virtual methods declared in a C++ struct ordered by their vtable
+    /// slot, methods inherited without being overridden are only listed on
+    /// the base struct
+    pub fn vtable<D>(&self, dwarf: &D) -> Result<Vec<VtableEntry>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_vtable(dwarf, unit)
+        })?
+    }
 
-            sum_member_size += curr_size;
+    pub(crate) fn u_bit_range_of_path<D>(&self, dwarf: &D, unit: &CU,
+                                         path: &str)
+    -> Result<(usize, usize), Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let mut typ = Type::Struct(*self);
+        let mut start = 0;
+        let mut end = 0;
+        for name in path.split('.') {
+            let found = u_unit_of(dwarf, unit, typ.location(), |unit| {
+                u_find_member(dwarf, unit, typ, name)
+            })?;
+            let (member, parent_offset) = match found {
+                Some(found) => found,
+                None => return Err(Error::MemberNotFound(name.to_string()))
+            };
+            let (layout, inner) = u_unit_of(dwarf, unit, member.location,
+                                            |unit| {
+                let layout = member.u_bit_layout(dwarf, unit)?;
+                Ok((layout, u_follow_type(dwarf, unit, &member)?))
+            })?;
+            start += parent_offset + layout.byte_offset * 8 + layout.bit_offset;
+            end = start + layout.bit_size;
+            typ = inner;
+        }
+        Ok((start, end))
+    }
 
-            // nothing to do for the first member
-            if prev_offset == 0 {
-                prev_offset = curr_offset;
-                prev_size = curr_size;
-                continue
-            }
+    pub(crate) fn u_flat_offset_table<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<Vec<(String, usize, usize)>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let mut table = vec![];
+        let mut visited = HashSet::from([self.location]);
+        u_flatten_members(dwarf, unit, Type::Struct(*self), "", 0, 0,
+                          &mut visited, &mut table)?;
+        Ok(table)
+    }
 
-            // array alignment is based on the entry type size
-            let byte_size_single = match member.get_type(dwarf)? {
-                Type::Array(arr) => arr.entry_size(dwarf)?,
-                _ => curr_size
-            };
+    /// Get a `(dotted_name, absolute_offset, size)` entry for every leaf
+    /// member, nested structs and unions are descended into while arrays are
+    /// listed as a whole, offsets and sizes are in bytes
+    pub fn flat_offset_table<D>(&self, dwarf: &D)
+    -> Result<Vec<(String, usize, usize)>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_flat_offset_table(dwarf, unit)
+        })?
+    }
 
-            // size zero members don't matter
-            if curr_size == 0 || byte_size_single == 0 {
-                continue
-            }
+    pub(crate) fn u_flatten_scalars<D>(&self, dwarf: &D, unit: &CU,
+                                       expand_arrays: bool)
+    -> Result<Vec<ScalarField>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let mut fields = vec![];
+        let mut visited = HashSet::new();
+        u_flatten_scalars(dwarf, unit, Type::Struct(*self), "", 0,
+                          expand_arrays, 0, &mut visited, &mut fields)?;
+        Ok(fields)
+    }
 
-            // calc padding between end of prev type
-            let hole_sz = curr_offset - (prev_size + prev_offset);
-            sum_holes += hole_sz;
+    /// Get every scalar making up the struct with its dotted path (e.g.
+    /// `a.b[2].c`), absolute offset, type and size. Nested structs and unions
+    /// are descended into, pointers are not, and arrays of a known length are
+    /// expanded into their elements when `expand_arrays` is set
+    pub fn flatten_scalars<D>(&self, dwarf: &D, expand_arrays: bool)
+    -> Result<Vec<ScalarField>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_flatten_scalars(dwarf, unit, expand_arrays)
+        })?
+    }
 
-            if hole_sz > 0 {
-                nr_holes += 1;
-                hole_positions.push((idx, hole_sz));
-            }
+    /// Get the bit range `start..end` of a nested member relative to the start
+    /// of the struct, `path` names the members to walk separated by dots
+    /// (e.g. "ctrl.flags.enabled"), members of anonymous structs/unions are
+    /// named directly as in C
+    pub fn bit_range_of_path<D>(&self, dwarf: &D, path: &str)
+    -> Result<(usize, usize), Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_bit_range_of_path(dwarf, unit, path)
+        })?
+    }
 
-            // if the size is divisible byte the type size, it is naturally
-            // aligned, otherwise some packing likely occurred
-            if curr_offset % byte_size_single != 0 {
-                nr_unnat_alignment += 1;
+    /// Check that each `(member, offset)` pair matches the layout of the
+    /// struct, members are named by path as in `bit_range_of_path` and
+    /// offsets are in bytes. The first mismatch is returned as an
+    /// `Error::LayoutMismatch` and a missing member as `Error::MemberNotFound`
+    pub fn assert_layout<D>(&self, dwarf: &D, expected: &[(&str, usize)])
+    -> Result<(), Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            for (path, offset) in expected.iter() {
+                let (start, _) = self.u_bit_range_of_path(dwarf, unit, path)?;
+                if start / 8 != *offset {
+                    return Err(Error::LayoutMismatch(path.to_string(), *offset,
+                                                     start / 8))
+                }
             }
+            Ok(())
+        })?
+    }
+}
 
-            prev_offset = curr_offset;
-            prev_size = curr_size;
-        }
+// The largest natural alignment among the types of `members`
+fn u_max_member_alignment<D>(dwarf: &D, unit: &CU, members: Vec<Member>)
+-> Result<usize, Error>
+where D: DwarfContext + BorrowableDwarf {
+    let mut max_alignment = 1;
+    for member in members.into_iter() {
+        let alignment = u_inner_alignment(dwarf, unit, &member)?;
+        max_alignment = max_alignment.max(alignment);
+    }
+    Ok(max_alignment)
+}
 
-        let byte_size = self.byte_size(dwarf)?;
+// The natural alignment of the type `inner` refers to
+fn u_inner_alignment<D, T>(dwarf: &D, unit: &CU, inner: &T)
+-> Result<usize, Error>
+where D: DwarfContext + BorrowableDwarf, T: UnitInnerType {
+    u_with_type(dwarf, unit, inner, |typ, unit| {
+        typ.u_natural_alignment(dwarf, unit)
+    })
+}
 
-        // check the distance to the end of the struct for padding
-        let padding = byte_size - (prev_size + prev_offset);
+// The exact width in bits of the type `inner` refers to
+fn u_inner_bit_size<D, T>(dwarf: &D, unit: &CU, inner: &T)
+-> Result<usize, Error>
+where D: DwarfContext + BorrowableDwarf, T: UnitInnerType {
+    u_with_type(dwarf, unit, inner, |typ, unit| typ.u_bit_size(dwarf, unit))
+}
 
-        Ok(AlignmentStats { nr_holes, sum_holes, hole_positions, padding,
-                            sum_member_size, nr_unnat_alignment })
+impl Union {
+    fn location(&self) -> Location {
+        self.location
     }
 
     pub fn to_string_verbose<D>(&self, dwarf: &D, verbosity: u8)
     -> Result<String, Error>
-    where D: BorrowableDwarf + DwarfContext {
+    where D: DwarfContext + BorrowableDwarf {
+        self.to_string_opts(dwarf, &FormatOptions::with_verbosity(verbosity))
+    }
+
+    /// Format the union as a C-style definition according to `opts`
+    pub fn to_string_opts<D>(&self, dwarf: &D, opts: &FormatOptions)
+    -> Result<String, Error>
+    where D: DwarfContext + BorrowableDwarf {
         let mut repr = String::new();
-        let _ = dwarf.unit_context(&self.location, |unit| {
+        dwarf.unit_context(&self.location, |unit| {
             match self.u_name(dwarf, unit) {
-                Ok(name) => repr.push_str(&format!("struct {} {{\n", name)),
-                Err(Error::NameAttributeNotFound) => {
-                    repr.push_str("struct {\n")
-                },
+                Ok(name) => repr.push_str(&format!("union {} {{\n", name)),
+                Err(Error::NameAttributeNotFound) => repr.push_str("union {\n"),
                 Err(e) => return Err(e)
             };
             let members = self.u_members(unit)?;
@@ -786,168 +3318,324 @@ impl Struct {
                 let tab_level = 0;
                 let base_offset = 0;
                 repr.push_str(&format_member(dwarf, unit, member, tab_level,
-                                             verbosity, base_offset)?);
+                                             opts, base_offset)?);
             }
+            repr.push('}');
+            if opts.terminate {
+                repr.push(';');
+            }
+            Ok(())
+        })??;
+        Ok(repr)
+    }
 
-            if verbosity > 0 {
-                let bytesz = self.u_byte_size(unit)?;
-                repr.push_str(&format!("\n    /* total size: {} */\n", bytesz));
+    pub fn to_string<D>(&self, dwarf: &D) -> Result<String, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        self.to_string_verbose(dwarf, 0)
+    }
+
+    pub(crate) fn u_byte_size<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let entry_size = unit.entry_context(&self.location(), |entry| {
+            get_entry_byte_size(entry)
+        })?;
+
+        if let Some(entry_size) = entry_size {
+            return Ok(entry_size);
+        }
+
+        // if there was no byte_size attribute, need to loop over all the
+        // children to find the size
+        // do zero-member unions exist? maybe need to err here if bytesz is zero
+        let mut bytesz = 0;
+        for member in self.u_members(unit)? {
+            let membytesz = member.u_byte_size(dwarf, unit)?;
+
+            if membytesz > bytesz {
+                bytesz = membytesz;
             }
-            repr.push('}');
+        }
+        Ok(bytesz)
+    }
 
-            let alignment = match self.u_alignment(unit) {
-                Ok(alignment) => Some(alignment),
-                Err(Error::AlignmentAttributeNotFound) => None,
+    pub fn byte_size<D>(&self, dwarf: &D) -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_byte_size(dwarf, unit)
+        })?
+    }
+
+    pub(crate) fn u_variant_sizes<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<Vec<(String, usize)>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let mut sizes = self.u_members(unit)?.into_iter().map(|member| {
+            let name = match member.u_name(dwarf, unit) {
+                Ok(name) => name,
+                Err(Error::NameAttributeNotFound) => "".to_string(),
                 Err(e) => return Err(e)
             };
+            Ok((name, member.u_byte_size(dwarf, unit)?))
+        }).collect::<Result<Vec<_>, Error>>()?;
 
-            if let Some(alignment) = alignment {
-                repr.push_str(
-                    &format!(" __attribute((__aligned__({})))", alignment)
-                )
-            }
-
-            repr.push(';');
+        sizes.sort_by_key(|&(_, size)| std::cmp::Reverse(size));
+        Ok(sizes)
+    }
 
-            Ok(())
-        });
-        Ok(repr)
+    /// Get the name and byte size of each member, largest first, anonymous
+    /// members have an empty name
+    pub fn variant_sizes<D>(&self, dwarf: &D)
+    -> Result<Vec<(String, usize)>, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_variant_sizes(dwarf, unit)
+        })?
     }
+}
 
-    pub fn to_string<D>(&self, dwarf: &D) -> Result<String, Error>
-    where D: BorrowableDwarf + DwarfContext {
-        self.to_string_verbose(dwarf, 0)
+impl Enum {
+    fn location(&self) -> Location {
+        self.location
     }
 
-    pub(crate) fn u_byte_size(&self, unit: &CU) -> Result<usize, Error> {
+    /// internal byte_size on CU
+    pub(crate) fn u_byte_size<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
         let entry_size = unit.entry_context(&self.location(), |entry| {
             get_entry_byte_size(entry)
         })?;
 
         if let Some(entry_size) = entry_size {
-            return Ok(entry_size)
+            return Ok(entry_size);
         }
 
-        // This should(?) be unreachable
-        Err(Error::ByteSizeAttributeNotFound)
+        u_with_type(dwarf, unit, self, |inner, unit| {
+            inner.u_byte_size(dwarf, unit)
+        })
     }
 
+    /// The memory footprint of the enum, generally the size of the largest
+    /// variant
     pub fn byte_size<D>(&self, dwarf: &D) -> Result<usize, Error>
-    where D: DwarfContext {
-        dwarf.unit_context(&self.location, |unit| {
-            self.u_byte_size(unit)
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_byte_size(dwarf, unit)
         })?
     }
 
-    pub(crate) fn u_alignment(&self, unit: &CU) -> Result<usize, Error> {
-        let alignment = unit.entry_context(&self.location(), |entry| {
-            get_entry_alignment(entry)
+    pub(crate) fn u_bit_size(&self, unit: &CU) -> Result<usize, Error> {
+        let bit_size = unit.entry_context(&self.location, |entry| {
+            get_entry_bit_size(entry)
         })?;
+        if let Some(bit_size) = bit_size {
+            Ok(bit_size)
+        } else {
+            Err(Error::BitSizeAttributeNotFound)
+        }
+    }
 
-        if let Some(alignment) = alignment {
-            return Ok(alignment)
+    /// The DW_AT_bit_size of the enum, only present when its values occupy
+    /// fewer bits than its byte size, for enum typed bitfields the width is
+    /// on the member instead
+    pub fn bit_size<D>(&self, dwarf: &D) -> Result<usize, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_bit_size(unit)
+        })?
+    }
+
+    pub(crate) fn u_enumerators(&self, unit: &CU)
+    -> Result<Vec<Enumerator>, Error> {
+        let mut enumerators: Vec<Enumerator> = vec![];
+        let mut entries = {
+            match unit.entries_at_offset(self.location.offset) {
+                Ok(entries) => entries,
+                _ => return Err(Error::DIEError(
+                   format!("Failed to seek to DIE at {:?}", self.location())
+                ))
+            }
+        };
+        if entries.next_dfs().is_err() {
+            return Err(Error::DIEError(
+               format!("Failed to find next DIE at {:?}", self.location())
+            ))
         }
+        while let Ok(Some((_, entry))) = entries.next_dfs() {
+            if entry.tag() != gimli::DW_TAG_enumerator {
+                break;
+            }
+            let location = Location {
+                header: self.location.header,
+                offset: entry.offset(),
+            };
+            enumerators.push(Enumerator { location });
+        };
+        Ok(enumerators)
+    }
 
-        Err(Error::AlignmentAttributeNotFound)
+    /// The named values of the enum, in the order they were declared
+    pub fn enumerators<D>(&self, dwarf: &D) -> Result<Vec<Enumerator>, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_enumerators(unit)
+        })?
     }
 
-    pub fn alignment<D>(&self, dwarf: &D) -> Result<usize, Error>
+    pub(crate) fn u_has_gaps(&self, unit: &CU) -> Result<bool, Error> {
+        for (idx, enumerator) in self.u_enumerators(unit)?.iter().enumerate() {
+            if enumerator.u_value(unit)? != idx as i64 {
+                return Ok(true)
+            }
+        }
+        Ok(false)
+    }
+
+    /// Whether the values differ from the implicit 0, 1, 2, ... numbering in
+    /// declaration order, which means some values were assigned explicitly
+    /// (e.g. a flag enum), DWARF only records the resolved values
+    pub fn has_gaps<D>(&self, dwarf: &D) -> Result<bool, Error>
     where D: DwarfContext {
         dwarf.unit_context(&self.location, |unit| {
-            self.u_alignment(unit)
+            self.u_has_gaps(unit)
         })?
     }
-}
-
-impl Union {
-    fn location(&self) -> Location {
-        self.location
-    }
 
-    pub fn to_string_verbose<D>(&self, dwarf: &D, verbosity: u8)
+    /// Format the enum as a C-style definition according to `opts`
+    pub fn to_string_opts<D>(&self, dwarf: &D, opts: &FormatOptions)
     -> Result<String, Error>
     where D: DwarfContext + BorrowableDwarf {
-        let mut repr = String::new();
-        let _ = dwarf.unit_context(&self.location, |unit| {
+        dwarf.unit_context(&self.location, |unit| {
+            let mut repr = String::new();
             match self.u_name(dwarf, unit) {
-                Ok(name) => repr.push_str(&format!("union {} {{\n", name)),
-                Err(Error::NameAttributeNotFound) => repr.push_str("union {\n"),
+                Ok(name) => repr.push_str(&format!("enum {} {{\n", name)),
+                Err(Error::NameAttributeNotFound) => repr.push_str("enum {\n"),
                 Err(e) => return Err(e)
             };
-            let members = self.u_members(unit)?;
-            for member in members.into_iter() {
-                let tab_level = 0;
-                let base_offset = 0;
-                repr.push_str(&format_member(dwarf, unit, member, tab_level,
-                                             verbosity, base_offset)?);
+            for enumerator in self.u_enumerators(unit)?.into_iter() {
+                repr.push_str(&opts.indent.levels(1));
+                repr.push_str(&format_enumerator(dwarf, unit, enumerator)?);
             }
-            repr.push_str("};");
-            Ok(())
-        })?;
-        Ok(repr)
+            repr.push('}');
+            if opts.terminate {
+                repr.push(';');
+            }
+            Ok(repr)
+        })?
     }
 
     pub fn to_string<D>(&self, dwarf: &D) -> Result<String, Error>
     where D: DwarfContext + BorrowableDwarf {
-        self.to_string_verbose(dwarf, 0)
+        self.to_string_opts(dwarf, &FormatOptions::default())
     }
+}
 
-    pub(crate) fn u_byte_size(&self, unit: &CU) -> Result<usize, Error> {
-        let entry_size = unit.entry_context(&self.location(), |entry| {
-            get_entry_byte_size(entry)
+impl Enumerator {
+    pub(crate) fn u_value(&self, unit: &CU) -> Result<i64, Error> {
+        let value = unit.entry_context(&self.location, |entry| {
+            get_entry_const_value(entry)
         })?;
-
-        if let Some(entry_size) = entry_size {
-            return Ok(entry_size);
+        if let Some(value) = value {
+            Ok(value)
+        } else {
+            Err(Error::ConstValueAttributeNotFound)
         }
+    }
 
-        // if there was no byte_size attribute, need to loop over all the
-        // children to find the size
-        // do zero-member unions exist? maybe need to err here if bytesz is zero
-        let mut bytesz = 0;
-        for member in self.u_members(unit)? {
-            let member_type = member.u_get_type(unit)?;
-            let membytesz = member_type.u_byte_size(unit)?;
+    /// The value associated with this enumerator
+    pub fn value<D>(&self, dwarf: &D) -> Result<i64, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_value(unit)
+        })?
+    }
+}
 
-            if membytesz > bytesz {
-                bytesz = membytesz;
-            }
-        }
-        Ok(bytesz)
+impl Subrange {
+    pub(crate) fn u_lower_bound(&self, unit: &CU) -> Result<i64, Error> {
+        let lower_bound = unit.entry_context(&self.location, |entry| {
+            get_entry_bound(entry, gimli::DW_AT_lower_bound)
+        })?;
+        Ok(lower_bound.unwrap_or_else(|| unit_default_lower_bound(unit)))
     }
 
-    pub fn byte_size<D>(&self, dwarf: &D) -> Result<usize, Error>
+    /// The index of the first element, the language default (0 for C, 1 for
+    /// Fortran) when there is no DW_AT_lower_bound
+    pub fn lower_bound<D>(&self, dwarf: &D) -> Result<i64, Error>
     where D: DwarfContext {
-        dwarf.unit_context(&self.location(), |unit| {
-            self.u_byte_size(unit)
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_lower_bound(unit)
         })?
     }
-}
 
-impl Enum {
-    fn location(&self) -> Location {
-        self.location
+    pub(crate) fn u_upper_bound(&self, unit: &CU)
+    -> Result<Option<i64>, Error> {
+        unit.entry_context(&self.location, |entry| {
+            get_entry_bound(entry, gimli::DW_AT_upper_bound)
+        })
     }
 
-    /// internal byte_size on CU
-    pub(crate) fn u_byte_size(&self, unit: &CU) -> Result<usize, Error> {
-        let entry_size = unit.entry_context(&self.location(), |entry| {
-            get_entry_byte_size(entry)
+    /// The index of the last element, None when the subrange has no constant
+    /// DW_AT_upper_bound (e.g. it is given by DW_AT_count instead)
+    pub fn upper_bound<D>(&self, dwarf: &D) -> Result<Option<i64>, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_upper_bound(unit)
+        })?
+    }
+
+    pub(crate) fn u_count(&self, unit: &CU) -> Result<Option<usize>, Error> {
+        let count = unit.entry_context(&self.location, |entry| {
+            get_entry_bound(entry, gimli::DW_AT_count)
         })?;
+        let count = match count {
+            Some(count) => Some(count),
+            None => {
+                let lower_bound = self.u_lower_bound(unit)?;
+                self.u_upper_bound(unit)?.map(|upper| upper - lower_bound + 1)
+            }
+        };
+        Ok(count.map(|count| count.max(0) as usize))
+    }
 
-        if let Some(entry_size) = entry_size {
-            return Ok(entry_size);
-        }
+    /// The number of elements, from DW_AT_count or the bounds, None when it
+    /// isn't a constant
+    pub fn count<D>(&self, dwarf: &D) -> Result<Option<usize>, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_count(unit)
+        })?
+    }
 
-        self.u_get_type(unit)?.u_byte_size(unit)
+    pub(crate) fn u_stride(&self, unit: &CU) -> Result<Option<usize>, Error> {
+        let stride = unit.entry_context(&self.location, |entry| {
+            get_entry_udata(entry, gimli::DW_AT_byte_stride)
+        })?;
+        Ok(stride.map(|stride| stride as usize))
     }
 
-    /// The memory footprint of the enum, generally the size of the largest
-    /// variant
-    pub fn byte_size<D>(&self, dwarf: &D) -> Result<usize, Error>
+    /// The DW_AT_byte_stride of the subrange, only present when the distance
+    /// between elements differs from the element size
+    pub fn stride<D>(&self, dwarf: &D) -> Result<Option<usize>, Error>
     where D: DwarfContext {
-        dwarf.unit_context(&self.location(), |unit| {
-            self.u_byte_size(unit)
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_stride(unit)
+        })?
+    }
+
+    pub(crate) fn u_bit_stride(&self, unit: &CU)
+    -> Result<Option<usize>, Error> {
+        let stride = unit.entry_context(&self.location, |entry| {
+            get_entry_udata(entry, gimli::DW_AT_bit_stride)
+        })?;
+        Ok(stride.map(|stride| stride as usize))
+    }
+
+    /// The DW_AT_bit_stride of the subrange, for bit-packed arrays
+    pub fn bit_stride<D>(&self, dwarf: &D) -> Result<Option<usize>, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_bit_stride(unit)
         })?
     }
 }
@@ -995,6 +3683,125 @@ impl Base {
             self.u_byte_size(unit)
         })?
     }
+
+    pub(crate) fn u_encoding(&self, unit: &CU) -> Result<gimli::DwAte, Error> {
+        let encoding = unit.entry_context(&self.location(), |entry| {
+            match entry.attr_value(gimli::DW_AT_encoding) {
+                Ok(Some(AttributeValue::Encoding(encoding))) => Some(encoding),
+                _ => None
+            }
+        })?;
+
+        if let Some(encoding) = encoding {
+            Ok(encoding)
+        } else {
+            Err(Error::EncodingAttributeNotFound)
+        }
+    }
+
+    /// How the value is represented, e.g. DW_ATE_signed or DW_ATE_float
+    pub fn encoding<D>(&self, dwarf: &D) -> Result<gimli::DwAte, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_encoding(unit)
+        })?
+    }
+
+    pub(crate) fn u_is_bool(&self, unit: &CU) -> Result<bool, Error> {
+        Ok(self.u_encoding(unit)? == gimli::DW_ATE_boolean)
+    }
+
+    /// Whether the type is a boolean, whichever name the compiler gave it
+    /// (`_Bool` in C, `bool` in C++)
+    pub fn is_bool<D>(&self, dwarf: &D) -> Result<bool, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_is_bool(unit)
+        })?
+    }
+
+    pub(crate) fn u_interpret(&self, unit: &CU, bytes: &[u8])
+    -> Result<i128, Error> {
+        let byte_size = self.u_byte_size(unit)?;
+        let signed = match self.u_encoding(unit)? {
+            gimli::DW_ATE_signed | gimli::DW_ATE_signed_char => true,
+            gimli::DW_ATE_unsigned | gimli::DW_ATE_unsigned_char |
+            gimli::DW_ATE_boolean => false,
+            encoding => return Err(Error::UnimplementedError(
+                format!("interpreting values encoded as {encoding}")
+            ))
+        };
+        if byte_size > 16 {
+            return Err(Error::UnimplementedError(
+                format!("interpreting {byte_size} byte integers")
+            ))
+        }
+        if bytes.len() < byte_size {
+            return Err(Error::ValueTooShort(byte_size, bytes.len()))
+        }
+
+        // the target's byte order, which the sections are read with
+        let mut value: u128 = 0;
+        let bytes = &bytes[..byte_size];
+        match unit_endianness(unit, self.location) {
+            RunTimeEndian::Little => {
+                for byte in bytes.iter().rev() {
+                    value = (value << 8) | *byte as u128;
+                }
+            },
+            RunTimeEndian::Big => {
+                for byte in bytes.iter() {
+                    value = (value << 8) | *byte as u128;
+                }
+            }
+        }
+
+        let unused = 128 - byte_size * 8;
+        match (signed, byte_size) {
+            (_, 0) => Ok(0),
+            (true, _) => Ok(((value << unused) as i128) >> unused),
+            (false, _) => Ok(value as i128)
+        }
+    }
+
+    /// Decode the integer value of the type from its bytes in the target's
+    /// memory (e.g. read from a core dump), in the byte order of the target
+    /// rather than the host, see `Dwarf::target_endianness`. Only integer
+    /// and boolean encodings are supported
+    pub fn interpret<D>(&self, dwarf: &D, bytes: &[u8]) -> Result<i128, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_interpret(unit, bytes)
+        })?
+    }
+
+    pub(crate) fn u_stdint_name(&self, unit: &CU)
+    -> Result<Option<String>, Error> {
+        let encoding = self.u_encoding(unit)?;
+        let bits = self.u_byte_size(unit)? * 8;
+        let name = match (encoding, bits) {
+            (gimli::DW_ATE_signed | gimli::DW_ATE_signed_char,
+             8 | 16 | 32 | 64) => format!("int{bits}_t"),
+            (gimli::DW_ATE_unsigned | gimli::DW_ATE_unsigned_char,
+             8 | 16 | 32 | 64) => format!("uint{bits}_t"),
+            (gimli::DW_ATE_boolean, 8) => "bool".to_string(),
+            (gimli::DW_ATE_float, 32) => "float".to_string(),
+            (gimli::DW_ATE_float, 64) => "double".to_string(),
+            _ => return Ok(None)
+        };
+        Ok(Some(name))
+    }
+
+    /// The <stdint.h> spelling of the type based on its encoding and size
+    /// (e.g. `uint64_t` for an 8 byte `unsigned long`), floats map to
+    /// `float`/`double`, None is returned for types without a portable
+    /// fixed-width name such as `long double` or `__int128`
+    pub fn stdint_name<D>(&self, dwarf: &D) -> Result<Option<String>, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_stdint_name(unit)
+        })?
+    }
 }
 
 impl Typedef {
@@ -1002,7 +3809,9 @@ impl Typedef {
         self.location
     }
 
-    pub(crate) fn u_byte_size(&self, unit: &CU) -> Result<usize, Error> {
+    pub(crate) fn u_byte_size<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
         let entry_size = unit.entry_context(&self.location(), |entry| {
             get_entry_byte_size(entry)
         })?;
@@ -1011,16 +3820,25 @@ impl Typedef {
             return Ok(entry_size);
         }
 
-        let inner_type = self.u_get_type(unit)?;
-        inner_type.u_byte_size(unit)
+        u_with_type(dwarf, unit, self, |inner, unit| {
+            inner.u_byte_size(dwarf, unit)
+        })
     }
 
     pub fn byte_size<D>(&self, dwarf: &D) -> Result<usize, Error>
-    where D: DwarfContext {
+    where D: DwarfContext + BorrowableDwarf {
         dwarf.unit_context(&self.location(), |unit| {
-            self.u_byte_size(unit)
+            self.u_byte_size(dwarf, unit)
         })?
     }
+
+    /// The C declaration of the typedef, e.g. `typedef char buf_t[16];`, with
+    /// the name placed inside the declarator for function pointers and arrays
+    /// and anonymous structs, unions and enums defined inline
+    pub fn declaration<D>(&self, dwarf: &D) -> Result<String, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        format_typedef(dwarf, *self, &FormatOptions::default())
+    }
 }
 
 impl Const {
@@ -1028,7 +3846,9 @@ impl Const {
         self.location
     }
 
-    pub(crate) fn u_byte_size(&self, unit: &CU) -> Result<usize, Error> {
+    pub(crate) fn u_byte_size<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
         let entry_size = unit.entry_context(&self.location(), |entry| {
             get_entry_byte_size(entry)
         })?;
@@ -1037,14 +3857,15 @@ impl Const {
             return Ok(entry_size);
         }
 
-        let inner_type = self.u_get_type(unit)?;
-        inner_type.u_byte_size(unit)
+        u_with_type(dwarf, unit, self, |inner, unit| {
+            inner.u_byte_size(dwarf, unit)
+        })
     }
 
     pub fn byte_size<D>(&self, dwarf: &D) -> Result<usize, Error>
-    where D: DwarfContext {
+    where D: DwarfContext + BorrowableDwarf {
         dwarf.unit_context(&self.location(), |unit| {
-            self.u_byte_size(unit)
+            self.u_byte_size(dwarf, unit)
         })?
     }
 }
@@ -1054,7 +3875,9 @@ impl Volatile {
         self.location
     }
 
-    pub(crate) fn u_byte_size(&self, unit: &CU) -> Result<usize, Error> {
+    pub(crate) fn u_byte_size<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
         let entry_size = unit.entry_context(&self.location(), |entry| {
             get_entry_byte_size(entry)
         })?;
@@ -1063,14 +3886,15 @@ impl Volatile {
             return Ok(entry_size);
         }
 
-        let inner_type = self.u_get_type(unit)?;
-        inner_type.u_byte_size(unit)
+        u_with_type(dwarf, unit, self, |inner, unit| {
+            inner.u_byte_size(dwarf, unit)
+        })
     }
 
     pub fn byte_size<D>(&self, dwarf: &D) -> Result<usize, Error>
-    where D: DwarfContext {
+    where D: DwarfContext + BorrowableDwarf {
         dwarf.unit_context(&self.location(), |unit| {
-            self.u_byte_size(unit)
+            self.u_byte_size(dwarf, unit)
         })?
     }
 }
@@ -1080,7 +3904,9 @@ impl Restrict {
         self.location
     }
 
-    pub(crate) fn u_byte_size(&self, unit: &CU) -> Result<usize, Error> {
+    pub(crate) fn u_byte_size<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
         let entry_size = unit.entry_context(&self.location(), |entry| {
             get_entry_byte_size(entry)
         })?;
@@ -1089,14 +3915,15 @@ impl Restrict {
             return Ok(entry_size);
         }
 
-        let inner_type = self.u_get_type(unit)?;
-        inner_type.u_byte_size(unit)
+        u_with_type(dwarf, unit, self, |inner, unit| {
+            inner.u_byte_size(dwarf, unit)
+        })
     }
 
     pub fn byte_size<D>(&self, dwarf: &D) -> Result<usize, Error>
-    where D: DwarfContext {
+    where D: DwarfContext + BorrowableDwarf {
         dwarf.unit_context(&self.location(), |unit| {
-            self.u_byte_size(unit)
+            self.u_byte_size(dwarf, unit)
         })?
     }
 }
@@ -1143,6 +3970,83 @@ impl Array {
         Ok(bound)
     }
 
+    pub(crate) fn u_subranges(&self, unit: &CU)
+    -> Result<Vec<Subrange>, Error> {
+        let mut tree = match unit.entries_tree(Some(self.location.offset)) {
+            Ok(tree) => tree,
+            _ => return Err(Error::DIEError(
+                format!("Failed to seek to DIE at {:?}", self.location())
+            ))
+        };
+        let root = match tree.root() {
+            Ok(root) => root,
+            _ => return Err(Error::DIEError(
+                format!("Failed to find next DIE at {:?}", self.location())
+            ))
+        };
+
+        let mut subranges: Vec<Subrange> = vec![];
+        let mut children = root.children();
+        while let Ok(Some(child)) = children.next() {
+            let entry = child.entry();
+            if entry.tag() != gimli::DW_TAG_subrange_type {
+                continue;
+            }
+            let location = Location {
+                header: self.location.header,
+                offset: entry.offset(),
+            };
+            subranges.push(Subrange { location });
+        }
+        Ok(subranges)
+    }
+
+    /// The DW_TAG_subrange_type children of the array, outermost first
+    pub fn subranges<D>(&self, dwarf: &D) -> Result<Vec<Subrange>, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_subranges(unit)
+        })?
+    }
+
+    pub(crate) fn u_dimensions(&self, unit: &CU)
+    -> Result<Vec<Dimension>, Error> {
+        let mut dimensions: Vec<Dimension> = vec![];
+        for subrange in self.u_subranges(unit)? {
+            dimensions.push(Dimension {
+                lower_bound: subrange.u_lower_bound(unit)?,
+                count: subrange.u_count(unit)?,
+                byte_stride: subrange.u_stride(unit)?,
+                bit_stride: subrange.u_bit_stride(unit)?
+            });
+        }
+
+        // a stride on the array itself is the distance between its elements,
+        // so it belongs to the innermost dimension
+        let (array_byte_stride, array_bit_stride) = {
+            unit.entry_context(&self.location, |entry| {
+                (get_entry_udata(entry, gimli::DW_AT_byte_stride),
+                 get_entry_udata(entry, gimli::DW_AT_bit_stride))
+            })?
+        };
+        if let Some(last) = dimensions.last_mut() {
+            if last.byte_stride.is_none() && last.bit_stride.is_none() {
+                last.byte_stride = array_byte_stride.map(|v| v as usize);
+                last.bit_stride = array_bit_stride.map(|v| v as usize);
+            }
+        }
+        Ok(dimensions)
+    }
+
+    /// Every dimension of the array, outermost first, a multidimensional C
+    /// array such as `int a[2][3]` has one dimension per subscript
+    pub fn dimensions<D>(&self, dwarf: &D) -> Result<Vec<Dimension>, Error>
+    where D: DwarfContext {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_dimensions(unit)
+        })?
+    }
+
     /// The number of items in the array
     pub fn get_bound<D>(&self, dwarf: &D) -> Result<usize, Error>
     where D: DwarfContext {
@@ -1151,20 +4055,47 @@ impl Array {
         })?
     }
 
-    pub(crate) fn u_entry_size(&self, unit: &CU) -> Result<usize, Error> {
-        let inner_type = self.u_get_type(unit)?;
-        inner_type.u_byte_size(unit)
+    pub(crate) fn u_entry_size<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        u_with_type(dwarf, unit, self, |inner, unit| {
+            inner.u_byte_size(dwarf, unit)
+        })
     }
 
     /// The size of one array item
     pub fn entry_size<D>(&self, dwarf: &D) -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location, |unit| {
+            self.u_entry_size(dwarf, unit)
+        })?
+    }
+
+    pub(crate) fn u_is_descriptor_based(&self, unit: &CU)
+    -> Result<bool, Error> {
+        unit.entry_context(&self.location, |entry| {
+            matches!(entry.attr_value(gimli::DW_AT_data_location), Ok(Some(_)))
+            || matches!(entry.attr_value(gimli::DW_AT_associated), Ok(Some(_)))
+        })
+    }
+
+    /// Whether the array's data lives behind a descriptor (e.g. Fortran
+    /// allocatable or pointer arrays) rather than being laid out in place
+    pub fn is_descriptor_based<D>(&self, dwarf: &D) -> Result<bool, Error>
     where D: DwarfContext {
         dwarf.unit_context(&self.location, |unit| {
-            self.u_entry_size(unit)
+            self.u_is_descriptor_based(unit)
         })?
     }
 
-    pub(crate) fn u_byte_size(&self, unit: &CU) -> Result<usize, Error> {
+    pub(crate) fn u_byte_size<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        // the size of a descriptor-based array is only known at runtime
+        if self.u_is_descriptor_based(unit)? {
+            return Err(Error::NonContiguousArrayError(self.location))
+        }
+
         let byte_size = unit.entry_context(&self.location(), |entry| {
             get_entry_byte_size(entry)
         })?;
@@ -1173,16 +4104,128 @@ impl Array {
             return Ok(byte_size);
         }
 
-        let inner_size = self.u_entry_size(unit)?;
+        // a bit-packed array takes up as many bytes as its bits round up to
+        if self.u_bit_stride(unit)?.is_some() {
+            return Ok(self.u_bit_size(dwarf, unit)?.div_ceil(8));
+        }
+
+        // an element of unknown or zero size (e.g. an incomplete struct) would
+        // make the array look like it takes up no space
+        let inner_size = match self.u_entry_size(dwarf, unit) {
+            Ok(0) | Err(Error::ByteSizeAttributeNotFound) => {
+                return Err(Error::UnknownElementSizeError(self.location))
+            },
+            Ok(inner_size) => inner_size,
+            Err(e) => return Err(e)
+        };
         let bound = self.u_get_bound(unit)?;
         Ok(inner_size * bound)
     }
 
-    /// The memory footprint of the entire array
+    /// The memory footprint of the entire array, this is an
+    /// `Error::UnknownElementSizeError` if the size of the elements is unknown
+    /// or zero and an `Error::NonContiguousArrayError` if the array is
+    /// descriptor-based
     pub fn byte_size<D>(&self, dwarf: &D) -> Result<usize, Error>
-    where D: DwarfContext {
+    where D: DwarfContext + BorrowableDwarf {
         dwarf.unit_context(&self.location(), |unit| {
-            self.u_byte_size(unit)
+            self.u_byte_size(dwarf, unit)
+        })?
+    }
+
+    // the DW_AT_bit_stride of the innermost dimension, if the array is
+    // bit-packed
+    fn u_bit_stride(&self, unit: &CU) -> Result<Option<usize>, Error> {
+        let dimensions = self.u_dimensions(unit)?;
+        Ok(dimensions.last().and_then(|dimension| dimension.bit_stride))
+    }
+
+    // the distance between consecutive elements in bits
+    fn u_element_bit_stride<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let dimensions = self.u_dimensions(unit)?;
+        match dimensions.last() {
+            Some(Dimension { bit_stride: Some(stride), .. }) => Ok(*stride),
+            Some(Dimension { byte_stride: Some(stride), .. }) => {
+                Ok(stride * 8)
+            },
+            _ => match self.u_entry_size(dwarf, unit) {
+                Ok(0) | Err(Error::ByteSizeAttributeNotFound) => {
+                    Err(Error::UnknownElementSizeError(self.location))
+                },
+                Ok(entry_size) => Ok(entry_size * 8),
+                Err(e) => Err(e)
+            }
+        }
+    }
+
+    pub(crate) fn u_bit_size<D>(&self, dwarf: &D, unit: &CU)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        if self.u_bit_stride(unit)?.is_none() {
+            return Ok(self.u_byte_size(dwarf, unit)? * 8);
+        }
+
+        let bit_size = unit.entry_context(&self.location(), |entry| {
+            get_entry_bit_size(entry)
+        })?;
+        if let Some(bit_size) = bit_size {
+            return Ok(bit_size);
+        }
+
+        let count: usize = self.u_dimensions(unit)?.iter().map(|dimension| {
+            dimension.count.unwrap_or(0)
+        }).product();
+        Ok(count * self.u_element_bit_stride(dwarf, unit)?)
+    }
+
+    /// The size of the entire array in bits, for bit-packed arrays (those
+    /// with a DW_AT_bit_stride) this is the element count times the stride
+    /// and `byte_size * 8` otherwise
+    pub fn bit_size<D>(&self, dwarf: &D) -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_bit_size(dwarf, unit)
+        })?
+    }
+
+    pub(crate) fn u_element_bit_offset<D>(&self, dwarf: &D, unit: &CU,
+                                          index: usize)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        Ok(index * self.u_element_bit_stride(dwarf, unit)?)
+    }
+
+    /// The offset in bits of the element at `index` from the start of the
+    /// array, counting elements in memory order
+    pub fn element_bit_offset<D>(&self, dwarf: &D, index: usize)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_element_bit_offset(dwarf, unit, index)
+        })?
+    }
+
+    pub(crate) fn u_element_offset<D>(&self, dwarf: &D, unit: &CU,
+                                      index: usize)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        let bit_offset = self.u_element_bit_offset(dwarf, unit, index)?;
+        if bit_offset % 8 != 0 {
+            return Err(Error::UnalignedElementError(self.location, index))
+        }
+        Ok(bit_offset / 8)
+    }
+
+    /// The offset in bytes of the element at `index` from the start of the
+    /// array, this is an `Error::UnalignedElementError` if the element of a
+    /// bit-packed array does not start on a byte boundary
+    pub fn element_offset<D>(&self, dwarf: &D, index: usize)
+    -> Result<usize, Error>
+    where D: DwarfContext + BorrowableDwarf {
+        dwarf.unit_context(&self.location(), |unit| {
+            self.u_element_offset(dwarf, unit, index)
         })?
     }
 }
@@ -3,22 +3,37 @@ use dwat::Dwarf;
 use std::fs::File;
 use memmap2::Mmap;
 use tempfile::TempDir;
+use object::{Object, ObjectSection};
 
 use dwat::prelude::*;
 
 fn compile(source: &str) -> anyhow::Result<(TempDir, PathBuf)> {
+    compile_with(source, &["-gdwarf-5"])
+}
+
+fn compile_with(source: &str, args: &[&str])
+-> anyhow::Result<(TempDir, PathBuf)> {
+    compile_sources(&[source], args)
+}
+
+// Compile each source as its own translation unit and link them together
+fn compile_sources(sources: &[&str], args: &[&str])
+-> anyhow::Result<(TempDir, PathBuf)> {
     let tmp_dir = TempDir::new()?;
-    let src_path = tmp_dir.path().join("src.c");
 
-    {
+    let mut src_paths = vec![];
+    for (idx, source) in sources.iter().enumerate() {
+        let src_path = tmp_dir.path().join(format!("src{idx}.c"));
         let mut tmp_file = File::create(&src_path)?;
         tmp_file.write_all(source.as_bytes())?;
+        src_paths.push(src_path);
     }
 
     let out_path = tmp_dir.path().join("bin");
+    // args go first so options such as `-x c++` apply to the sources
     let output = Command::new("gcc")
-        .arg(&src_path)
-        .arg("-gdwarf-5") // TODO: Allow this to be configurable, env var maybe
+        .args(args)
+        .args(&src_paths)
         .arg("-o")
         .arg(&out_path)
         .output()?;
@@ -30,6 +45,29 @@ fn compile(source: &str) -> anyhow::Result<(TempDir, PathBuf)> {
     Ok((tmp_dir, out_path))
 }
 
+// Build a Rust program with debug info, used for Rust specific DWARF such as
+// variant parts
+fn compile_rust(source: &str) -> anyhow::Result<(TempDir, PathBuf)> {
+    let tmp_dir = TempDir::new()?;
+
+    let src_path = tmp_dir.path().join("main.rs");
+    File::create(&src_path)?.write_all(source.as_bytes())?;
+
+    let out_path = tmp_dir.path().join("bin");
+    let output = Command::new("rustc")
+        .args(["-g", "-C", "opt-level=0"])
+        .arg(&src_path)
+        .arg("-o")
+        .arg(&out_path)
+        .output()?;
+
+    if !output.status.success() {
+        panic!("rustc failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    Ok((tmp_dir, out_path))
+}
+
 const SIMPLE: &str = "
 struct simple {
     unsigned long long s;
@@ -102,6 +140,9 @@ fn padded_struct() -> anyhow::Result<()> {
         panic!("failed to get second offset");
     }
 
+    assert!(!found.is_packed(&dwarf)?);
+    assert!(!found.to_string(&dwarf)?.contains("packed"));
+
     Ok(())
 }
 
@@ -148,5 +189,4064 @@ fn packed_struct() -> anyhow::Result<()> {
         panic!("failed to get second offset");
     }
 
+    // packing isn't recorded in DWARF and has to be inferred from the layout
+    assert!(found.is_packed(&dwarf)?);
+    let formatted = found.to_string(&dwarf)?;
+    assert!(formatted.contains("} __attribute__((packed));"));
+
+    let opts = dwat::FormatOptions {
+        emit_packed: false,
+        ..Default::default()
+    };
+    let formatted = found.to_string_opts(&dwarf, &opts)?;
+    assert!(!formatted.contains("__attribute__((packed))"));
+
+    let opts = dwat::FormatOptions::default();
+    let header = dwarf.emit_c_header(&[dwat::Type::Struct(found)], &opts)?;
+    check_header(&header, "_Static_assert(sizeof(struct packed) == 12, \"\");")
+}
+
+const NATURALLY_ALIGNED: &str = "
+struct inner { int a; long long b; };
+struct outer { char c; struct inner i; } o;
+struct cplx { float f; _Complex float c; } x;
+int main() {}";
+
+#[test]
+fn naturally_aligned_struct() -> anyhow::Result<()> {
+    // i386 aligns 8 byte scalars to 4 bytes within structs
+    let (_tmpdir, path) = compile_sources(&[NATURALLY_ALIGNED],
+                                          &["-g", "-m32", "-c"])?;
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let inner = dwarf.lookup_type::<dwat::Struct>("inner".to_string())?;
+    let inner = inner.unwrap();
+    assert_eq!(inner.members(&dwarf)?[1].offset(&dwarf)?, 4);
+    assert!(!inner.is_packed(&dwarf)?);
+
+    let outer = dwarf.lookup_type::<dwat::Struct>("outer".to_string())?;
+    let outer = outer.unwrap();
+    assert!(!outer.is_packed(&dwarf)?);
+    let opts = dwat::FormatOptions::default();
+    let header = dwarf.emit_c_header(&[dwat::Type::Struct(outer)], &opts)?;
+    assert!(!header.contains("packed"));
+    check_header_with(&header,
+                      "_Static_assert(sizeof(struct outer) == 16, \"\");",
+                      &["-m32"])?;
+
+    // complex numbers are aligned like their parts
+    let (_tmpdir, path) = compile(NATURALLY_ALIGNED)?;
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let cplx = dwarf.lookup_type::<dwat::Struct>("cplx".to_string())?;
+    let cplx = cplx.unwrap();
+    assert_eq!(cplx.members(&dwarf)?[1].offset(&dwarf)?, 4);
+    assert!(!cplx.is_packed(&dwarf)?);
+
+    Ok(())
+}
+
+// Check that a header compiles by including it from a source file
+fn check_header(header: &str, assertion: &str) -> anyhow::Result<()> {
+    check_header_with(header, assertion, &[])
+}
+
+// Like check_header, passing `args` to the compiler, e.g. to target i386
+fn check_header_with(header: &str, assertion: &str, args: &[&str])
+-> anyhow::Result<()> {
+    let tmp_dir = TempDir::new()?;
+    let header_path = tmp_dir.path().join("out.h");
+    let src_path = tmp_dir.path().join("check.c");
+
+    File::create(&header_path)?.write_all(header.as_bytes())?;
+    let source = format!("#include \"out.h\"\n{assertion}\n");
+    File::create(&src_path)?.write_all(source.as_bytes())?;
+
+    let output = Command::new("gcc")
+        .arg("-fsyntax-only")
+        .args(args)
+        .arg(&src_path)
+        .output()?;
+
+    if !output.status.success() {
+        panic!("header failed to compile: {}\n{header}",
+               String::from_utf8_lossy(&output.stderr));
+    }
+    Ok(())
+}
+
+#[test]
+fn emit_header_padded() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile(PADDED)?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("padded".to_string())?;
+    let found = found.unwrap();
+
+    let opts = dwat::FormatOptions::default();
+    let header = dwarf.emit_c_header(&[dwat::Type::Struct(found)], &opts)?;
+    assert!(header.contains("struct padded {"));
+
+    check_header(&header, "_Static_assert(sizeof(struct padded) == 16, \"\");")
+}
+
+const HEADER_DEPS: &str = "
+typedef struct node node_t;
+typedef unsigned int u32;
+enum color { RED, GREEN = 5 };
+typedef struct { int x; int y; } point_t;
+struct node {
+    node_t *next;
+    point_t pts[2];
+    enum color c;
+    u32 flags;
+};
+int main() {
+    struct node n;
+}";
+
+#[test]
+fn emit_header_dependencies() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile(HEADER_DEPS)?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("node".to_string())?;
+    let found = found.unwrap();
+
+    let opts = dwat::FormatOptions::default();
+    let header = dwarf.emit_c_header(&[dwat::Type::Struct(found)], &opts)?;
+    assert!(header.contains("typedef struct node node_t;"));
+    assert!(header.contains("GREEN = 5,"));
+    assert!(header.contains("} point_t;"));
+
+    check_header(&header, "_Static_assert(sizeof(struct node) == 32, \"\");")
+}
+
+// Collect the DWARF sections of a linked ELF, the relocations in them are
+// already resolved so they can be moved into another container as-is
+fn debug_sections(elf: &[u8]) -> anyhow::Result<Vec<(String, Vec<u8>)>> {
+    let elf = object::File::parse(elf)?;
+    let mut sections = Vec::new();
+    for section in elf.sections() {
+        let name = section.name()?;
+        if name.starts_with(".debug_") {
+            sections.push((name.to_string(), section.data()?.to_vec()));
+        }
+    }
+    Ok(sections)
+}
+
+// Move the DWARF sections of a linked ELF into a Mach-O object for `arch`
+fn macho_from_elf(elf: &[u8], arch: object::Architecture)
+-> anyhow::Result<Vec<u8>> {
+    let mut macho = object::write::Object::new(
+        object::BinaryFormat::MachO,
+        arch,
+        object::Endianness::Little
+    );
+    for (name, data) in debug_sections(elf)? {
+        // Mach-O spells .debug_info as __debug_info, limited to 16 bytes
+        let mut name = format!("__{}", &name[1..]).into_bytes();
+        name.truncate(16);
+        let id = macho.add_section(b"__DWARF".to_vec(), name,
+                                   object::SectionKind::Debug);
+        macho.append_section_data(id, &data, 1);
+    }
+    Ok(macho.write()?)
+}
+
+#[test]
+fn macho_container() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile(PADDED)?;
+
+    let elf = std::fs::read(&path)?;
+    let macho = macho_from_elf(&elf, object::Architecture::X86_64)?;
+
+    let dwarf = Dwarf::load(&*macho)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("padded".to_string())?;
+    assert!(found.is_some());
+
+    let found = found.unwrap();
+    assert!(found.byte_size(&dwarf)? == 16);
+    assert!(found.members(&dwarf)?[1].offset(&dwarf)? == 8);
+
+    Ok(())
+}
+
+#[test]
+fn fat_macho() -> anyhow::Result<()> {
+    let (_tmpdir, padded) = compile(PADDED)?;
+    let (_tmpdir, simple) = compile(SIMPLE)?;
+
+    let slices = [
+        (0x0100_0007u32, macho_from_elf(&std::fs::read(&padded)?,
+                                        object::Architecture::X86_64)?),
+        (0x0100_000cu32, macho_from_elf(&std::fs::read(&simple)?,
+                                        object::Architecture::Aarch64)?),
+    ];
+
+    // fat_header and fat_arch are big endian, slices are 4K aligned
+    let mut fat: Vec<u8> = vec![];
+    fat.extend(0xcafe_babeu32.to_be_bytes());
+    fat.extend((slices.len() as u32).to_be_bytes());
+    let mut offset = 0x1000;
+    for (cputype, slice) in slices.iter() {
+        for field in [*cputype, 0, offset, slice.len() as u32, 12] {
+            fat.extend(field.to_be_bytes());
+        }
+        offset = (offset + slice.len() as u32 + 0xfff) & !0xfff;
+    }
+    for (_, slice) in slices.iter() {
+        fat.resize((fat.len() + 0xfff) & !0xfff, 0);
+        fat.extend(slice);
+    }
+
+    let dwarf = Dwarf::load_arch(&*fat, object::Architecture::Aarch64)?;
+    let found = dwarf.lookup_type::<dwat::Struct>("simple".to_string())?;
+    assert_eq!(found.unwrap().byte_size(&dwarf)?, 8);
+    assert!(dwarf.lookup_type::<dwat::Struct>("padded".to_string())?
+            .is_none());
+
+    let dwarf = Dwarf::load_arch(&*fat, object::Architecture::X86_64)?;
+    let found = dwarf.lookup_type::<dwat::Struct>("padded".to_string())?;
+    assert_eq!(found.unwrap().byte_size(&dwarf)?, 16);
+
+    assert!(matches!(Dwarf::load_arch(&*fat, object::Architecture::Riscv64),
+                     Err(dwat::Error::DwarfLoadError(_))));
+
+    Ok(())
+}
+
+#[test]
+fn coff_container() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile(PADDED)?;
+
+    let elf = std::fs::read(&path)?;
+    let mut coff = object::write::Object::new(
+        object::BinaryFormat::Coff,
+        object::Architecture::X86_64,
+        object::Endianness::Little
+    );
+    for (name, data) in debug_sections(&elf)? {
+        // names longer than 8 bytes end up in the string table as `/N`
+        let id = coff.add_section(vec![], name.into_bytes(),
+                                  object::SectionKind::Debug);
+        coff.append_section_data(id, &data, 1);
+    }
+    let coff = coff.write()?;
+
+    let dwarf = Dwarf::load(&*coff)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("padded".to_string())?;
+    assert!(found.is_some());
+
+    let found = found.unwrap();
+    assert!(found.byte_size(&dwarf)? == 16);
+    assert!(found.members(&dwarf)?[1].offset(&dwarf)? == 8);
+
+    Ok(())
+}
+
+#[test]
+fn mingw_pe() -> anyhow::Result<()> {
+    // only run where a MinGW cross compiler is installed
+    let mingw = "x86_64-w64-mingw32-gcc";
+    if Command::new(mingw).arg("--version").output().is_err() {
+        return Ok(());
+    }
+
+    let tmp_dir = TempDir::new()?;
+    let src_path = tmp_dir.path().join("src.c");
+    File::create(&src_path)?.write_all(PADDED.as_bytes())?;
+
+    let out_path = tmp_dir.path().join("bin.exe");
+    let output = Command::new(mingw)
+        .arg("-g")
+        .arg(&src_path)
+        .arg("-o")
+        .arg(&out_path)
+        .output()?;
+
+    if !output.status.success() {
+        panic!("mingw failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    let file = File::open(&out_path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("padded".to_string())?;
+    assert!(found.is_some());
+    assert!(found.unwrap().byte_size(&dwarf)? == 16);
+
+    Ok(())
+}
+
+#[test]
+fn sort_members() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile(PADDED)?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("padded".to_string())?;
+    let found = found.unwrap();
+
+    let mut members = found.members(&dwarf)?;
+    members.reverse();
+    assert!(members[0].cmp_by_offset(&members[1], &dwarf)?.is_gt());
+
+    dwat::sort_members_by_offset(&dwarf, &mut members)?;
+    assert!(members[0].name(&dwarf)? == "ui");
+    assert!(members[1].name(&dwarf)? == "ull");
+
+    Ok(())
+}
+
+const BITFIELDS: &str = "
+struct regs {
+    unsigned int a:3;
+    unsigned int b:5;
+    unsigned int c:9;
+    unsigned char d;
+};
+int main() {
+    struct regs r;
+}";
+
+#[test]
+fn bitfield_layout() -> anyhow::Result<()> {
+    // DWARF 4 and 5 describe bitfield positions with different attributes
+    for version in ["-gdwarf-4", "-gdwarf-5"] {
+        let (_tmpdir, path) = compile_with(BITFIELDS, &[version])?;
+
+        let file = File::open(&path)?;
+        let mmap = unsafe { Mmap::map(&file) }?;
+        let dwarf = Dwarf::load(&*mmap)?;
+
+        let found = dwarf.lookup_type::<dwat::Struct>("regs".to_string())?;
+        let found = found.unwrap();
+
+        let layouts = found.members(&dwarf)?.into_iter().map(|memb| {
+            memb.bit_layout(&dwarf)
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        assert!(layouts[0] == dwat::BitLayout {
+            byte_offset: 0, bit_offset: 0, bit_size: 3
+        });
+
+        // each bitfield starts where the previous one ended
+        for pair in layouts[..3].windows(2) {
+            let prev_end = pair[0].byte_offset * 8 + pair[0].bit_offset
+                           + pair[0].bit_size;
+            let start = pair[1].byte_offset * 8 + pair[1].bit_offset;
+            assert!(prev_end == start);
+        }
+
+        assert!(layouts[3] == dwat::BitLayout {
+            byte_offset: 3, bit_offset: 0, bit_size: 8
+        });
+    }
+
+    Ok(())
+}
+
+// gcc describes _Atomic with DW_TAG_atomic_type, which dwat doesn't model
+const ATOMIC: &str = "
+struct counter {
+    _Atomic int count;
+    int limit;
+};
+int main() {
+    struct counter c;
+}";
+
+#[test]
+fn unknown_tag_policy() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile(ATOMIC)?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let mut dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("counter".to_string())?;
+    let found = found.unwrap();
+
+    let members = found.members(&dwarf)?;
+    assert!(matches!(members[0].get_type(&dwarf),
+                     Err(dwat::Error::UnknownTagError(
+                         gimli::DW_TAG_atomic_type, _))));
+
+    // the formatter doesn't drop the member
+    let repr = found.to_string(&dwarf)?;
+    assert!(repr.contains("/* unhandled: DW_TAG_atomic_type */ count;"));
+    assert!(repr.contains("int limit;"));
+
+    dwarf.set_unknown_tag_policy(dwat::UnknownTagPolicy::Skip);
+    let repr = found.to_string(&dwarf)?;
+    assert!(repr.contains("void count;"));
+    assert!(repr.contains("int limit;"));
+
+    Ok(())
+}
+
+#[test]
+fn num_compile_units() -> anyhow::Result<()> {
+    // an object file has exactly one unit, linked programs may also pull in
+    // units from crt objects
+    let (_tmpdir, path) = compile_with(SIMPLE, &["-gdwarf-5", "-c"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    assert_eq!(dwarf.num_compile_units()?, 1);
+
+    Ok(())
+}
+
+const UNION: &str = "
+union value {
+    char c;
+    unsigned long long ull;
+    int i[4];
+};
+int main() {
+    union value v;
+}";
+
+#[test]
+fn union_variant_sizes() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile(UNION)?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Union>("value".to_string())?;
+    let found = found.unwrap();
+
+    assert_eq!(found.member_count(&dwarf)?, 3);
+
+    let sizes = found.variant_sizes(&dwarf)?;
+    assert_eq!(sizes, vec![("i".to_string(), 16),
+                           ("ull".to_string(), 8),
+                           ("c".to_string(), 1)]);
+    assert_eq!(sizes[0].1, found.byte_size(&dwarf)?);
+
+    Ok(())
+}
+
+const NESTED_BITFIELDS: &str = "
+struct ctrl {
+    unsigned int mode:2;
+    unsigned int enabled:1;
+};
+struct device {
+    unsigned long long id;
+    union {
+        unsigned int raw;
+        struct ctrl ctrl;
+    };
+};
+int main() {
+    struct device d;
+}";
+
+#[test]
+fn bit_range_of_path() -> anyhow::Result<()> {
+    for version in ["-gdwarf-4", "-gdwarf-5"] {
+        let (_tmpdir, path) = compile_with(NESTED_BITFIELDS, &[version])?;
+
+        let file = File::open(&path)?;
+        let mmap = unsafe { Mmap::map(&file) }?;
+        let dwarf = Dwarf::load(&*mmap)?;
+
+        let found = dwarf.lookup_type::<dwat::Struct>("device".to_string())?;
+        let found = found.unwrap();
+
+        assert_eq!(found.bit_range_of_path(&dwarf, "id")?, (0, 64));
+        assert_eq!(found.bit_range_of_path(&dwarf, "raw")?, (64, 96));
+        assert_eq!(found.bit_range_of_path(&dwarf, "ctrl.enabled")?, (66, 67));
+        assert!(matches!(found.bit_range_of_path(&dwarf, "ctrl.missing"),
+                         Err(dwat::Error::MemberNotFound(_))));
+    }
+
+    Ok(())
+}
+
+const CATEGORIES: &str = "
+struct inner {
+    int x;
+};
+typedef void vtype;
+struct things {
+    int i;
+    struct inner s;
+    int arr[4];
+    void (*fn)(int);
+    const vtype *vp;
+};
+int main() {
+    struct things t;
+}";
+
+#[test]
+fn type_category() -> anyhow::Result<()> {
+    use dwat::TypeCategory;
+
+    let (_tmpdir, path) = compile(CATEGORIES)?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("things".to_string())?;
+    let found = found.unwrap();
+
+    let members = found.members(&dwarf)?;
+    let categories = members.iter().map(|m| {
+        m.get_type(&dwarf)?.category(&dwarf)
+    }).collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(categories, vec![TypeCategory::Scalar, TypeCategory::Aggregate,
+                                TypeCategory::Aggregate, TypeCategory::Scalar,
+                                TypeCategory::Scalar]);
+
+    let arr = members[2].get_type(&dwarf)?;
+    assert!(arr.as_array().is_some());
+    assert!(arr.as_struct().is_none());
+
+    // pointees of the function pointer and the void pointer
+    for (member, category) in [(&members[3], TypeCategory::Function),
+                               (&members[4], TypeCategory::Void)] {
+        let pointer = member.get_type(&dwarf)?.as_pointer().unwrap();
+        let pointee = pointer.get_type(&dwarf)?;
+        assert_eq!(pointee.category(&dwarf)?, category);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn scan_progress() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_sources(&[SIMPLE, "struct other { int x; } o;"],
+                                          &["-gdwarf-5"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let mut reports = vec![];
+    let mut progress = |p: dwat::dwarf::ScanProgress| reports.push(p);
+    let structs = dwarf.get_named_types_with_progress::<dwat::Struct>(
+        Some(&mut progress)
+    )?;
+
+    // crt objects may contribute units beyond the two compiled here
+    let cus_total = dwarf.num_compile_units()?;
+    assert!(cus_total >= 2);
+    assert_eq!(reports.len(), cus_total);
+    for (idx, report) in reports.iter().enumerate() {
+        assert_eq!(report.cus_done, idx + 1);
+        assert_eq!(report.cus_total, cus_total);
+    }
+    assert_eq!(reports.last().unwrap().items_found, structs.len());
+
+    // the plain (units_done, units_total) form reports the same units
+    let mut units = vec![];
+    let mut progress = |done: usize, total: usize| units.push((done, total));
+    let counted = dwarf.get_named_types_with_unit_progress::<dwat::Struct>(
+        Some(&mut progress)
+    )?;
+    assert_eq!(counted.len(), structs.len());
+    let expected: Vec<(usize, usize)> = reports.iter()
+        .map(|p| (p.cus_done, p.cus_total))
+        .collect();
+    assert_eq!(units, expected);
+
+    let mut units = vec![];
+    let mut progress = |done: usize, total: usize| units.push((done, total));
+    dwarf.get_fg_named_structs_map_with_unit_progress(Some(&mut progress))?;
+    assert_eq!(units, expected);
+
+    Ok(())
+}
+
+const ALIGNED: &str = "
+union __attribute__((aligned(16))) aligned_u {
+    int i;
+    char c;
+};
+typedef int aligned_int __attribute__((aligned(8)));
+struct holder {
+    union aligned_u u;
+    aligned_int a;
+    int plain;
+};
+int main() {
+    struct holder h;
+}";
+
+#[test]
+fn type_alignment() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile(ALIGNED)?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("holder".to_string())?;
+    let found = found.unwrap();
+
+    let members = found.members(&dwarf)?;
+    assert_eq!(members[0].get_type(&dwarf)?.alignment(&dwarf)?, 16);
+    assert_eq!(members[1].get_type(&dwarf)?.alignment(&dwarf)?, 8);
+    assert!(matches!(members[2].get_type(&dwarf)?.alignment(&dwarf),
+                     Err(dwat::Error::AlignmentAttributeNotFound)));
+
+    Ok(())
+}
+
+#[test]
+fn lookup_all() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_sources(&[
+        "struct dup { int a; } d1; int main() {}",
+        "struct dup { long b; char c; } d2;"
+    ], &["-gdwarf-5"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_all::<dwat::Struct>("dup")?;
+    assert_eq!(found.len(), 2);
+
+    let mut sizes = found.iter().map(|s| s.byte_size(&dwarf))
+                         .collect::<Result<Vec<_>, _>>()?;
+    sizes.sort();
+    assert_eq!(sizes, vec![4, 16]);
+
+    assert!(dwarf.lookup_all::<dwat::Struct>("missing")?.is_empty());
+
+    Ok(())
+}
+
+const FUNCTIONS: &str = "
+int add(int count, int other) {
+    return count + other;
+}
+int main() {
+    return add(1, 2);
+}";
+
+#[test]
+fn subprogram_params() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile(FUNCTIONS)?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Subprogram>("add".to_string())?;
+    let found = found.unwrap();
+
+    let params = found.get_params(&dwarf)?;
+    assert_eq!(params.len(), 2);
+    assert_eq!(params[0].name(&dwarf)?, "count");
+    assert_eq!(params[1].name(&dwarf)?, "other");
+    assert!(!params[0].has_default_value(&dwarf)?);
+
+    Ok(())
+}
+
+const NARROW_ENUM: &str = "
+enum E : unsigned char { A, B, C };
+struct S {
+    E e:3;
+    unsigned int u:5;
+};
+int main() {
+    S s;
+}";
+
+#[test]
+fn narrow_enum() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_with(NARROW_ENUM,
+                                       &["-x", "c++", "-gdwarf-5"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Enum>("E".to_string())?;
+    let found = found.unwrap();
+    assert_eq!(found.byte_size(&dwarf)?, 1);
+    assert!(matches!(found.bit_size(&dwarf),
+                     Err(dwat::Error::BitSizeAttributeNotFound)));
+
+    let s = dwarf.lookup_type::<dwat::Struct>("S".to_string())?.unwrap();
+    let members = s.members(&dwarf)?;
+    assert_eq!(members[0].byte_size(&dwarf)?, 1);
+    assert_eq!(members[0].bit_size(&dwarf)?, 3);
+    assert_eq!(members[0].bit_layout(&dwarf)?.bit_offset, 0);
+    assert!(s.to_string(&dwarf)?.contains("enum E e:3;"));
+
+    Ok(())
+}
+
+#[test]
+fn for_each_member() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile(PADDED)?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let mut fields = vec![];
+    dwarf.for_each_member("padded", |member, dwarf| {
+        fields.push((member.name(dwarf)?, member.offset(dwarf)?));
+        Ok(())
+    })?;
+    assert_eq!(fields, vec![("ui".to_string(), 0), ("ull".to_string(), 8)]);
+
+    let missing = dwarf.for_each_member("missing", |_, _| Ok(()));
+    assert!(matches!(missing, Err(dwat::Error::TypeNotFound(_))));
+
+    Ok(())
+}
+
+#[test]
+fn member_offsets() -> anyhow::Result<()> {
+    use std::collections::HashMap;
+
+    let (_tmpdir, path) = compile(PADDED)?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let offsets = dwarf.member_offsets("padded")?;
+    assert_eq!(offsets, HashMap::from([("ui".to_string(), 0),
+                                       ("ull".to_string(), 8)]));
+
+    assert!(matches!(dwarf.member_offsets("missing"),
+                     Err(dwat::Error::TypeNotFound(_))));
+
+    // static members have no location and anonymous members no name
+    let (_tmpdir, path) = compile_sources(&["
+struct counter {
+    static int instances;
+    int value;
+    union { int i; float f; };
+    long tail;
+} counter;
+int main() {}"], &["-g", "-x", "c++"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let offsets = dwarf.member_offsets("counter")?;
+    assert_eq!(offsets, HashMap::from([("value".to_string(), 0),
+                                       ("tail".to_string(), 8)]));
+
+    Ok(())
+}
+
+#[test]
+fn members_where() -> anyhow::Result<()> {
+    use dwat::prelude::*;
+
+    let (_tmpdir, path) = compile("
+struct mixed {
+    int count;
+    char *name;
+    long values[4];
+    void (*callback)(void);
+    const struct mixed *next;
+} mixed;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let mixed = dwarf.lookup_type::<dwat::Struct>("mixed".to_string())?;
+    let mixed = mixed.unwrap();
+
+    let pointers = mixed.members_where(&dwarf, |member, dwarf| {
+        Ok(matches!(member.get_type(dwarf)?, dwat::Type::Pointer(_)))
+    })?;
+    let names = pointers.iter().map(|member| member.name(&dwarf))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(names, ["name", "callback", "next"]);
+
+    let large = mixed.members_where(&dwarf, |member, dwarf| {
+        Ok(member.byte_size(dwarf)? > 8)
+    })?;
+    assert_eq!(large.len(), 1);
+    assert_eq!(large[0].name(&dwarf)?, "values");
+
+    // the first error of the predicate is returned
+    let failing = mixed.members_where(&dwarf, |_, _| {
+        Err(dwat::Error::TypeNotFound("mixed".to_string()))
+    });
+    assert!(matches!(failing, Err(dwat::Error::TypeNotFound(_))));
+
+    Ok(())
+}
+
+const LINKAGE: &str = "
+namespace ns {
+int counter;
+int bump(int x) { return x + counter; }
+}
+int main() {
+    return ns::bump(1);
+}";
+
+#[test]
+fn lookup_by_linkage_name() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_with(LINKAGE, &["-x", "c++", "-gdwarf-5"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    // the declaration in the namespace and the out of line definition
+    let found = {
+        dwarf.lookup_tagged_by_linkage_name::<dwat::Subprogram>("_ZN2ns4bumpEi")?
+    };
+    assert_eq!(found.len(), 2);
+    for subprogram in found.iter() {
+        assert_eq!(subprogram.linkage_name(&dwarf)?, "_ZN2ns4bumpEi");
+    }
+
+    // only the definition names its parameters
+    let mut param_names = vec![];
+    for subprogram in found.iter() {
+        for param in subprogram.get_params(&dwarf)? {
+            if let Ok(name) = param.name(&dwarf) {
+                param_names.push(name);
+            }
+        }
+    }
+    assert_eq!(param_names, vec!["x".to_string()]);
+
+    let found = {
+        dwarf.lookup_tagged_by_linkage_name::<dwat::Variable>("_ZN2ns7counterE")?
+    };
+    assert_eq!(found.len(), 2);
+
+    // functions and variables aren't types
+    assert!(dwarf.lookup_by_linkage_name("_ZN2ns4bumpEi")?.is_empty());
+
+    Ok(())
+}
+
+const BASE_TYPES: &str = "
+struct bases {
+    int i;
+    unsigned long ul;
+    signed char c;
+    _Bool b;
+    float f;
+    long double ld;
+};
+int main() {
+    struct bases b;
+}";
+
+#[test]
+fn stdint_names() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile(BASE_TYPES)?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("bases".to_string())?;
+    let found = found.unwrap();
+
+    let names = found.members(&dwarf)?.iter().map(|member| {
+        member.get_type(&dwarf)?.as_base().unwrap().stdint_name(&dwarf)
+    }).collect::<Result<Vec<_>, _>>()?;
+
+    let expected = [Some("int32_t"), Some("uint64_t"), Some("int8_t"),
+                    Some("bool"), Some("float"), None];
+    assert_eq!(names, expected.map(|name| name.map(String::from)));
+
+    Ok(())
+}
+
+const VIRTUAL: &str = "
+struct Shape {
+    virtual int area() { return 0; }
+    int sides;
+};
+int main() {
+    Shape s;
+    return s.area();
+}";
+
+#[test]
+fn artificial_members() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_with(VIRTUAL, &["-x", "c++", "-fno-rtti",
+                                                 "-gdwarf-5"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("Shape".to_string())?;
+    let found = found.unwrap();
+
+    // the vtable pointer is generated by the compiler
+    let members = found.members(&dwarf)?;
+    assert_eq!(found.member_count(&dwarf)?, 2);
+    assert!(members[0].is_artificial(&dwarf)?);
+    assert!(!members[1].is_artificial(&dwarf)?);
+
+    let opts = dwat::FormatOptions::default();
+    assert!(found.to_string_opts(&dwarf, &opts)?.contains("_vptr"));
+
+    let opts = dwat::FormatOptions { hide_artificial: true, ..opts };
+    let repr = found.to_string_opts(&dwarf, &opts)?;
+    assert!(!repr.contains("_vptr"));
+    assert!(repr.contains("int sides;"));
+
+    Ok(())
+}
+
+#[test]
+fn bool_base() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile(BASE_TYPES)?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("bases".to_string())?;
+    let found = found.unwrap();
+
+    let members = found.members(&dwarf)?;
+    let b = members[3].get_type(&dwarf)?.as_base().unwrap();
+    assert!(b.is_bool(&dwarf)?);
+    assert_eq!(b.name(&dwarf)?, "_Bool");
+
+    let c = members[2].get_type(&dwarf)?.as_base().unwrap();
+    assert!(!c.is_bool(&dwarf)?);
+
+    let opts = dwat::FormatOptions { cpp_bool: true, ..Default::default() };
+    let repr = found.to_string_opts(&dwarf, &opts)?;
+    assert!(repr.contains("    bool b;"));
+
+    Ok(())
+}
+
+#[test]
+fn owned_dwarf() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile(PADDED)?;
+
+    let dwarf = dwat::OwnedDwarf::from_path(&path)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("padded".to_string())?;
+    let found = found.unwrap();
+    assert_eq!(found.byte_size(&dwarf)?, 16);
+    assert!(found.to_string_verbose(&dwarf, 1)?.contains("struct padded {"));
+
+    let members = found.members(&dwarf)?;
+    assert_eq!(members[1].bit_layout(&dwarf)?.byte_offset, 8);
+    assert_eq!(dwarf.lookup_all::<dwat::Struct>("padded")?.len(), 1);
+
+    let opts = dwat::FormatOptions::default();
+    let header = dwarf.emit_c_header(&[dwat::Type::Struct(found)], &opts)?;
+    check_header(&header, "_Static_assert(sizeof(struct padded) == 16, \"\");")
+}
+
+#[test]
+fn owned_dwarf_from_path() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile(PADDED)?;
+
+    let dwarf = dwat::OwnedDwarf::from_path(&path)?;
+    let found = dwarf.lookup_type::<dwat::Struct>("padded".to_string())?;
+    assert_eq!(found.unwrap().byte_size(&dwarf)?, 16);
+
+    let missing = path.with_file_name("missing");
+    assert!(matches!(dwat::OwnedDwarf::from_path(missing),
+                     Err(dwat::Error::DwarfLoadError(_))));
+
+    Ok(())
+}
+
+#[test]
+fn owned_dwarf_clone() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile(PADDED)?;
+
+    let dwarf = dwat::OwnedDwarf::from_path(&path)?;
+    let cloned = dwarf.clone();
+
+    let handle = std::thread::spawn(move || -> Result<usize, dwat::Error> {
+        let found = cloned.lookup_type::<dwat::Struct>("padded".to_string())?;
+        found.unwrap().byte_size(&cloned)
+    });
+    assert_eq!(handle.join().unwrap()?, 16);
+
+    // the original is still usable after its clone is dropped
+    let found = dwarf.lookup_type::<dwat::Struct>("padded".to_string())?;
+    assert_eq!(found.unwrap().byte_size(&dwarf)?, 16);
+
+    Ok(())
+}
+
+#[test]
+fn owned_dwarf_threads() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile(PADDED)?;
+
+    let dwarf = std::sync::Arc::new(dwat::OwnedDwarf::from_path(&path)?);
+
+    let handles: Vec<_> = (0..4).map(|_| {
+        let dwarf = std::sync::Arc::clone(&dwarf);
+        std::thread::spawn(move || -> Result<String, dwat::Error> {
+            let found = {
+                dwarf.lookup_type::<dwat::Struct>("padded".to_string())?
+            }.unwrap();
+            assert_eq!(found.byte_size(&*dwarf)?, 16);
+            assert_eq!(found.members(&*dwarf)?.len(), 2);
+            assert_eq!(dwarf.get_named_types::<dwat::Struct>()?.len(), 1);
+            found.to_string(&*dwarf)
+        })
+    }).collect();
+
+    let expected = {
+        let found = dwarf.lookup_type::<dwat::Struct>("padded".to_string())?;
+        found.unwrap().to_string(&*dwarf)?
+    };
+    for handle in handles {
+        assert_eq!(handle.join().unwrap()?, expected);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn lookup_declarations() -> anyhow::Result<()> {
+    use dwat::dwarf::LookupOptions;
+
+    // the first unit only declares the struct
+    let (_tmpdir, path) = compile_sources(&[
+        "struct opaque; struct opaque *p; int main() {}",
+        "struct opaque { int x; } o;"
+    ], &["-gdwarf-5"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let opts = LookupOptions::default();
+    let found = dwarf.lookup_type_opts::<dwat::Struct>("opaque", &opts)?;
+    assert_eq!(found.unwrap().members(&dwarf)?.len(), 1);
+
+    let opts = LookupOptions { skip_declarations: false,
+                               prefer_definition: false };
+    let found = dwarf.lookup_type_opts::<dwat::Struct>("opaque", &opts)?;
+    assert!(found.unwrap().members(&dwarf)?.is_empty());
+
+    let opts = LookupOptions { skip_declarations: false,
+                               prefer_definition: true };
+    let found = dwarf.lookup_type_opts::<dwat::Struct>("opaque", &opts)?;
+    assert_eq!(found.unwrap().members(&dwarf)?.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn named_types_skip_declarations() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_sources(&[
+        "struct opaque; struct opaque *p; int main() {}",
+        "struct opaque { int x; } o;"
+    ], &["-gdwarf-5"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let types = dwarf.get_named_types::<dwat::Struct>()?;
+    let opaques: Vec<_> = {
+        types.iter().filter(|(name, _)| name == "opaque").collect()
+    };
+    assert_eq!(opaques.len(), 1);
+    assert_eq!(opaques[0].1.members(&dwarf)?.len(), 1);
+
+    let map = dwarf.get_named_types_map::<dwat::Struct>()?;
+    assert_eq!(map["opaque"].location, opaques[0].1.location);
+
+    Ok(())
+}
+
+// Build an ELF object holding a single DWARF 5 unit whose entries are added
+// by `build`, for attributes that gcc doesn't emit
+fn craft_dwarf<F>(build: F) -> anyhow::Result<Vec<u8>>
+where F: FnOnce(&mut gimli::write::Unit) {
+    craft_dwarf_for(object::Architecture::X86_64, object::Endianness::Little,
+                    5, build)
+}
+
+// Like craft_dwarf, but for another target and DWARF version, for targets
+// which gcc can't build for here
+fn craft_dwarf_for<F>(arch: object::Architecture, endian: object::Endianness,
+                      version: u16, build: F)
+-> anyhow::Result<Vec<u8>>
+where F: FnOnce(&mut gimli::write::Unit) {
+    use gimli::write::{DwarfUnit, EndianVec, Sections};
+
+    let encoding = gimli::Encoding {
+        format: gimli::Format::Dwarf32,
+        version,
+        address_size: 8,
+    };
+    let mut dwarf = DwarfUnit::new(encoding);
+    build(&mut dwarf.unit);
+
+    let gimli_endian = match endian {
+        object::Endianness::Little => gimli::RunTimeEndian::Little,
+        object::Endianness::Big => gimli::RunTimeEndian::Big,
+    };
+    let mut sections = Sections::new(EndianVec::new(gimli_endian));
+    dwarf.write(&mut sections)?;
+    debug_elf(arch, endian, &sections)
+}
+
+// Wrap written DWARF sections in an ELF object for `arch`
+fn debug_elf(arch: object::Architecture, endian: object::Endianness,
+             sections: &gimli::write::Sections<gimli::write::EndianVec<
+                 gimli::RunTimeEndian
+             >>)
+-> anyhow::Result<Vec<u8>> {
+    let mut elf = object::write::Object::new(
+        object::BinaryFormat::Elf,
+        arch,
+        endian
+    );
+    sections.for_each(|id, data| -> anyhow::Result<()> {
+        if !data.slice().is_empty() {
+            let section = elf.add_section(vec![], id.name().into(),
+                                          object::SectionKind::Debug);
+            elf.append_section_data(section, data.slice(), 1);
+        }
+        Ok(())
+    })?;
+    Ok(elf.write()?)
+}
+
+#[test]
+fn mutable_members() -> anyhow::Result<()> {
+    use gimli::write::AttributeValue;
+
+    // struct Cache { int key; mutable int hits; };
+    let elf = craft_dwarf(|unit| {
+        let root = unit.root();
+        let int = unit.add(root, gimli::DW_TAG_base_type);
+        let entry = unit.get_mut(int);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"int".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(4));
+        entry.set(gimli::DW_AT_encoding,
+                  AttributeValue::Encoding(gimli::DW_ATE_signed));
+
+        let cache = unit.add(root, gimli::DW_TAG_structure_type);
+        let entry = unit.get_mut(cache);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"Cache".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(8));
+
+        for (idx, name) in ["key", "hits"].iter().enumerate() {
+            let member = unit.add(cache, gimli::DW_TAG_member);
+            let entry = unit.get_mut(member);
+            entry.set(gimli::DW_AT_name,
+                      AttributeValue::String(name.as_bytes().to_vec()));
+            entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(int));
+            entry.set(gimli::DW_AT_data_member_location,
+                      AttributeValue::Udata(idx as u64 * 4));
+            if *name == "hits" {
+                entry.set(gimli::DW_AT_mutable, AttributeValue::Flag(true));
+            }
+        }
+    })?;
+
+    let dwarf = Dwarf::load(&*elf)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("Cache".to_string())?;
+    let found = found.unwrap();
+
+    let members = found.members(&dwarf)?;
+    assert!(!members[0].is_mutable(&dwarf)?);
+    assert!(members[1].is_mutable(&dwarf)?);
+
+    let repr = found.to_string(&dwarf)?;
+    assert!(repr.contains("    int key;"));
+    assert!(repr.contains("    mutable int hits;"));
+
+    Ok(())
+}
+
+#[test]
+fn suggest_names() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile("
+struct node { int a; } n;
+struct note { int b; } m;
+struct packet { int c; } p;
+int main() {}")?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("nodee".to_string())?;
+    assert!(found.is_none());
+
+    let suggestions = dwarf.suggest_names::<dwat::Struct>("nodee", 2)?;
+    assert_eq!(suggestions, vec!["node".to_string(), "note".to_string()]);
+
+    Ok(())
+}
+
+#[test]
+fn type_unit_signatures() -> anyhow::Result<()> {
+    // the structs are moved to .debug_types and referenced by signature
+    let (_tmpdir, path) = compile_with("
+struct inner { int a; };
+struct outer { struct inner in; int b; } o;
+int main() {}", &["-gdwarf-4", "-fdebug-types-section"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let var = dwarf.lookup_type::<dwat::Variable>("o".to_string())?.unwrap();
+    let outer = match var.get_type(&dwarf)? {
+        dwat::Type::Struct(s) => s,
+        _ => panic!("expected a struct")
+    };
+    assert_eq!(outer.name(&dwarf)?, "outer");
+    assert_eq!(outer.byte_size(&dwarf)?, 8);
+
+    let member = outer.members(&dwarf)?[0];
+    let inner = member.get_type(&dwarf)?.as_struct().unwrap();
+    assert_eq!(inner.name(&dwarf)?, "inner");
+
+    assert!(outer.to_string(&dwarf)?.contains("struct inner in;"));
+
+    // sizes, alignment and layout follow the signature as well
+    assert_eq!(member.byte_size(&dwarf)?, 4);
+    let verbose = outer.to_string_verbose(&dwarf, 1)?;
+    assert!(verbose.contains("/* total size: 8 */"));
+    assert_eq!(outer.alignment_stats(&dwarf)?.sum_member_size, 8);
+    assert!(!outer.is_packed(&dwarf)?);
+    assert_eq!(outer.flat_offset_table(&dwarf)?,
+               vec![("in.a".to_string(), 0, 4), ("b".to_string(), 4, 4)]);
+
+    Ok(())
+}
+
+const FUNC_PTRS: &str = "
+typedef void (*fn_t)(int, char *);
+typedef int op_t(long);
+struct ops {
+    void (*cb)(int, char *);
+    fn_t cb2;
+    const fn_t cb3;
+    op_t *cb4;
+    int (*arr[2])(short);
+    void (*const ccb)(int);
+    void *p;
+} o;
+int main() {}";
+
+#[test]
+fn function_pointers() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile(FUNC_PTRS)?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let ops = dwarf.lookup_type::<dwat::Struct>("ops".to_string())?.unwrap();
+    let mut predicates = vec![];
+    for member in ops.members(&dwarf)? {
+        let typ = member.get_type(&dwarf)?;
+        predicates.push(typ.is_function_pointer(&dwarf)?);
+    }
+    assert_eq!(predicates,
+               vec![true, true, true, true, false, true, false]);
+
+    // members and typedefs share the declarator syntax
+    let opts = dwat::FormatOptions::default();
+    let header = dwarf.emit_c_header(&[dwat::Type::Struct(ops)], &opts)?;
+    assert!(header.contains("typedef void (*fn_t)(int, char *);\n"));
+
+    let ops_str = ops.to_string(&dwarf)?;
+    assert!(ops_str.contains("    void (*cb)(int, char *);\n"));
+    assert!(ops_str.contains("    const fn_t cb3;\n"));
+    assert!(ops_str.contains("    int (*arr[2])(short int);\n"));
+    assert!(ops_str.contains("    void (*const ccb)(int);\n"));
+
+    Ok(())
+}
+
+#[test]
+fn flat_offset_table() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile("
+struct point { int x; int y; };
+typedef struct point point_t;
+struct shape {
+    char kind;
+    point_t origin;
+    union {
+        struct { short w, h; } rect;
+        int radius;
+    };
+    const struct point verts[3];
+    struct shape *next;
+} s;
+int main() {}")?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let shape = dwarf.lookup_type::<dwat::Struct>("shape".to_string())?;
+    let table = shape.unwrap().flat_offset_table(&dwarf)?;
+    let expected = vec![
+        ("kind", 0, 1),
+        ("origin.x", 4, 4),
+        ("origin.y", 8, 4),
+        ("rect.w", 12, 2),
+        ("rect.h", 14, 2),
+        ("radius", 12, 4),
+        ("verts", 16, 24),
+        ("next", 40, 8),
+    ];
+    let expected: Vec<_> = expected.into_iter()
+        .map(|(name, offset, size)| (name.to_string(), offset, size))
+        .collect();
+    assert_eq!(table, expected);
+
+    Ok(())
+}
+
+#[test]
+fn flatten_scalars() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile("
+typedef unsigned int u32;
+struct point { u32 x; u32 y; };
+struct shape {
+    char kind;
+    struct { struct point p[2]; } b;
+    union {
+        short w;
+        int radius;
+    };
+    char grid[2][2];
+    struct shape *next;
+} s;
+int main() {}")?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let shape = dwarf.lookup_type::<dwat::Struct>("shape".to_string())?;
+    let shape = shape.unwrap();
+
+    let fields: Vec<_> = shape.flatten_scalars(&dwarf, true)?.into_iter()
+        .map(|field| (field.path, field.offset, field.type_name, field.size))
+        .collect();
+    let expected = vec![
+        ("kind", 0, "char", 1),
+        ("b.p[0].x", 4, "u32", 4),
+        ("b.p[0].y", 8, "u32", 4),
+        ("b.p[1].x", 12, "u32", 4),
+        ("b.p[1].y", 16, "u32", 4),
+        ("w", 20, "short int", 2),
+        ("radius", 20, "int", 4),
+        ("grid[0][0]", 24, "char", 1),
+        ("grid[0][1]", 25, "char", 1),
+        ("grid[1][0]", 26, "char", 1),
+        ("grid[1][1]", 27, "char", 1),
+        ("next", 32, "struct shape *", 8),
+    ];
+    let expected: Vec<_> = expected.into_iter()
+        .map(|(path, offset, name, size)| {
+            (path.to_string(), offset, name.to_string(), size)
+        })
+        .collect();
+    assert_eq!(fields, expected);
+
+    // arrays can be kept whole
+    let fields = shape.flatten_scalars(&dwarf, false)?;
+    let paths: Vec<_> = fields.iter().map(|field| field.path.as_str())
+        .collect();
+    assert_eq!(paths, vec!["kind", "b.p", "w", "radius", "grid", "next"]);
+    assert_eq!(fields[1].size, 16);
+
+    Ok(())
+}
+
+#[test]
+fn dump_enums() -> anyhow::Result<()> {
+    // every unit defines `color`, only the last two agree on its enumerators
+    let (_tmpdir, path) = compile_sources(&[
+        "enum color { RED, GREEN }; enum color c0; int main() {}",
+        "enum color { CYAN, MAGENTA }; enum color c1;",
+        "enum color { CYAN, MAGENTA }; enum color c2;",
+    ], &["-g"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+    assert_eq!(dwarf.get_fg_named_enums_map()?.len(), 2);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_dwat"))
+        .args(["dump", "--kind", "enum"])
+        .arg(&path)
+        .output()?;
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout)?;
+    assert_eq!(stdout.matches("enum color {").count(), 2);
+    assert_eq!(stdout.matches("RED = 0,").count(), 1);
+    assert_eq!(stdout.matches("CYAN = 0,").count(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn typedef_named_anonymous_types() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile("
+typedef struct { int x; } Foo;
+typedef enum { A, B } Bar;
+struct { int y; } unnamed;
+Foo foo;
+Bar bar;
+int main() {}")?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    // only named on request
+    assert!(dwarf.get_named_types::<dwat::Struct>()?.is_empty());
+    assert!(dwarf.get_named_types_map::<dwat::Enum>()?.is_empty());
+
+    let mut found = 0;
+    let mut report = |p: dwat::dwarf::ScanProgress| found = p.items_found;
+    let structs = dwarf.get_named_types_with_typedef_names_with_progress::<
+        dwat::Struct
+    >(Some(&mut report))?;
+    assert_eq!(structs.len(), 1);
+    assert_eq!(structs[0].0, "Foo");
+    assert_eq!(structs[0].1.members(&dwarf)?.len(), 1);
+    // the typedefs walked along the way aren't items
+    assert_eq!(found, 2);
+
+    let enums = dwarf.get_named_types_with_typedef_names::<dwat::Enum>()?;
+    let names: Vec<&str> = enums.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(names, vec!["Bar"]);
+
+    // typedefs are matched within their own compile unit
+    let (_tmpdir, path) = compile_sources(&[
+        "typedef struct { int x; } Foo; Foo foo; int main() {}",
+        "typedef struct { char c; } Baz; Baz baz;",
+    ], &["-g"])?;
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let structs = dwarf.get_named_types_with_typedef_names::<dwat::Struct>()?;
+    let names: Vec<&str> = structs.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(names, vec!["Foo", "Baz"]);
+
+    Ok(())
+}
+
+#[test]
+fn subprogram_decl_location() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_sources(&[
+        "static int helper(int x) { return x; }\nint main() { return helper(0); }",
+        "\n\nstatic int helper(int x) { return -x; }\nint f(void) { return helper(1); }",
+    ], &["-g"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let helpers: Vec<dwat::Subprogram> = {
+        dwarf.get_named_types::<dwat::Subprogram>()?.into_iter()
+        .filter(|(name, _)| name == "helper")
+        .map(|(_, subprogram)| subprogram)
+        .collect()
+    };
+    assert_eq!(helpers.len(), 2);
+    assert_ne!(helpers[0], helpers[1]);
+
+    let mut sites = vec![];
+    for helper in helpers.iter() {
+        sites.push((helper.decl_file(&dwarf)?, helper.decl_line(&dwarf)?));
+    }
+    sites.sort();
+    assert!(sites[0].0.ends_with("/src0.c"));
+    assert_eq!(sites[0].1, 1);
+    assert!(sites[1].0.ends_with("/src1.c"));
+    assert_eq!(sites[1].1, 3);
+
+    Ok(())
+}
+
+#[test]
+fn const_value_bytes() -> anyhow::Result<()> {
+    // optimized out constants keep their value in DW_AT_const_value
+    let (_tmpdir, path) = compile_with("
+struct pair { int a, b; };
+static const struct pair table = { 1, 2 };
+static const int n = 7;
+int main(void) { return table.a + n; }", &["-g", "-O2"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let table = dwarf.lookup_type::<dwat::Variable>("table".to_string())?;
+    let bytes = table.unwrap().const_value_bytes(&dwarf)?;
+    assert_eq!(bytes, Some(vec![1, 0, 0, 0, 2, 0, 0, 0]));
+
+    let n = dwarf.lookup_type::<dwat::Variable>("n".to_string())?;
+    assert_eq!(n.unwrap().const_value_bytes(&dwarf)?, None);
+
+    Ok(())
+}
+
+#[test]
+fn types_with_cu() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_sources(&[
+        SIMPLE,
+        "struct other { int z; } o;",
+    ], &["-g"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let structs = dwarf.types_with_cu::<dwat::Struct>()?;
+    assert_eq!(structs.len(), 2);
+    for (cu, name, _) in structs.iter() {
+        let source = match name.as_str() {
+            "simple" => "src0.c",
+            "other" => "src1.c",
+            _ => panic!("unexpected struct {name}")
+        };
+        assert!(cu.name(&dwarf)?.ends_with(source));
+    }
+    assert_ne!(structs[0].0, structs[1].0);
+
+    Ok(())
+}
+
+#[test]
+fn array_unknown_element_size() -> anyhow::Result<()> {
+    use gimli::write::AttributeValue;
+
+    // struct fwd; struct empty {}; struct fwd fwds[4]; struct empty empties[4];
+    let elf = craft_dwarf(|unit| {
+        let root = unit.root();
+        let fwd = unit.add(root, gimli::DW_TAG_structure_type);
+        let entry = unit.get_mut(fwd);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"fwd".to_vec()));
+        entry.set(gimli::DW_AT_declaration, AttributeValue::Flag(true));
+
+        let empty = unit.add(root, gimli::DW_TAG_structure_type);
+        let entry = unit.get_mut(empty);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"empty".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(0));
+
+        for (name, elem) in [("fwds", fwd), ("empties", empty)] {
+            let array = unit.add(root, gimli::DW_TAG_array_type);
+            unit.get_mut(array).set(gimli::DW_AT_type,
+                                    AttributeValue::UnitRef(elem));
+            let range = unit.add(array, gimli::DW_TAG_subrange_type);
+            unit.get_mut(range).set(gimli::DW_AT_upper_bound,
+                                    AttributeValue::Udata(3));
+
+            let var = unit.add(root, gimli::DW_TAG_variable);
+            let entry = unit.get_mut(var);
+            entry.set(gimli::DW_AT_name,
+                      AttributeValue::String(name.as_bytes().to_vec()));
+            entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(array));
+        }
+    })?;
+
+    let dwarf = Dwarf::load(&*elf)?;
+
+    for name in ["fwds", "empties"] {
+        let var = dwarf.lookup_type::<dwat::Variable>(name.to_string())?;
+        let array = var.unwrap().get_type(&dwarf)?.as_array().unwrap();
+        assert_eq!(array.get_bound(&dwarf)?, 4);
+        assert!(matches!(array.byte_size(&dwarf),
+                         Err(dwat::Error::UnknownElementSizeError(_))));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn compile_unit_source_files() -> anyhow::Result<()> {
+    // compile with relative paths so the line program doesn't hold absolute
+    // ones for the project files
+    let tmp_dir = TempDir::new()?;
+    std::fs::create_dir(tmp_dir.path().join("inc"))?;
+    std::fs::write(tmp_dir.path().join("inc/defs.h"),
+                   "struct defs { size_t len; };")?;
+    std::fs::write(tmp_dir.path().join("main.c"),
+                   "#include <stddef.h>\n#include \"defs.h\"\n\
+                    struct defs d; int main() {}")?;
+
+    let output = Command::new("gcc")
+        .args(["-g", "-Iinc", "main.c", "-o", "bin"])
+        .current_dir(tmp_dir.path())
+        .output()?;
+    assert!(output.status.success());
+
+    let file = File::open(tmp_dir.path().join("bin"))?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let (_, cu) = dwarf.get_named_types::<dwat::CompileUnit>()?.into_iter()
+        .find(|(name, _)| name == "main.c")
+        .unwrap();
+
+    let comp_dir = cu.comp_dir(&dwarf)?;
+    assert_eq!(PathBuf::from(&comp_dir).canonicalize()?,
+               tmp_dir.path().canonicalize()?);
+
+    let files = cu.source_files(&dwarf)?;
+    assert!(files.contains(&format!("{comp_dir}/main.c")));
+    assert!(files.contains(&format!("{comp_dir}/inc/defs.h")));
+    // system headers are already absolute
+    let stddef = files.iter().find(|f| f.ends_with("/stddef.h")).unwrap();
+    assert!(!stddef.starts_with(&comp_dir));
+    assert!(files.iter().all(|f| f.starts_with('/')));
+
+    Ok(())
+}
+
+#[test]
+fn fg_named_structs_map() -> anyhow::Result<()> {
+    // DWARF 5 bitfields have no DW_AT_data_member_location
+    let (_tmpdir, path) = compile_sources(&[
+        "struct flags { int a:3; int b:5; int c; } f; int main() {}",
+        "struct pair { int x, y; } p;",
+        "struct pair { long x, y; } q;",
+        "struct pair { int a, b; } r;",
+    ], &["-gdwarf-5"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let map = dwarf.get_fg_named_structs_map()?;
+    let mut keys: Vec<_> = map.keys()
+        .map(|k| (k.name.clone(), k.byte_size, k.members.clone()))
+        .collect();
+    keys.sort();
+
+    let members = |list: &[(&str, usize)]| -> Vec<(String, usize)> {
+        list.iter().map(|(name, off)| (name.to_string(), *off)).collect()
+    };
+    assert_eq!(keys, vec![
+        ("flags".to_string(), 8, members(&[("a", 0), ("b", 0), ("c", 4)])),
+        ("pair".to_string(), 8, members(&[("a", 0), ("b", 4)])),
+        ("pair".to_string(), 8, members(&[("x", 0), ("y", 4)])),
+        ("pair".to_string(), 16, members(&[("x", 0), ("y", 8)])),
+    ]);
+
+    // the fast map takes the second 8 byte pair to be the first one
+    let fast = dwarf.get_fg_named_structs_map_fast()?;
+    let mut keys: Vec<_> = fast.keys()
+        .map(|k| (k.name.clone(), k.byte_size, k.members.clone()))
+        .collect();
+    keys.sort();
+    assert_eq!(keys, vec![
+        ("flags".to_string(), 8, members(&[("a", 0), ("b", 0), ("c", 4)])),
+        ("pair".to_string(), 8, members(&[("x", 0), ("y", 4)])),
+        ("pair".to_string(), 16, members(&[("x", 0), ("y", 8)])),
+    ]);
+
+    Ok(())
+}
+
+const METHODS: &str = "
+extern \"C\" void __cxa_pure_virtual() {}
+struct Shape {
+    virtual int area() = 0;
+    virtual int sides() { return 0; }
+    int id() { return 1; }
+};
+struct Square : Shape {
+    int area() { return 4; }
+};
+int main() {
+    Square s;
+    return s.area() + s.sides() + s.id();
+}";
+
+#[test]
+fn struct_methods() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_with(METHODS, &["-g", "-x", "c++",
+                                                 "-fno-rtti"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("Shape".to_string())?;
+    let found = found.unwrap();
+
+    let mut methods = vec![];
+    for method in found.methods(&dwarf)? {
+        methods.push((method.name(&dwarf)?, method.virtuality(&dwarf)?));
+    }
+    // gcc doesn't distinguish pure virtual methods in DW_AT_virtuality
+    assert!(methods.contains(&("area".to_string(),
+                               gimli::DW_VIRTUALITY_virtual)));
+    assert!(methods.contains(&("sides".to_string(),
+                               gimli::DW_VIRTUALITY_virtual)));
+    assert!(methods.contains(&("id".to_string(),
+                               gimli::DW_VIRTUALITY_none)));
+
+    // methods aren't members
+    assert_eq!(found.member_count(&dwarf)?, 1);
+
+    Ok(())
+}
+
+const VTABLE: &str = "
+extern \"C\" void __cxa_pure_virtual() {}
+struct Animal {
+    virtual int legs() { return 4; }
+    virtual void speak(int times, const char *what) {}
+    virtual int id() = 0;
+    int plain() { return 0; }
+};
+struct Bird : Animal {
+    int legs() { return 2; }
+    int id() { return 1; }
+};
+int main() {
+    Bird b;
+    b.speak(1, \"tweet\");
+    return b.legs() + b.plain();
+}";
+
+#[test]
+fn struct_vtable() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_with(VTABLE, &["-g", "-x", "c++",
+                                                "-fno-rtti"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let animal = dwarf.lookup_type::<dwat::Struct>("Animal".to_string())?;
+    let vtable = animal.unwrap().vtable(&dwarf)?;
+
+    let slots: Vec<(usize, &str)> = {
+        vtable.iter().map(|e| (e.slot, e.name.as_str())).collect()
+    };
+    assert_eq!(slots, [(0, "legs"), (1, "speak"), (2, "id")]);
+    assert_eq!(vtable[1].signature, "void speak(int, const char *)");
+
+    // overrides keep the slot of the base method
+    let bird = dwarf.lookup_type::<dwat::Struct>("Bird".to_string())?;
+    let vtable = bird.unwrap().vtable(&dwarf)?;
+    let slots: Vec<(usize, &str)> = {
+        vtable.iter().map(|e| (e.slot, e.name.as_str())).collect()
+    };
+    assert_eq!(slots, [(0, "legs"), (2, "id")]);
+    assert_eq!(vtable[0].signature, "int legs()");
+
+    Ok(())
+}
+
+// struct be { unsigned int a:3; unsigned int b:5; short c; int d; } on a big
+// endian target, with DWARF 4 or DWARF 5 style bitfields
+fn craft_big_endian(version: u16) -> anyhow::Result<Vec<u8>> {
+    use gimli::write::AttributeValue;
+
+    craft_dwarf_for(object::Architecture::PowerPc64, object::Endianness::Big,
+                    version, |unit| {
+        let root = unit.root();
+        let mut base = |name: &str, size: u64| {
+            let id = unit.add(root, gimli::DW_TAG_base_type);
+            let entry = unit.get_mut(id);
+            entry.set(gimli::DW_AT_name,
+                      AttributeValue::String(name.as_bytes().to_vec()));
+            entry.set(gimli::DW_AT_byte_size,
+                      AttributeValue::Data1(size as u8));
+            entry.set(gimli::DW_AT_encoding,
+                      AttributeValue::Encoding(gimli::DW_ATE_signed));
+            id
+        };
+        let uint = base("unsigned int", 4);
+        let short = base("short int", 2);
+        let int = base("int", 4);
+
+        let be = unit.add(root, gimli::DW_TAG_structure_type);
+        let entry = unit.get_mut(be);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"be".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Data2(8));
+
+        // name, type, byte offset, bit offset from the start and bit size
+        let members = [("a", uint, 0, 0, 3), ("b", uint, 0, 3, 5),
+                       ("c", short, 2, 16, 0), ("d", int, 4, 32, 0)];
+        for (name, typ, offset, bit_offset, bit_size) in members {
+            let member = unit.add(be, gimli::DW_TAG_member);
+            let entry = unit.get_mut(member);
+            entry.set(gimli::DW_AT_name,
+                      AttributeValue::String(name.as_bytes().to_vec()));
+            entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(typ));
+            if bit_size == 0 {
+                entry.set(gimli::DW_AT_data_member_location,
+                          AttributeValue::Udata(offset));
+                continue;
+            }
+            entry.set(gimli::DW_AT_bit_size, AttributeValue::Udata(bit_size));
+            if version >= 5 {
+                entry.set(gimli::DW_AT_data_bit_offset,
+                          AttributeValue::Udata(bit_offset));
+            } else {
+                // counted from the most significant bit of the storage unit,
+                // which comes first in memory on big endian targets
+                entry.set(gimli::DW_AT_byte_size, AttributeValue::Data1(4));
+                entry.set(gimli::DW_AT_bit_offset,
+                          AttributeValue::Udata(bit_offset));
+                entry.set(gimli::DW_AT_data_member_location,
+                          AttributeValue::Udata(offset));
+            }
+        }
+    })
+}
+
+#[test]
+fn big_endian() -> anyhow::Result<()> {
+    for version in [4, 5] {
+        let elf = craft_big_endian(version)?;
+        let dwarf = Dwarf::load(&*elf)?;
+
+        let found = dwarf.lookup_type::<dwat::Struct>("be".to_string())?;
+        let found = found.unwrap();
+        assert_eq!(found.byte_size(&dwarf)?, 8);
+
+        let members = found.members(&dwarf)?;
+        let layouts = {
+            members.iter().map(|m| m.bit_layout(&dwarf))
+                   .collect::<Result<Vec<_>, _>>()?
+        };
+        let layout = |byte_offset, bit_offset, bit_size| {
+            dwat::BitLayout { byte_offset, bit_offset, bit_size }
+        };
+        assert_eq!(layouts, [layout(0, 0, 3), layout(0, 3, 5),
+                             layout(2, 0, 16), layout(4, 0, 32)]);
+
+        assert_eq!(members[3].offset(&dwarf)?, 4);
+        assert_eq!(members[2].byte_size(&dwarf)?, 2);
+
+        let repr = found.to_string(&dwarf)?;
+        assert!(repr.contains("    unsigned int a:3;\n"));
+        assert!(repr.contains("    unsigned int b:5;\n"));
+    }
+    Ok(())
+}
+
+#[test]
+fn member_type_name() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_with("
+struct foo { int x; };
+struct names {
+    struct foo *p;
+    unsigned int arr[4];
+    const char *s;
+    void (*cb)(int);
+} n;
+int main() {}", &["-g"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("names".to_string())?;
+    let members = found.unwrap().members(&dwarf)?;
+
+    let mut names = vec![];
+    for member in members.iter() {
+        names.push(member.type_name(&dwarf)?);
+    }
+    assert_eq!(names[0], "struct foo *");
+    assert_eq!(names[1], "unsigned int [4]");
+    assert_eq!(names[2], "const char *");
+    assert_eq!(names[3], "void (*)(int)");
+
+    let typ = members[0].get_type(&dwarf)?;
+    assert_eq!(typ.type_name(&dwarf)?, "struct foo *");
+
+    Ok(())
+}
+
+#[test]
+fn pointer_to_array() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_with("
+struct arrptrs {
+    int (*p)[4];
+    int (*const cp)[4];
+    char (*ap[2])[3];
+} a;
+int main() {}", &["-g"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("arrptrs".to_string())?;
+    let found = found.unwrap();
+
+    let repr = found.to_string(&dwarf)?;
+    assert!(repr.contains("    int (*p)[4];\n"));
+    assert!(repr.contains("    int (*const cp)[4];\n"));
+    assert!(repr.contains("    char (*ap[2])[3];\n"));
+
+    let members = found.members(&dwarf)?;
+    assert_eq!(members[0].type_name(&dwarf)?, "int (*)[4]");
+
+    Ok(())
+}
+
+#[test]
+fn missing_debug_abbrev() -> anyhow::Result<()> {
+    let (tmpdir, path) = compile(SIMPLE)?;
+
+    let stripped = tmpdir.path().join("stripped");
+    let output = Command::new("objcopy")
+        .arg("--remove-section=.debug_abbrev")
+        .arg(&path)
+        .arg(&stripped)
+        .output()?;
+    assert!(output.status.success());
+
+    let file = File::open(&stripped)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    match Dwarf::load(&*mmap) {
+        Err(dwat::Error::DwarfLoadError(msg)) => {
+            assert_eq!(msg, "missing .debug_abbrev")
+        },
+        _ => panic!("expected a DwarfLoadError")
+    }
+    assert!(dwat::OwnedDwarf::load(&*mmap).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn any_type() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_with("
+struct point { int x; int y; } p;
+enum color { RED, GREEN } c;
+typedef unsigned long size_type;
+size_type s;
+int main() {}", &["-g"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let mut types: Vec<Box<dyn AnyType<Dwarf>>> = vec![];
+    for name in ["p", "c", "s"] {
+        let var = dwarf.lookup_type::<dwat::Variable>(name.to_string())?;
+        types.push(Box::new(var.unwrap().get_type(&dwarf)?));
+    }
+
+    let kinds: Vec<gimli::DwTag> = types.iter().map(|t| t.kind()).collect();
+    assert_eq!(kinds, [gimli::DW_TAG_structure_type,
+                       gimli::DW_TAG_enumeration_type,
+                       gimli::DW_TAG_typedef]);
+
+    assert_eq!(types[0].name(&dwarf)?, "point");
+    assert_eq!(types[0].byte_size(&dwarf)?, 8);
+    assert!(types[0].to_string(&dwarf)?.starts_with("struct point {"));
+
+    assert_eq!(types[1].name(&dwarf)?, "color");
+    assert!(types[1].to_string(&dwarf)?.starts_with("enum color {"));
+
+    assert_eq!(types[2].name(&dwarf)?, "size_type");
+    assert_eq!(types[2].byte_size(&dwarf)?, 8);
+    assert_eq!(types[2].to_string(&dwarf)?,
+               "typedef long unsigned int size_type;");
+
+    Ok(())
+}
+
+#[test]
+fn type_snapshots() -> anyhow::Result<()> {
+    use dwat::{TypeSnapshot, MemberSnapshot};
+
+    let (_tmpdir, path) = compile_with("
+enum color { RED, GREEN = 5 } c;
+struct node { struct node *next; unsigned int flags:3; } *n;
+int main() {}", &["-g"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let var = dwarf.lookup_type::<dwat::Variable>("c".to_string())?.unwrap();
+    assert_eq!(var.get_type(&dwarf)?.snapshot(&dwarf)?, TypeSnapshot::Enum {
+        name: Some("color".to_string()),
+        byte_size: 4,
+        enumerators: vec![("RED".to_string(), 0), ("GREEN".to_string(), 5)]
+    });
+
+    let var = dwarf.lookup_type::<dwat::Variable>("n".to_string())?.unwrap();
+    let ptr = var.get_type(&dwarf)?;
+    assert_eq!(ptr.snapshot(&dwarf)?, TypeSnapshot::Pointer {
+        pointee: "struct node".to_string(),
+        byte_size: 8
+    });
+
+    // nested aggregates are only referred to by name
+    let node = ptr.as_pointer().unwrap().get_type(&dwarf)?;
+    let members = match node.snapshot(&dwarf)? {
+        TypeSnapshot::Struct { members, .. } => members,
+        snapshot => panic!("expected a struct, got {snapshot:?}")
+    };
+    assert_eq!(members, [
+        MemberSnapshot { name: Some("next".to_string()),
+                         type_name: "struct node *".to_string(),
+                         bit_offset: 0, bit_size: None },
+        MemberSnapshot { name: Some("flags".to_string()),
+                         type_name: "unsigned int".to_string(),
+                         bit_offset: 64, bit_size: Some(3) },
+    ]);
+
+    Ok(())
+}
+
+#[test]
+fn subprogram_this_type() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_with("
+struct Counter {
+    int n;
+    int get() const { return n; }
+    void inc() { n++; }
+    static int make() { return 0; }
+};
+int main() {
+    Counter c;
+    c.inc();
+    return c.get() + Counter::make();
+}", &["-g", "-x", "c++", "-fno-rtti"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let counter = dwarf.lookup_type::<dwat::Struct>("Counter".to_string())?;
+    let counter = counter.unwrap();
+
+    let mut checked = 0;
+    for method in counter.methods(&dwarf)? {
+        let name = method.name(&dwarf)?;
+        let this_type = method.this_type(&dwarf)?;
+        match name.as_str() {
+            "get" | "inc" => {
+                assert!(method.is_method(&dwarf)?);
+                let class = this_type.unwrap().as_struct().unwrap();
+                assert_eq!(class.name(&dwarf)?, "Counter");
+            },
+            "make" => {
+                assert!(!method.is_method(&dwarf)?);
+                assert!(this_type.is_none());
+            },
+            _ => continue
+        }
+        checked += 1;
+    }
+    assert_eq!(checked, 3);
+
+    // main is a free function
+    let main = dwarf.lookup_type::<dwat::Subprogram>("main".to_string())?;
+    assert!(!main.unwrap().is_method(&dwarf)?);
+
+    Ok(())
+}
+
+#[test]
+fn format_indent() -> anyhow::Result<()> {
+    use dwat::{FormatOptions, Indent};
+
+    let (_tmpdir, path) = compile_with("
+struct outer {
+    int a;
+    struct { int b; } inner;
+    enum { X } e;
+} o;
+int main() {}", &["-g"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("outer".to_string())?;
+    let found = found.unwrap();
+
+    assert_eq!(found.to_string(&dwarf)?, "struct outer {
+    int a;
+    struct {
+        int b;
+    } inner;
+    enum {
+        X = 0,
+    } e;
+};");
+
+    let opts = FormatOptions { indent: Indent::Tabs, ..Default::default() };
+    assert_eq!(found.to_string_opts(&dwarf, &opts)?,
+               "struct outer {\n\tint a;\n\tstruct {\n\t\tint b;\n\t} inner;\n\
+                \tenum {\n\t\tX = 0,\n\t} e;\n};");
+
+    let opts = FormatOptions { indent: Indent::Spaces(2),
+                               ..Default::default() };
+    assert_eq!(found.to_string_opts(&dwarf, &opts)?, "struct outer {
+  int a;
+  struct {
+    int b;
+  } inner;
+  enum {
+    X = 0,
+  } e;
+};");
+
+    Ok(())
+}
+
+#[test]
+fn subprogram_signature() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_with("
+struct point { int x; int y; };
+int logf(const char *fmt, ...) { return 0; }
+void move(struct point *p, long) { }
+int main() {
+    struct point p;
+    move(&p, 1);
+    return logf(\"%d\", 1);
+}", &["-g"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let logf = dwarf.lookup_type::<dwat::Subprogram>("logf".to_string())?;
+    let sig = logf.unwrap().signature(&dwarf)?;
+    assert_eq!(sig.name, "logf");
+    assert!(sig.is_variadic);
+    let return_type = sig.return_type.unwrap().as_base().unwrap();
+    assert_eq!(return_type.name(&dwarf)?, "int");
+    assert_eq!(sig.params.len(), 1);
+    assert_eq!(sig.params[0].0.as_deref(), Some("fmt"));
+    assert_eq!(sig.params[0].1.type_name(&dwarf)?, "const char *");
+
+    let mov = dwarf.lookup_type::<dwat::Subprogram>("move".to_string())?;
+    let sig = mov.unwrap().signature(&dwarf)?;
+    assert!(!sig.is_variadic);
+    assert!(sig.return_type.is_none());
+    let params: Vec<(Option<&str>, String)> = {
+        sig.params.iter().map(|(name, typ)| {
+            Ok((name.as_deref(), typ.type_name(&dwarf)?))
+        }).collect::<Result<_, dwat::Error>>()?
+    };
+    assert_eq!(params, [(Some("p"), "struct point *".to_string()),
+                        (None, "long int".to_string())]);
+
+    let snapshot = sig.snapshot(&dwarf)?;
+    assert_eq!(snapshot, dwat::SignatureSnapshot {
+        name: "move".to_string(),
+        return_type: None,
+        params: vec![(Some("p".to_string()), "struct point *".to_string()),
+                     (None, "long int".to_string())],
+        is_variadic: false
+    });
+
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_string(&snapshot)?;
+        assert_eq!(json, "{\"name\":\"move\",\"return_type\":null,\
+                          \"params\":[[\"p\",\"struct point *\"],\
+                          [null,\"long int\"]],\"is_variadic\":false}");
+        let parsed: dwat::SignatureSnapshot = serde_json::from_str(&json)?;
+        assert_eq!(parsed, snapshot);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn union_member_by_name() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_with("
+struct value {
+    int tag;
+    union payload {
+        int i;
+        double d;
+        struct { short lo; short hi; };
+    } as;
+} v;
+int main() {}", &["-g"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let value = dwarf.lookup_type::<dwat::Struct>("value".to_string())?;
+    let value = value.unwrap();
+    let tag = value.member_by_name(&dwarf, "tag")?.unwrap();
+    assert_eq!(tag.offset(&dwarf)?, 0);
+    assert!(value.member_by_name(&dwarf, "missing")?.is_none());
+
+    // select the variant of the union named by the tag
+    let payload = dwarf.lookup_type::<dwat::Union>("payload".to_string())?;
+    let payload = payload.unwrap();
+    let variant = payload.member_by_name(&dwarf, "d")?.unwrap();
+    let typ = variant.get_type(&dwarf)?.as_base().unwrap();
+    assert_eq!(typ.name(&dwarf)?, "double");
+    assert_eq!(variant.bit_layout(&dwarf)?.byte_offset, 0);
+
+    // members of anonymous structs inside of the union are found too
+    let hi = payload.member_by_name(&dwarf, "hi")?.unwrap();
+    assert_eq!(hi.name(&dwarf)?, "hi");
+    assert_eq!(hi.offset(&dwarf)?, 2);
+
+    Ok(())
+}
+
+#[test]
+fn explicit_padding_members() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_with("
+struct regs {
+    unsigned char flags;
+    char __pad0[3];
+    int value;
+    char name[8];
+    unsigned int reserved1;
+    union { int a; float b; };
+    unsigned long padding;
+} r;
+int main() {}", &["-g"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let found = dwarf.lookup_type::<dwat::Struct>("regs".to_string())?;
+    let padding = found.unwrap().explicit_padding_members(&dwarf)?;
+    let names: Vec<String> = {
+        padding.iter().map(|m| m.name(&dwarf)).collect::<Result<_, _>>()?
+    };
+    assert_eq!(names, ["__pad0", "reserved1", "padding"]);
+
+    Ok(())
+}
+
+#[test]
+fn compile_unit_producer_flags() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_with(SIMPLE, &["-g", "-O2",
+                                                "-fstack-protector-strong"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let structs = dwarf.types_with_cu::<dwat::Struct>()?;
+    let (cu, _, _) = &structs[0];
+
+    assert!(cu.producer(&dwarf)?.starts_with("GNU C"));
+    let flags = cu.producer_flags(&dwarf)?;
+    assert!(flags.contains(&"-O2".to_string()));
+    assert!(flags.contains(&"-fstack-protector-strong".to_string()));
+    assert!(flags.iter().all(|flag| flag.starts_with('-')));
+
+    Ok(())
+}
+
+#[test]
+fn array_dimensions() -> anyhow::Result<()> {
+    use gimli::write::AttributeValue;
+
+    // type grid; real :: cells(0:3, 5); end type, with a 16 byte stride on
+    // the second dimension
+    let elf = craft_dwarf(|unit| {
+        let root = unit.root();
+        unit.get_mut(root).set(gimli::DW_AT_language,
+                               AttributeValue::Language(
+                                   gimli::DW_LANG_Fortran90));
+
+        let real = unit.add(root, gimli::DW_TAG_base_type);
+        let entry = unit.get_mut(real);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"real".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(4));
+        entry.set(gimli::DW_AT_encoding,
+                  AttributeValue::Encoding(gimli::DW_ATE_float));
+
+        let array = unit.add(root, gimli::DW_TAG_array_type);
+        unit.get_mut(array).set(gimli::DW_AT_type, AttributeValue::UnitRef(real));
+
+        let rows = unit.add(array, gimli::DW_TAG_subrange_type);
+        let entry = unit.get_mut(rows);
+        entry.set(gimli::DW_AT_lower_bound, AttributeValue::Udata(0));
+        entry.set(gimli::DW_AT_upper_bound, AttributeValue::Udata(3));
+
+        let cols = unit.add(array, gimli::DW_TAG_subrange_type);
+        let entry = unit.get_mut(cols);
+        entry.set(gimli::DW_AT_upper_bound, AttributeValue::Udata(5));
+        entry.set(gimli::DW_AT_byte_stride, AttributeValue::Udata(16));
+
+        let grid = unit.add(root, gimli::DW_TAG_structure_type);
+        let entry = unit.get_mut(grid);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"grid".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(80));
+
+        let cells = unit.add(grid, gimli::DW_TAG_member);
+        let entry = unit.get_mut(cells);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"cells".to_vec()));
+        entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(array));
+        entry.set(gimli::DW_AT_data_member_location, AttributeValue::Udata(0));
+    })?;
+
+    let dwarf = Dwarf::load(&*elf)?;
+    let grid = dwarf.lookup_type::<dwat::Struct>("grid".to_string())?.unwrap();
+    let cells = grid.member_by_name(&dwarf, "cells")?.unwrap();
+    let dwat::Type::Array(array) = cells.get_type(&dwarf)? else {
+        panic!("expected an array type");
+    };
+
+    let dimensions = array.dimensions(&dwarf)?;
+    assert_eq!(dimensions, vec![
+        dwat::Dimension { lower_bound: 0, count: Some(4), byte_stride: None,
+                          bit_stride: None },
+        dwat::Dimension { lower_bound: 1, count: Some(5),
+                          byte_stride: Some(16), bit_stride: None },
+    ]);
+
+    // C arrays start at zero and have one subrange per subscript
+    let (_tmpdir, path) = compile("
+struct matrix { int m[2][3]; int tail[]; } mat;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let matrix = {
+        dwarf.lookup_type::<dwat::Struct>("matrix".to_string())?.unwrap()
+    };
+    let members = matrix.members(&dwarf)?;
+    let dwat::Type::Array(m) = members[0].get_type(&dwarf)? else {
+        panic!("expected an array type");
+    };
+    let counts: Vec<_> = {
+        m.dimensions(&dwarf)?.iter().map(|dim| dim.count).collect()
+    };
+    assert_eq!(counts, vec![Some(2), Some(3)]);
+    assert!(m.dimensions(&dwarf)?.iter().all(|dim| dim.lower_bound == 0));
+
+    let dwat::Type::Array(tail) = members[1].get_type(&dwarf)? else {
+        panic!("expected an array type");
+    };
+    assert_eq!(tail.dimensions(&dwarf)?[0].count, None);
+
+    Ok(())
+}
+
+#[test]
+fn format_recursion_limit() -> anyhow::Result<()> {
+    use gimli::write::AttributeValue;
+    use dwat::FormatOptions;
+
+    // `deep` holds a 64 level pointer chain, `cyclic` a pointer to itself
+    let elf = craft_dwarf(|unit| {
+        let root = unit.root();
+        let int = unit.add(root, gimli::DW_TAG_base_type);
+        let entry = unit.get_mut(int);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"int".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(4));
+        entry.set(gimli::DW_AT_encoding,
+                  AttributeValue::Encoding(gimli::DW_ATE_signed));
+
+        let mut chain = int;
+        for _ in 0..64 {
+            let pointer = unit.add(root, gimli::DW_TAG_pointer_type);
+            unit.get_mut(pointer).set(gimli::DW_AT_type,
+                                      AttributeValue::UnitRef(chain));
+            chain = pointer;
+        }
+
+        let cycle = unit.add(root, gimli::DW_TAG_pointer_type);
+        unit.get_mut(cycle).set(gimli::DW_AT_type,
+                                AttributeValue::UnitRef(cycle));
+
+        for (name, typ) in [("deep", chain), ("cyclic", cycle)] {
+            let struc = unit.add(root, gimli::DW_TAG_structure_type);
+            let entry = unit.get_mut(struc);
+            entry.set(gimli::DW_AT_name,
+                      AttributeValue::String(name.as_bytes().to_vec()));
+            entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(8));
+
+            let member = unit.add(struc, gimli::DW_TAG_member);
+            let entry = unit.get_mut(member);
+            entry.set(gimli::DW_AT_name, AttributeValue::String(b"p".to_vec()));
+            entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(typ));
+            entry.set(gimli::DW_AT_data_member_location,
+                      AttributeValue::Udata(0));
+        }
+    })?;
+
+    let dwarf = Dwarf::load(&*elf)?;
+    let deep = dwarf.lookup_type::<dwat::Struct>("deep".to_string())?.unwrap();
+    let cyclic = {
+        dwarf.lookup_type::<dwat::Struct>("cyclic".to_string())?.unwrap()
+    };
+
+    let repr = deep.to_string(&dwarf)?;
+    assert!(repr.contains(&format!("int {}p;", "*".repeat(64))));
+
+    let opts = FormatOptions { max_depth: 16, ..Default::default() };
+    assert!(matches!(deep.to_string_opts(&dwarf, &opts),
+                     Err(dwat::Error::RecursionLimitExceeded(_))));
+
+    // the default limit stops a cycle long before the stack runs out
+    assert!(matches!(cyclic.to_string(&dwarf),
+                     Err(dwat::Error::RecursionLimitExceeded(_))));
+
+    Ok(())
+}
+
+#[test]
+fn bit_packed_array() -> anyhow::Result<()> {
+    use gimli::write::AttributeValue;
+
+    // type color is (red, green, blue); type palette is array (1 .. 5) of
+    // color with component_size => 2; packed into a record
+    let elf = craft_dwarf(|unit| {
+        let root = unit.root();
+        unit.get_mut(root).set(gimli::DW_AT_language,
+                               AttributeValue::Language(gimli::DW_LANG_Ada95));
+
+        let color = unit.add(root, gimli::DW_TAG_enumeration_type);
+        let entry = unit.get_mut(color);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"color".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(1));
+
+        let array = unit.add(root, gimli::DW_TAG_array_type);
+        let entry = unit.get_mut(array);
+        entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(color));
+        entry.set(gimli::DW_AT_bit_stride, AttributeValue::Udata(2));
+
+        let range = unit.add(array, gimli::DW_TAG_subrange_type);
+        let entry = unit.get_mut(range);
+        entry.set(gimli::DW_AT_lower_bound, AttributeValue::Udata(1));
+        entry.set(gimli::DW_AT_upper_bound, AttributeValue::Udata(5));
+
+        let record = unit.add(root, gimli::DW_TAG_structure_type);
+        let entry = unit.get_mut(record);
+        entry.set(gimli::DW_AT_name,
+                  AttributeValue::String(b"record".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(2));
+
+        let palette = unit.add(record, gimli::DW_TAG_member);
+        let entry = unit.get_mut(palette);
+        entry.set(gimli::DW_AT_name,
+                  AttributeValue::String(b"palette".to_vec()));
+        entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(array));
+        entry.set(gimli::DW_AT_data_member_location, AttributeValue::Udata(0));
+    })?;
+
+    let dwarf = Dwarf::load(&*elf)?;
+    let record = dwarf.lookup_type::<dwat::Struct>("record".to_string())?;
+    let palette = record.unwrap().member_by_name(&dwarf, "palette")?.unwrap();
+    let dwat::Type::Array(array) = palette.get_type(&dwarf)? else {
+        panic!("expected an array type");
+    };
+
+    // the array's stride applies to its only dimension
+    assert_eq!(array.dimensions(&dwarf)?, vec![
+        dwat::Dimension { lower_bound: 1, count: Some(5), byte_stride: None,
+                          bit_stride: Some(2) },
+    ]);
+
+    assert_eq!(array.bit_size(&dwarf)?, 10);
+    assert_eq!(array.byte_size(&dwarf)?, 2);
+    assert_eq!(dwat::Type::Array(array).bit_size(&dwarf)?, 10);
+
+    assert_eq!(array.element_bit_offset(&dwarf, 3)?, 6);
+    assert_eq!(array.element_offset(&dwarf, 4)?, 1);
+    assert!(matches!(array.element_offset(&dwarf, 1),
+                     Err(dwat::Error::UnalignedElementError(_, 1))));
+
+    // byte-aligned arrays have byte-aligned elements
+    let (_tmpdir, path) = compile("
+struct pair { short p[2]; } pair;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let pair = dwarf.lookup_type::<dwat::Struct>("pair".to_string())?.unwrap();
+    let p = pair.member_by_name(&dwarf, "p")?.unwrap();
+    let dwat::Type::Array(array) = p.get_type(&dwarf)? else {
+        panic!("expected an array type");
+    };
+    assert_eq!(array.bit_size(&dwarf)?, 32);
+    assert_eq!(array.element_bit_offset(&dwarf, 1)?, 16);
+    assert_eq!(array.element_offset(&dwarf, 1)?, 2);
+
+    Ok(())
+}
+
+#[test]
+fn array_subranges() -> anyhow::Result<()> {
+    use dwat::Tagged;
+
+    let (_tmpdir, path) = compile("
+struct matrix { int m[2][3]; } mat;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let matrix = {
+        dwarf.lookup_type::<dwat::Struct>("matrix".to_string())?.unwrap()
+    };
+    let m = matrix.member_by_name(&dwarf, "m")?.unwrap();
+    let dwat::Type::Array(array) = m.get_type(&dwarf)? else {
+        panic!("expected an array type");
+    };
+
+    assert_eq!(dwat::Subrange::tag(), gimli::DW_TAG_subrange_type);
+
+    let subranges = array.subranges(&dwarf)?;
+    assert_eq!(subranges.len(), 2);
+
+    let mut counts = vec![];
+    for subrange in subranges {
+        assert_eq!(subrange.lower_bound(&dwarf)?, 0);
+        assert_eq!(subrange.stride(&dwarf)?, None);
+        assert_eq!(subrange.bit_stride(&dwarf)?, None);
+        counts.push((subrange.upper_bound(&dwarf)?, subrange.count(&dwarf)?));
+    }
+    assert_eq!(counts, vec![(Some(1), Some(2)), (Some(2), Some(3))]);
+
+    Ok(())
+}
+
+#[test]
+fn undefined_references() -> anyhow::Result<()> {
+    use dwat::prelude::*;
+
+    // `ext` and `opaque` are only forward declared, `local` is declared in
+    // one unit and defined in another and `unused` is never referenced
+    let (_tmpdir, path) = compile_sources(&["
+struct ext;
+union opaque;
+struct local;
+struct unused;
+struct holder { struct ext *e; union opaque *o; struct local *l; } holder;
+int main() {}", "
+struct local { int x; } local;"], &["-g"])?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    assert_eq!(dwarf.undefined_references()?, vec!["ext", "opaque"]);
+
+    Ok(())
+}
+
+#[test]
+fn descriptor_based_arrays() -> anyhow::Result<()> {
+    use gimli::write::{AttributeValue, Expression};
+
+    // type grid; real, pointer :: cells(:); real :: fixed(2); end type
+    let elf = craft_dwarf(|unit| {
+        let root = unit.root();
+        unit.get_mut(root).set(gimli::DW_AT_language,
+                               AttributeValue::Language(
+                                   gimli::DW_LANG_Fortran90));
+
+        let real = unit.add(root, gimli::DW_TAG_base_type);
+        let entry = unit.get_mut(real);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"real".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(4));
+        entry.set(gimli::DW_AT_encoding,
+                  AttributeValue::Encoding(gimli::DW_ATE_float));
+
+        let mut data_location = Expression::new();
+        data_location.op(gimli::DW_OP_push_object_address);
+        data_location.op_deref();
+
+        let cells = unit.add(root, gimli::DW_TAG_array_type);
+        let entry = unit.get_mut(cells);
+        entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(real));
+        entry.set(gimli::DW_AT_data_location,
+                  AttributeValue::Exprloc(data_location.clone()));
+        entry.set(gimli::DW_AT_associated,
+                  AttributeValue::Exprloc(data_location));
+        let subrange = unit.add(cells, gimli::DW_TAG_subrange_type);
+        let mut upper_bound = Expression::new();
+        upper_bound.op(gimli::DW_OP_push_object_address);
+        upper_bound.op_plus_uconst(40);
+        upper_bound.op_deref();
+        unit.get_mut(subrange).set(gimli::DW_AT_upper_bound,
+                                   AttributeValue::Exprloc(upper_bound));
+
+        let fixed = unit.add(root, gimli::DW_TAG_array_type);
+        unit.get_mut(fixed).set(gimli::DW_AT_type, AttributeValue::UnitRef(real));
+        let subrange = unit.add(fixed, gimli::DW_TAG_subrange_type);
+        unit.get_mut(subrange).set(gimli::DW_AT_count,
+                                   AttributeValue::Udata(2));
+
+        let grid = unit.add(root, gimli::DW_TAG_structure_type);
+        let entry = unit.get_mut(grid);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"grid".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(72));
+
+        for (name, typ, offset) in [("cells", cells, 0), ("fixed", fixed, 64)] {
+            let member = unit.add(grid, gimli::DW_TAG_member);
+            let entry = unit.get_mut(member);
+            entry.set(gimli::DW_AT_name,
+                      AttributeValue::String(name.as_bytes().to_vec()));
+            entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(typ));
+            entry.set(gimli::DW_AT_data_member_location,
+                      AttributeValue::Udata(offset));
+        }
+    })?;
+
+    let dwarf = Dwarf::load(&*elf)?;
+    let grid = dwarf.lookup_type::<dwat::Struct>("grid".to_string())?.unwrap();
+    let members = grid.members(&dwarf)?;
+
+    let dwat::Type::Array(cells) = members[0].get_type(&dwarf)? else {
+        panic!("expected an array type");
+    };
+    assert!(cells.is_descriptor_based(&dwarf)?);
+    assert!(matches!(cells.byte_size(&dwarf),
+                     Err(dwat::Error::NonContiguousArrayError(_))));
+
+    let dwat::Type::Array(fixed) = members[1].get_type(&dwarf)? else {
+        panic!("expected an array type");
+    };
+    assert!(!fixed.is_descriptor_based(&dwarf)?);
+    assert_eq!(fixed.byte_size(&dwarf)?, 8);
+
+    let repr = grid.to_string_verbose(&dwarf, 1)?;
+    assert!(repr.contains("real cells[/* descriptor */];"));
+    assert!(repr.contains("/*    ? |    0 */"));
+    assert!(repr.contains("real fixed[2];"));
+
+    Ok(())
+}
+
+#[test]
+fn format_unterminated() -> anyhow::Result<()> {
+    use dwat::FormatOptions;
+
+    let (_tmpdir, path) = compile("
+struct point { int x; union { int i; float f; } v; } p;
+enum color { RED } c;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let opts = FormatOptions { terminate: false, ..Default::default() };
+
+    let point = dwarf.lookup_type::<dwat::Struct>("point".to_string())?;
+    let point = point.unwrap();
+    let body = point.to_string_opts(&dwarf, &opts)?;
+    assert_eq!(format!("{body};"), point.to_string(&dwarf)?);
+    assert!(body.ends_with('}'));
+    // nested members are still terminated
+    assert!(body.contains("    } v;\n"));
+
+    let color = dwarf.lookup_type::<dwat::Enum>("color".to_string())?.unwrap();
+    assert_eq!(color.to_string_opts(&dwarf, &opts)?,
+               "enum color {\n    RED = 0,\n}");
+
+    let v = point.member_by_name(&dwarf, "v")?.unwrap();
+    let dwat::Type::Union(v) = v.get_type(&dwarf)? else {
+        panic!("expected a union type");
+    };
+    assert!(v.to_string_opts(&dwarf, &opts)?.ends_with('}'));
+
+    // headers stay valid C regardless of the option
+    let header = dwarf.emit_c_header(&[dwat::Type::Struct(point)], &opts)?;
+    assert!(header.contains("} v;\n};"));
+
+    Ok(())
+}
+
+#[test]
+fn struct_size_histogram() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile("
+struct a { char c; } a;
+struct b { int x; } b;
+struct c { int x, y; } c;
+struct d { long l; } d;
+struct e { long l; char c; } e;
+struct fwd *f;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let histogram = dwarf.struct_size_histogram()?;
+    let buckets: Vec<(usize, usize)> = histogram.into_iter().collect();
+    assert_eq!(buckets, vec![(1, 1), (4, 1), (8, 2), (16, 1)]);
+
+    Ok(())
+}
+
+#[test]
+fn type_bit_size() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile("
+struct flags { unsigned int mode:3; _Bool ready; const long wide; } f;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let flags = dwarf.lookup_type::<dwat::Struct>("flags".to_string())?;
+    let flags = flags.unwrap();
+    let members = flags.members(&dwarf)?;
+
+    // the width of a bitfield is on the member, its type is a full int
+    assert_eq!(members[0].bit_size(&dwarf)?, 3);
+    assert_eq!(members[0].get_type(&dwarf)?.bit_size(&dwarf)?, 32);
+
+    assert_eq!(members[1].get_type(&dwarf)?.bit_size(&dwarf)?, 8);
+    assert_eq!(members[2].get_type(&dwarf)?.bit_size(&dwarf)?, 64);
+    assert_eq!(dwat::Type::Struct(flags).bit_size(&dwarf)?,
+               flags.byte_size(&dwarf)? * 8);
+
+    // typedef _BitInt(3) small;
+    let elf = craft_dwarf(|unit| {
+        use gimli::write::AttributeValue;
+
+        let root = unit.root();
+        let bitint = unit.add(root, gimli::DW_TAG_base_type);
+        let entry = unit.get_mut(bitint);
+        entry.set(gimli::DW_AT_name,
+                  AttributeValue::String(b"_BitInt(3)".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(1));
+        entry.set(gimli::DW_AT_bit_size, AttributeValue::Udata(3));
+        entry.set(gimli::DW_AT_encoding,
+                  AttributeValue::Encoding(gimli::DW_ATE_signed));
+
+        let small = unit.add(root, gimli::DW_TAG_typedef);
+        let entry = unit.get_mut(small);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"small".to_vec()));
+        entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(bitint));
+    })?;
+    let dwarf = Dwarf::load(&*elf)?;
+
+    let small = dwarf.lookup_type::<dwat::Typedef>("small".to_string())?;
+    let small = dwat::Type::Typedef(small.unwrap());
+    assert_eq!(small.bit_size(&dwarf)?, 3);
+    assert_eq!(small.byte_size(&dwarf)?, 1);
+
+    Ok(())
+}
+
+#[test]
+fn array_parameters() -> anyhow::Result<()> {
+    use gimli::write::AttributeValue;
+
+    // int sum(int vals[4], int *out, ...);
+    let elf = craft_dwarf(|unit| {
+        let root = unit.root();
+        let int = unit.add(root, gimli::DW_TAG_base_type);
+        let entry = unit.get_mut(int);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"int".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(4));
+        entry.set(gimli::DW_AT_encoding,
+                  AttributeValue::Encoding(gimli::DW_ATE_signed));
+
+        let array = unit.add(root, gimli::DW_TAG_array_type);
+        unit.get_mut(array).set(gimli::DW_AT_type, AttributeValue::UnitRef(int));
+        let subrange = unit.add(array, gimli::DW_TAG_subrange_type);
+        unit.get_mut(subrange).set(gimli::DW_AT_count, AttributeValue::Udata(4));
+
+        let pointer = unit.add(root, gimli::DW_TAG_pointer_type);
+        let entry = unit.get_mut(pointer);
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(8));
+        entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(int));
+
+        let sum = unit.add(root, gimli::DW_TAG_subprogram);
+        let entry = unit.get_mut(sum);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"sum".to_vec()));
+        entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(int));
+
+        for (name, typ) in [("vals", array), ("out", pointer)] {
+            let param = unit.add(sum, gimli::DW_TAG_formal_parameter);
+            let entry = unit.get_mut(param);
+            entry.set(gimli::DW_AT_name,
+                      AttributeValue::String(name.as_bytes().to_vec()));
+            entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(typ));
+        }
+        unit.add(sum, gimli::DW_TAG_unspecified_parameters);
+    })?;
+
+    let dwarf = Dwarf::load(&*elf)?;
+    let sum = dwarf.lookup_type::<dwat::Subprogram>("sum".to_string())?;
+    assert_eq!(sum.unwrap().prototype(&dwarf)?,
+               "int sum(int vals[4], int *out, ...)");
+
+    // C adjusts array parameters to pointers, gcc types them as such
+    let (_tmpdir, path) = compile("
+int first(int arr[static 4]) { return arr[0]; }
+void nothing(void) {}
+int main() { return 0; }")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let first = dwarf.lookup_type::<dwat::Subprogram>("first".to_string())?;
+    assert_eq!(first.unwrap().prototype(&dwarf)?, "int first(int *arr)");
+
+    let nothing = dwarf.lookup_type::<dwat::Subprogram>("nothing".to_string())?;
+    assert_eq!(nothing.unwrap().prototype(&dwarf)?, "void nothing(void)");
+
+    Ok(())
+}
+
+#[test]
+fn stripped_has_no_debug_info() -> anyhow::Result<()> {
+    let (tmpdir, path) = compile(SIMPLE)?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    assert!(Dwarf::load(&*mmap)?.has_debug_info());
+    assert!(Dwarf::load_strict(&*mmap)?.has_debug_info());
+
+    let stripped = tmpdir.path().join("stripped");
+    let output = Command::new("strip")
+        .arg("--strip-debug")
+        .arg(&path)
+        .arg("-o")
+        .arg(&stripped)
+        .output()?;
+    assert!(output.status.success());
+
+    let file = File::open(&stripped)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+
+    // loading still succeeds, but there is nothing to find
+    let dwarf = Dwarf::load(&*mmap)?;
+    assert!(!dwarf.has_debug_info());
+    assert!(dwarf.get_named_types::<dwat::Struct>()?.is_empty());
+
+    match Dwarf::load_strict(&*mmap) {
+        Err(dwat::Error::DwarfLoadError(msg)) => {
+            assert_eq!(msg, "no debug info")
+        },
+        _ => panic!("expected a DwarfLoadError")
+    }
+    assert!(dwat::OwnedDwarf::load_strict(&*mmap).is_err());
+    assert!(!dwat::OwnedDwarf::load(&*mmap)?.has_debug_info());
+
+    Ok(())
+}
+
+#[test]
+fn lookup_first_of() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile("
+typedef int node;
+struct node { int value; };
+struct node n;
+node m;
+enum shade { DARK } s;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let tags = [gimli::DW_TAG_structure_type, gimli::DW_TAG_typedef,
+                gimli::DW_TAG_enumeration_type];
+    let found = dwarf.lookup_first_of("node", &tags)?;
+    assert!(matches!(found, Some(dwat::Type::Struct(_))));
+
+    let tags = [gimli::DW_TAG_typedef, gimli::DW_TAG_structure_type];
+    let found = dwarf.lookup_first_of("node", &tags)?;
+    assert!(matches!(found, Some(dwat::Type::Typedef(_))));
+
+    // lower precedence tags still match when nothing better exists
+    let tags = [gimli::DW_TAG_structure_type, gimli::DW_TAG_typedef,
+                gimli::DW_TAG_enumeration_type];
+    let found = dwarf.lookup_first_of("shade", &tags)?;
+    assert!(matches!(found, Some(dwat::Type::Enum(_))));
+
+    assert!(dwarf.lookup_first_of("node", &[gimli::DW_TAG_union_type])?
+            .is_none());
+    assert!(dwarf.lookup_first_of("missing", &tags)?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn section_sizes() -> anyhow::Result<()> {
+    let (tmpdir, path) = compile_with(SIMPLE, &["-g", "-gz=zlib"])?;
+
+    let data = std::fs::read(&path)?;
+    let obj = object::File::parse(&*data)?;
+    let raw_size = obj.section_by_name(".debug_info").unwrap().size() as usize;
+
+    // compressed sections are counted after decompression
+    let dwarf = Dwarf::load(&*data)?;
+    let info_size = dwarf.section_size(gimli::SectionId::DebugInfo);
+    assert!(info_size > raw_size);
+    assert!(dwarf.section_size(gimli::SectionId::DebugAbbrev) > 0);
+    assert_eq!(dwarf.section_size(gimli::SectionId::DebugFrame), 0);
+
+    let owned = dwat::OwnedDwarf::load(&*data)?;
+    assert_eq!(owned.section_size(gimli::SectionId::DebugInfo), info_size);
+
+    let stripped = tmpdir.path().join("stripped");
+    let output = Command::new("strip")
+        .arg("--strip-debug")
+        .arg(&path)
+        .arg("-o")
+        .arg(&stripped)
+        .output()?;
+    assert!(output.status.success());
+
+    let data = std::fs::read(&stripped)?;
+    let dwarf = Dwarf::load(&*data)?;
+    assert_eq!(dwarf.section_size(gimli::SectionId::DebugInfo), 0);
+
+    Ok(())
+}
+
+#[test]
+fn assert_layout() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile("
+struct header { char kind; int len; };
+struct packet { struct header hdr; long payload; short crc; } p;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let packet = dwarf.lookup_type::<dwat::Struct>("packet".to_string())?;
+    let packet = packet.unwrap();
+
+    packet.assert_layout(&dwarf, &[("hdr", 0), ("hdr.len", 4),
+                                   ("payload", 8), ("crc", 16)])?;
+
+    let err = packet.assert_layout(&dwarf, &[("hdr", 0), ("payload", 4),
+                                             ("crc", 0)]).unwrap_err();
+    match err {
+        dwat::Error::LayoutMismatch(ref name, expected, actual) => {
+            assert_eq!((name.as_str(), expected, actual), ("payload", 4, 8));
+        },
+        _ => panic!("expected a LayoutMismatch")
+    }
+    assert_eq!(err.to_string(), "member payload is at offset 8, expected 4");
+
+    assert!(matches!(packet.assert_layout(&dwarf, &[("checksum", 18)]),
+                     Err(dwat::Error::MemberNotFound(_))));
+
+    Ok(())
+}
+
+#[test]
+fn borrowed_sections() -> anyhow::Result<()> {
+    use gimli::SectionId;
+
+    let ids = [SectionId::DebugAbbrev, SectionId::DebugInfo,
+               SectionId::DebugLine, SectionId::DebugLineStr,
+               SectionId::DebugStr, SectionId::DebugAranges,
+               SectionId::DebugRngLists, SectionId::DebugLocLists];
+
+    let (_tmpdir, path) = compile(SIMPLE)?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+    assert!(dwarf.section_size(SectionId::DebugInfo) > 0);
+    for id in ids.iter() {
+        assert!(!dwarf.is_section_copied(*id), "{} was copied", id.name());
+    }
+
+    let (_tmpdir, path) = compile_with(SIMPLE, &["-g", "-gz=zlib"])?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+    assert!(dwarf.is_section_copied(SectionId::DebugInfo));
+
+    Ok(())
+}
+
+#[test]
+fn rust_enum_variant_part() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_rust("
+pub enum Shape { Circle(u32), Rect { w: u16, h: u16 }, Empty }
+fn main() {
+    let shapes = [Shape::Circle(2), Shape::Rect { w: 1, h: 2 }, Shape::Empty];
+    std::hint::black_box(&shapes);
+}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let shape = dwarf.lookup_type::<dwat::Struct>("Shape".to_string())?;
+    let part = shape.unwrap().variant_part(&dwarf)?.unwrap();
+
+    let discr = part.discriminant(&dwarf)?.unwrap();
+    assert!(discr.is_artificial(&dwarf)?);
+    assert_eq!(discr.offset(&dwarf)?, 0);
+
+    let variants = part.variants(&dwarf)?;
+    let mut found = vec![];
+    for variant in variants.iter() {
+        let members = variant.members(&dwarf)?;
+        assert_eq!(members.len(), 1);
+        let name = members[0].name(&dwarf)?;
+
+        let fields: Vec<String> = match members[0].get_type(&dwarf)? {
+            dwat::Type::Struct(s) => {
+                s.members(&dwarf)?.iter()
+                 .map(|m| m.name(&dwarf)).collect::<Result<_, _>>()?
+            },
+            _ => panic!("expected a struct for each variant")
+        };
+        found.push((variant.discr_value(&dwarf)?, name, fields));
+    }
+    assert_eq!(found, vec![
+        (Some(0), "Circle".to_string(), vec!["__0".to_string()]),
+        (Some(1), "Rect".to_string(), vec!["w".to_string(), "h".to_string()]),
+        (Some(2), "Empty".to_string(), vec![]),
+    ]);
+
+    // C structs have no variant part
+    let (_tmpdir, path) = compile(SIMPLE)?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+    let simple = dwarf.lookup_type::<dwat::Struct>("simple".to_string())?;
+    assert!(simple.unwrap().variant_part(&dwarf)?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn compile_unit_low_pc() -> anyhow::Result<()> {
+    use object::ObjectSymbol;
+
+    let (_tmpdir, path) = compile_sources(&["
+struct with_code { int x; } w;
+int main() { return 0; }", "
+struct types_only { int y; } t;"], &["-g"])?;
+
+    let data = std::fs::read(&path)?;
+    let obj = object::File::parse(&*data)?;
+    let main = obj.symbols().find(|sym| sym.name() == Ok("main")).unwrap();
+
+    let dwarf = Dwarf::load(&*data)?;
+    let structs = dwarf.types_with_cu::<dwat::Struct>()?;
+    for (cu, name, _) in structs.iter() {
+        match name.as_str() {
+            "with_code" => {
+                assert_eq!(cu.low_pc(&dwarf)?, Some(main.address()))
+            },
+            "types_only" => assert_eq!(cu.low_pc(&dwarf)?, None),
+            _ => {}
+        }
+    }
+    assert!(structs.iter().any(|(_, name, _)| name == "types_only"));
+
+    Ok(())
+}
+
+#[test]
+fn skip_bad_entries() -> anyhow::Result<()> {
+    use gimli::write::AttributeValue;
+
+    // struct outer { int CORRUPT; }; struct good { int x; }; with the
+    // abbreviation code of the member overwritten below
+    let mut elf = craft_dwarf(|unit| {
+        let root = unit.root();
+        let int = unit.add(root, gimli::DW_TAG_base_type);
+        let entry = unit.get_mut(int);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"int".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(4));
+        entry.set(gimli::DW_AT_encoding,
+                  AttributeValue::Encoding(gimli::DW_ATE_signed));
+
+        for (name, member) in [("outer", "CORRUPT"), ("good", "x")] {
+            let struc = unit.add(root, gimli::DW_TAG_structure_type);
+            let entry = unit.get_mut(struc);
+            entry.set_sibling(true);
+            entry.set(gimli::DW_AT_name,
+                      AttributeValue::String(name.as_bytes().to_vec()));
+            entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(4));
+
+            let memb = unit.add(struc, gimli::DW_TAG_member);
+            let entry = unit.get_mut(memb);
+            entry.set(gimli::DW_AT_name,
+                      AttributeValue::String(member.as_bytes().to_vec()));
+            entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(int));
+            entry.set(gimli::DW_AT_data_member_location,
+                      AttributeValue::Udata(0));
+        }
+    })?;
+
+    // the name is an inline string directly after the abbreviation code
+    let name_at = elf.windows(8).position(|w| w == b"CORRUPT\0").unwrap();
+    elf[name_at - 1] = 0x7f;
+
+    let dwarf = Dwarf::load(&*elf)?;
+    assert!(dwarf.lookup_type::<dwat::Struct>("good".to_string())?.is_some());
+
+    let mut skipped = 0;
+    let mut report = |p: dwat::dwarf::ScanProgress| skipped = p.entries_skipped;
+    let names = dwarf.get_named_types_with_progress::<dwat::Struct>(
+        Some(&mut report)
+    )?;
+    let names: Vec<&str> = names.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(names, vec!["outer", "good"]);
+    assert_eq!(skipped, 1);
+
+    Ok(())
+}
+
+#[test]
+fn enum_has_gaps() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile("
+enum sequential { A, B, C } s;
+enum explicit_sequential { D = 0, E = 1 } es;
+enum flags { READ = 1, WRITE = 2, EXEC = 4 } f;
+enum offset { X = 5, Y, Z } o;
+enum empty_start { P, Q = 3 } e;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    for (name, gaps) in [("sequential", false), ("explicit_sequential", false),
+                         ("flags", true), ("offset", true),
+                         ("empty_start", true)] {
+        let enu = dwarf.lookup_type::<dwat::Enum>(name.to_string())?.unwrap();
+        assert_eq!(enu.has_gaps(&dwarf)?, gaps, "{name}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn format_without_attributes() -> anyhow::Result<()> {
+    use dwat::FormatOptions;
+
+    let (_tmpdir, path) = compile("
+struct over { int x; } __attribute__((aligned(64))) o;
+struct tight { char c; int i; } __attribute__((packed)) t;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let over = dwarf.lookup_type::<dwat::Struct>("over".to_string())?.unwrap();
+    let tight = dwarf.lookup_type::<dwat::Struct>("tight".to_string())?;
+    let tight = tight.unwrap();
+
+    assert!(over.to_string(&dwarf)?
+                .ends_with("} __attribute((__aligned__(64)));"));
+    assert!(tight.to_string(&dwarf)?
+                 .ends_with("} __attribute__((packed));"));
+    let opts = FormatOptions { emit_packed: false, ..Default::default() };
+    assert!(tight.to_string_opts(&dwarf, &opts)?.ends_with("};"));
+
+    let opts = FormatOptions {
+        emit_attributes: false,
+        ..Default::default()
+    };
+    let repr = over.to_string_opts(&dwarf, &opts)?;
+    assert!(!repr.contains("__attribute"));
+    assert!(repr.ends_with("};"));
+    let repr = tight.to_string_opts(&dwarf, &opts)?;
+    assert!(!repr.contains("__attribute"));
+    assert!(repr.ends_with("};"));
+
+    Ok(())
+}
+
+#[test]
+fn type_resolver() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile("
+struct point { int x, y; } p;
+union value { int i; float f; } v;
+enum mode { ON, OFF } m;
+typedef struct point point_t;
+point_t pt;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let mut resolver = dwat::TypeResolver::new(&dwarf);
+    let point = resolver.struct_by_name("point")?.unwrap();
+    let expected = dwarf.lookup_type::<dwat::Struct>("point".to_string())?;
+    assert_eq!(point.location, expected.unwrap().location);
+    assert_eq!(resolver.cached(), 1);
+
+    // repeated queries are served from the cache
+    let again = resolver.struct_by_name("point")?.unwrap();
+    assert_eq!(point.location, again.location);
+    assert_eq!(resolver.cached(), 1);
+
+    assert!(resolver.union_by_name("value")?.is_some());
+    assert!(resolver.enum_by_name("mode")?.is_some());
+    assert!(resolver.typedef_by_name("point_t")?.is_some());
+    assert!(resolver.base_by_name("int")?.is_some());
+
+    // the same name is cached separately per kind, misses are cached too
+    assert!(resolver.union_by_name("point")?.is_none());
+    assert!(resolver.struct_by_name("missing")?.is_none());
+    assert_eq!(resolver.cached(), 7);
+    assert!(resolver.struct_by_name("missing")?.is_none());
+    assert_eq!(resolver.cached(), 7);
+
+    let members = point.members(resolver.dwarf())?;
+    assert_eq!(members.len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn prefer_typedef_names() -> anyhow::Result<()> {
+    use dwat::FormatOptions;
+
+    let (_tmpdir, path) = compile("
+struct point { int x, y; };
+typedef struct point point_t;
+enum color { RED };
+typedef enum color color_t;
+struct other { int z; };
+struct shape { struct point origin; enum color fill; struct point *next;
+               struct other o; } s;
+point_t p;
+color_t c;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let shape = dwarf.lookup_type::<dwat::Struct>("shape".to_string())?;
+    let shape = shape.unwrap();
+
+    let repr = shape.to_string(&dwarf)?;
+    assert!(repr.contains("    struct point origin;\n"));
+    assert!(repr.contains("    enum color fill;\n"));
+    assert!(repr.contains("    struct point *next;\n"));
+
+    let opts = FormatOptions { prefer_typedef_names: true,
+                               ..Default::default() };
+    let repr = shape.to_string_opts(&dwarf, &opts)?;
+    assert!(repr.contains("    point_t origin;\n"));
+    assert!(repr.contains("    color_t fill;\n"));
+    assert!(repr.contains("    point_t *next;\n"));
+    // without an alias the tag spelling is kept
+    assert!(repr.contains("    struct other o;\n"));
+
+    // the typedefs themselves still name the aliased type
+    let point_t = dwarf.lookup_type::<dwat::Typedef>("point_t".to_string())?;
+    let header = dwarf.emit_c_header(&[dwat::Type::Typedef(point_t.unwrap())],
+                                     &opts)?;
+    assert!(header.contains("typedef struct point point_t;"));
+
+    Ok(())
+}
+
+#[test]
+fn variable_to_string() -> anyhow::Result<()> {
+    use dwat::dwarf::LookupOptions;
+
+    // the first unit only declares g_foo
+    let (_tmpdir, path) = compile_sources(&[
+        "struct foo { int a; }; extern struct foo g_foo; \
+         int use(void) { return g_foo.a; } int main() {}",
+        "struct foo { int a; } g_foo; int counts[4]; int counter;"
+    ], &["-gdwarf-5"])?;
+
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let counts = dwarf.lookup_type::<dwat::Variable>("counts".to_string())?;
+    assert_eq!(counts.unwrap().to_string(&dwarf)?, "int counts[4];");
+
+    let counter = dwarf.lookup_type::<dwat::Variable>("counter".to_string())?;
+    assert_eq!(counter.unwrap().to_string(&dwarf)?, "int counter;");
+
+    let opts = LookupOptions { skip_declarations: false,
+                               prefer_definition: false };
+    let g_foo = dwarf.lookup_type_opts::<dwat::Variable>("g_foo", &opts)?;
+    assert_eq!(g_foo.unwrap().to_string(&dwarf)?, "extern struct foo g_foo;");
+
+    let g_foo = dwarf.lookup_type::<dwat::Variable>("g_foo".to_string())?;
+    assert_eq!(g_foo.unwrap().to_string(&dwarf)?, "struct foo g_foo;");
+
+    Ok(())
+}
+
+#[test]
+fn for_each_cu() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile_sources(&[
+        "int main() {}", "int a;", "int b;"
+    ], &["-g"])?;
+
+    let dwarf = dwat::OwnedDwarf::from_path(&path)?;
+
+    let mut count = 0;
+    let mut names = vec![];
+    dwarf.for_each_cu(|unit| {
+        count += 1;
+        if let Some(name) = &unit.name {
+            names.push(name.to_string_lossy().into_owned());
+        }
+        Ok(())
+    })?;
+    assert_eq!(count, dwarf.num_compile_units()?);
+    assert!(names.iter().any(|name| name.ends_with("src1.c")));
+
+    // errors from the callback stop the iteration
+    let mut visited = 0;
+    let res = dwarf.for_each_cu(|_| {
+        visited += 1;
+        Err(dwat::Error::CUError("stop".to_string()))
+    });
+    assert!(res.is_err());
+    assert_eq!(visited, 1);
+
+    Ok(())
+}
+
+#[test]
+fn mark_union_members() -> anyhow::Result<()> {
+    use dwat::FormatOptions;
+
+    let (_tmpdir, path) = compile("\
+struct tagged { int kind; union { int i; float f; struct { short lo; \
+short hi; } parts; }; };
+struct tagged t;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let tagged = dwarf.lookup_type::<dwat::Struct>("tagged".to_string())?;
+    let tagged = tagged.unwrap();
+
+    let opts = FormatOptions { mark_union_members: true,
+                               ..FormatOptions::with_verbosity(1) };
+    let repr = tagged.to_string_opts(&dwarf, &opts)?;
+    let lines: Vec<&str> = repr.lines().collect();
+    let marked = |name: &str| lines.iter().any(|line| {
+        line.contains(name) && line.ends_with("|    4 */ /* union */")
+    });
+    assert!(marked("int i;"));
+    assert!(marked("float f;"));
+    assert!(marked("} parts;"));
+    // members of the struct nested in the union don't overlap each other
+    assert!(!marked("short int lo;"));
+    assert!(!marked("int kind;"));
+
+    // off by default
+    let repr = tagged.to_string_verbose(&dwarf, 1)?;
+    assert!(!repr.contains("/* union */"));
+
+    Ok(())
+}
+
+#[test]
+fn interpret_in_target_endianness() -> anyhow::Result<()> {
+    use gimli::write::AttributeValue;
+
+    let craft = |arch, endian| craft_dwarf_for(arch, endian, 5, |unit| {
+        let root = unit.root();
+        for (name, encoding) in [("int", gimli::DW_ATE_signed),
+                                 ("unsigned int", gimli::DW_ATE_unsigned)] {
+            let id = unit.add(root, gimli::DW_TAG_base_type);
+            let entry = unit.get_mut(id);
+            entry.set(gimli::DW_AT_name,
+                      AttributeValue::String(name.as_bytes().to_vec()));
+            entry.set(gimli::DW_AT_byte_size, AttributeValue::Data1(4));
+            entry.set(gimli::DW_AT_encoding,
+                      AttributeValue::Encoding(encoding));
+        }
+    });
+    let little = craft(object::Architecture::X86_64,
+                       object::Endianness::Little)?;
+    let big = craft(object::Architecture::PowerPc64,
+                    object::Endianness::Big)?;
+
+    let little = Dwarf::load(&*little)?;
+    let big = Dwarf::load(&*big)?;
+    assert_eq!(little.target_endianness(), gimli::RunTimeEndian::Little);
+    assert_eq!(big.target_endianness(), gimli::RunTimeEndian::Big);
+
+    let bytes = [0x00, 0x01, 0x00, 0x00];
+    let int = little.lookup_type::<dwat::Base>("int".to_string())?.unwrap();
+    assert_eq!(int.interpret(&little, &bytes)?, 0x100);
+    let int = big.lookup_type::<dwat::Base>("int".to_string())?.unwrap();
+    assert_eq!(int.interpret(&big, &bytes)?, 0x10000);
+
+    // signed values are sign extended, unsigned ones aren't
+    let bytes = [0xff, 0xff, 0xff, 0xfe];
+    assert_eq!(int.interpret(&big, &bytes)?, -2);
+    let uint = big.lookup_type::<dwat::Base>("unsigned int".to_string())?;
+    assert_eq!(uint.unwrap().interpret(&big, &bytes)?, 0xfffffffe);
+
+    assert!(matches!(int.interpret(&big, &bytes[..2]),
+                     Err(dwat::Error::ValueTooShort(4, 2))));
+
+    Ok(())
+}
+
+#[test]
+fn pointer_size() -> anyhow::Result<()> {
+    const PTRS: &str = "
+struct ptrs { void *slots[4]; char *name; long long wide; } p;";
+
+    // there is no 32-bit libc to link against, the relocations of i386
+    // objects keep their addends in place so the object can be read directly
+    let (_tmpdir, path) = compile_sources(&[PTRS], &["-g", "-m32", "-c"])?;
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    assert_eq!(dwarf.pointer_size()?, 4);
+
+    let ptrs = dwarf.lookup_type::<dwat::Struct>("ptrs".to_string())?.unwrap();
+    let slots = ptrs.member_by_name(&dwarf, "slots")?.unwrap();
+    let dwat::Type::Array(array) = slots.get_type(&dwarf)? else {
+        panic!("expected an array type");
+    };
+    assert_eq!(array.entry_size(&dwarf)?, 4);
+    assert_eq!(array.byte_size(&dwarf)?, 16);
+    assert_eq!(array.element_offset(&dwarf, 3)?, 12);
+    assert_eq!(ptrs.member_by_name(&dwarf, "name")?.unwrap().offset(&dwarf)?,
+               16);
+
+    let (_tmpdir, path) = compile_sources(&[PTRS], &["-g", "-c"])?;
+    let file = File::open(&path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    assert_eq!(Dwarf::load(&*mmap)?.pointer_size()?, 8);
+
+    // units built for 64-bit and 32-bit targets have no single pointer size
+    use gimli::write::{AttributeValue, EndianVec, LineProgram, Sections, Unit};
+    let mut mixed = gimli::write::Dwarf::new();
+    for address_size in [8, 4] {
+        let encoding = gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 5,
+            address_size,
+        };
+        let id = mixed.units.add(Unit::new(encoding, LineProgram::none()));
+        let unit = mixed.units.get_mut(id);
+        let root = unit.root();
+        unit.get_mut(root).set(gimli::DW_AT_name, AttributeValue::String(
+            format!("unit{address_size}.c").into_bytes()
+        ));
+    }
+    let endian = gimli::RunTimeEndian::Little;
+    let mut sections = Sections::new(EndianVec::new(endian));
+    mixed.write(&mut sections)?;
+    let elf = debug_elf(object::Architecture::X86_64,
+                        object::Endianness::Little, &sections)?;
+    assert!(matches!(Dwarf::load(&*elf)?.pointer_size(),
+                     Err(dwat::Error::MixedAddressSizes(8, 4))));
+    let dwarf = Dwarf::load(&*elf)?;
+    let sizes = dwarf.get_named_types::<dwat::CompileUnit>()?.iter()
+        .map(|(_, cu)| cu.address_size(&dwarf))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(sizes, [8, 4]);
+
+    Ok(())
+}
+
+#[test]
+fn unsupported_version() -> anyhow::Result<()> {
+    use gimli::write::AttributeValue;
+
+    let elf = craft_dwarf_for(object::Architecture::X86_64,
+                              object::Endianness::Little, 3, |unit| {
+        let root = unit.root();
+        let old = unit.add(root, gimli::DW_TAG_structure_type);
+        let entry = unit.get_mut(old);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"old".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(0));
+    })?;
+    let dwarf = Dwarf::load(&*elf)?;
+    assert_eq!(dwarf.min_version(), Some(3));
+    assert!(matches!(dwarf.check_version(),
+                     Err(dwat::Error::UnsupportedVersion(3))));
+
+    // parsing is still attempted
+    assert!(dwarf.lookup_type::<dwat::Struct>("old".to_string())?.is_some());
+
+    let elf = craft_big_endian(4)?;
+    let dwarf = dwat::OwnedDwarf::load(&*elf)?;
+    assert_eq!(dwarf.min_version(), Some(4));
+    assert!(dwarf.check_version().is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn tag_names() -> anyhow::Result<()> {
+    use gimli::write::AttributeValue;
+
+    let (_tmpdir, path) = compile("\
+struct node { struct node *next; const int id; };
+struct node n;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let node = dwarf.lookup_type::<dwat::Struct>("node".to_string())?;
+    let node = node.unwrap();
+    assert_eq!(dwat::Type::Struct(node).tag_name(), "DW_TAG_structure_type");
+
+    let members = node.members(&dwarf)?;
+    assert_eq!(members[0].get_type(&dwarf)?.tag_name(), "DW_TAG_pointer_type");
+    assert_eq!(members[1].get_type(&dwarf)?.tag_name(), "DW_TAG_const_type");
+
+    // entries which aren't types have names too
+    assert_eq!(dwarf.tag_name_at(&node.location)?, "DW_TAG_structure_type");
+    let var = dwarf.lookup_type::<dwat::Variable>("n".to_string())?;
+    assert_eq!(dwarf.tag_name_at(&var.unwrap().location)?, "DW_TAG_variable");
+
+    // a vendor tag gimli doesn't know of
+    let elf = craft_dwarf(|unit| {
+        let root = unit.root();
+        let id = unit.add(root, gimli::DwTag(0x6000));
+        unit.get_mut(id).set(gimli::DW_AT_name,
+                             AttributeValue::String(b"odd".to_vec()));
+    })?;
+    let dwarf = Dwarf::load(&*elf)?;
+    let mut locations = vec![];
+    dwarf.for_each_cu(|unit| {
+        let mut entries = unit.entries();
+        while let Ok(Some((_, entry))) = entries.next_dfs() {
+            if entry.tag().0 == 0x6000 {
+                locations.push(dwat::Location {
+                    header: unit.header.offset(),
+                    offset: entry.offset()
+                });
+            }
+        }
+        Ok(())
+    })?;
+    assert_eq!(dwarf.tag_name_at(&locations[0])?, "0x6000");
+
+    Ok(())
+}
+
+#[test]
+fn type_tag_and_kind() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile("\
+struct pair { int a[2]; union { int i; } u; volatile char *p; };
+struct pair pr;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let pair = dwarf.lookup_type::<dwat::Struct>("pair".to_string())?;
+    let pair = dwat::Type::Struct(pair.unwrap());
+    assert_eq!(pair.tag(), gimli::DW_TAG_structure_type);
+    assert_eq!(pair.kind_name(), "struct");
+
+    let kinds = pair.as_struct().unwrap().members(&dwarf)?.iter()
+        .map(|m| m.get_type(&dwarf).map(|t| (t.tag(), t.kind_name())))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(kinds, [(gimli::DW_TAG_array_type, "array"),
+                       (gimli::DW_TAG_union_type, "union"),
+                       (gimli::DW_TAG_pointer_type, "pointer")]);
+
+    Ok(())
+}
+
+#[test]
+fn type_as_aggregate() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile("
+typedef union { int i; float f; } num_t;
+typedef const volatile num_t cv_num_t;
+enum color { RED };
+typedef void nothing_t;
+struct holder {
+    cv_num_t num;
+    const enum color color;
+    struct holder *next;
+    nothing_t *p;
+    int plain;
+} holder;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let holder = dwarf.lookup_type::<dwat::Struct>("holder".to_string())?;
+    let holder = holder.unwrap();
+    let aggregate = |name: &str| -> anyhow::Result<Option<dwat::Type>> {
+        let member = holder.member_by_name(&dwarf, name)?.unwrap();
+        Ok(member.get_type(&dwarf)?.as_aggregate(&dwarf)?)
+    };
+
+    assert!(matches!(aggregate("num")?, Some(dwat::Type::Union(_))));
+    assert!(matches!(aggregate("color")?, Some(dwat::Type::Enum(_))));
+    assert!(aggregate("next")?.is_none());
+    assert!(aggregate("plain")?.is_none());
+
+    let struc = dwat::Type::Struct(holder);
+    assert!(matches!(struc.as_aggregate(&dwarf)?,
+                     Some(dwat::Type::Struct(_))));
+
+    let nothing = dwarf.lookup_type::<dwat::Typedef>("nothing_t".to_string())?;
+    let nothing = dwat::Type::Typedef(nothing.unwrap());
+    assert!(nothing.as_aggregate(&dwarf)?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn type_to_string() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile("
+struct node { int value; };
+struct list {
+    const struct node *head;
+    int (*cmp)(int, int);
+    unsigned long counts[4];
+} list;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let list = dwarf.lookup_type::<dwat::Struct>("list".to_string())?.unwrap();
+    let member_type = |name: &str| -> anyhow::Result<dwat::Type> {
+        let member = list.member_by_name(&dwarf, name)?.unwrap();
+        Ok(member.get_type(&dwarf)?)
+    };
+
+    let head = member_type("head")?;
+    assert!(matches!(head, dwat::Type::Pointer(_)));
+    assert_eq!(head.to_string(&dwarf)?, "const struct node *");
+
+    let dwat::Type::Pointer(ptr) = head else { unreachable!() };
+    assert_eq!(ptr.deref(&dwarf)?.to_string(&dwarf)?, "const struct node");
+
+    assert_eq!(member_type("cmp")?.to_string(&dwarf)?, "int (*)(int, int)");
+    assert_eq!(member_type("counts")?.to_string(&dwarf)?,
+               "long unsigned int [4]");
+
+    Ok(())
+}
+
+#[test]
+fn typedef_declaration() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile("
+typedef int (*handler_t)(void);
+typedef char buf_t[16];
+typedef const char *(*table_t[4])(int, long);
+typedef void callback_t(int);
+typedef struct { int x; } point_t;
+typedef unsigned long size_type;
+handler_t h; buf_t b; table_t t; callback_t *c; point_t p; size_type s;
+int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let declaration = |name: &str| -> anyhow::Result<String> {
+        let typedef = dwarf.lookup_type::<dwat::Typedef>(name.to_string())?;
+        Ok(typedef.unwrap().declaration(&dwarf)?)
+    };
+
+    assert_eq!(declaration("handler_t")?, "typedef int (*handler_t)(void);");
+    assert_eq!(declaration("buf_t")?, "typedef char buf_t[16];");
+    assert_eq!(declaration("table_t")?,
+               "typedef const char *(*table_t[4])(int, long int);");
+    assert_eq!(declaration("callback_t")?, "typedef void callback_t(int);");
+    assert_eq!(declaration("point_t")?,
+               "typedef struct {\n    int x;\n} point_t;");
+    assert_eq!(declaration("size_type")?,
+               "typedef long unsigned int size_type;");
+
+    Ok(())
+}
+
+#[test]
+fn load_with_symbols() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile("\
+int counter;
+extern int printf(const char *, ...);
+int main() { return printf(\"%d\", counter); }")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+
+    let (dwarf, symbols) = Dwarf::load_with_symbols(&*mmap)?;
+    assert!(dwarf.lookup_type::<dwat::Variable>("counter".to_string())?
+                 .is_some());
+    assert!(symbols["counter"] != 0);
+    assert!(symbols["main"] != 0);
+    // printf is undefined in the binary
+    assert!(!symbols.contains_key("printf"));
+
+    let (owned, owned_symbols) = dwat::OwnedDwarf::load_with_symbols(&*mmap)?;
+    assert!(owned.has_debug_info());
+    assert_eq!(symbols, owned_symbols);
+
+    Ok(())
+}
+
+// A file with a variable whose name and type are in the supplementary file
+// `sup`, as dwz leaves them, along with the name and type of the variable
+fn craft_sup_reference(sup: &[u8]) -> anyhow::Result<Vec<u8>> {
+    use gimli::write::AttributeValue;
+
+    let sup_dwarf = Dwarf::load(sup)?;
+    let shared = sup_dwarf.lookup_type::<dwat::Struct>(
+        "supplementary_shared_struct".to_string()
+    )?.unwrap();
+    let type_offset = shared.location.header.as_debug_info_offset().unwrap().0
+                      + shared.location.offset.0;
+
+    let object = object::File::parse(sup)?;
+    let debug_str = object.section_by_name(".debug_str").unwrap().data()?;
+    let name = b"\0supplementary_instance\0";
+    let name_offset = debug_str.windows(name.len())
+        .position(|window| window == name).unwrap() + 1;
+
+    craft_dwarf(|unit| {
+        let root = unit.root();
+        let var = unit.add(root, gimli::DW_TAG_variable);
+        let entry = unit.get_mut(var);
+        entry.set(gimli::DW_AT_name, AttributeValue::DebugStrRefSup(
+            gimli::DebugStrOffset(name_offset)
+        ));
+        entry.set(gimli::DW_AT_type, AttributeValue::DebugInfoRefSup(
+            gimli::DebugInfoOffset(type_offset)
+        ));
+
+        // struct supplementary_holder { struct supplementary_shared_struct s; }
+        let holder = unit.add(root, gimli::DW_TAG_structure_type);
+        let entry = unit.get_mut(holder);
+        entry.set(gimli::DW_AT_name,
+                  AttributeValue::String(b"supplementary_holder".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(16));
+        let member = unit.add(holder, gimli::DW_TAG_member);
+        let entry = unit.get_mut(member);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"s".to_vec()));
+        entry.set(gimli::DW_AT_type, AttributeValue::DebugInfoRefSup(
+            gimli::DebugInfoOffset(type_offset)
+        ));
+        entry.set(gimli::DW_AT_data_member_location, AttributeValue::Udata(0));
+    })
+}
+
+#[test]
+fn supplementary_references() -> anyhow::Result<()> {
+    let (_tmpdir, path) = compile("\
+struct supplementary_shared_struct { int first_member_field; \
+long second_member_field; } supplementary_instance;
+int main() {}")?;
+    let file = File::open(path)?;
+    let sup = unsafe { Mmap::map(&file) }?;
+    let elf = craft_sup_reference(&sup)?;
+
+    // nothing can be resolved without the supplementary file
+    let mut dwarf = Dwarf::load(&*elf)?;
+    assert!(dwarf.debug_altlink().is_none());
+    let var = dwarf.lookup_type::<dwat::Variable>(
+        "supplementary_instance".to_string()
+    )?;
+    assert!(var.is_none());
+
+    dwarf.load_sup(&*sup)?;
+    let var = dwarf.lookup_type::<dwat::Variable>(
+        "supplementary_instance".to_string()
+    )?.unwrap();
+    let shared = var.get_type(&dwarf)?.as_struct().unwrap();
+    assert_eq!(shared.name(&dwarf)?, "supplementary_shared_struct");
+    let members = shared.members(&dwarf)?;
+    assert_eq!(members[1].name(&dwarf)?, "second_member_field");
+    assert_eq!(members[1].offset(&dwarf)?, 8);
+    assert!(shared.to_string(&dwarf)?.contains("    int first_member_field;"));
+
+    // members of this file typed by the supplementary file have a layout
+    let holder = dwarf.lookup_type::<dwat::Struct>(
+        "supplementary_holder".to_string()
+    )?.unwrap();
+    assert_eq!(holder.members(&dwarf)?[0].byte_size(&dwarf)?, 16);
+    let verbose = holder.to_string_verbose(&dwarf, 1)?;
+    assert!(verbose.contains("/*   16 |    0 */"));
+    assert_eq!(holder.alignment_stats(&dwarf)?.sum_member_size, 16);
+    assert!(!holder.is_packed(&dwarf)?);
+    assert_eq!(holder.flat_offset_table(&dwarf)?.len(), 2);
+
+    // the same through OwnedDwarf, where the reference fails without it
+    let mut owned = dwat::OwnedDwarf::load(&*elf)?;
+    let mut locations = vec![];
+    owned.for_each_cu(|unit| {
+        let mut entries = unit.entries();
+        while let Ok(Some((_, entry))) = entries.next_dfs() {
+            if entry.tag() == gimli::DW_TAG_variable {
+                locations.push(dwat::Location {
+                    header: unit.header.offset(),
+                    offset: entry.offset()
+                });
+            }
+        }
+        Ok(())
+    })?;
+    let var = dwat::Variable { location: locations[0] };
+    assert!(matches!(var.get_type(&owned),
+                     Err(dwat::Error::SupplementaryReference(_))));
+
+    owned.load_sup(&*sup)?;
+    let shared = var.get_type(&owned)?.as_struct().unwrap();
+    assert_eq!(shared.name(&owned)?, "supplementary_shared_struct");
+
+    Ok(())
+}
+
+#[test]
+fn dwz_supplementary_file() -> anyhow::Result<()> {
+    if Command::new("dwz").arg("--version").output().is_err() {
+        eprintln!("dwz is not installed, skipping");
+        return Ok(())
+    }
+
+    let source = "\
+struct dwz_shared { int a; long b; char name[16]; } shared;
+int main() { return shared.a; }";
+    let (_first_dir, first) = compile(source)?;
+    let (_second_dir, second) = compile(source)?;
+
+    // dwz moves the types both files have to a supplementary file
+    let tmp_dir = TempDir::new()?;
+    std::fs::copy(first, tmp_dir.path().join("first"))?;
+    std::fs::copy(second, tmp_dir.path().join("second"))?;
+    let output = Command::new("dwz")
+        .current_dir(tmp_dir.path())
+        .args(["-m", "common", "-M", "common", "first", "second"])
+        .output()?;
+    assert!(output.status.success(), "dwz failed: {}",
+            String::from_utf8_lossy(&output.stderr));
+
+    let dwarf = dwat::OwnedDwarf::from_path(tmp_dir.path().join("first"))?;
+    assert_eq!(dwarf.debug_altlink(), Some(std::path::Path::new("common")));
+
+    let shared = dwarf.lookup_type::<dwat::Variable>("shared".to_string())?;
+    let shared = shared.unwrap().get_type(&dwarf)?.as_struct().unwrap();
+    assert_eq!(shared.name(&dwarf)?, "dwz_shared");
+    assert_eq!(shared.members(&dwarf)?.len(), 3);
+    assert_eq!(shared.byte_size(&dwarf)?, 32);
+    let verbose = shared.to_string_verbose(&dwarf, 1)?;
+    assert!(verbose.contains("/* total size: 32 */"));
+
+    Ok(())
+}
+
+#[test]
+fn member_has_location() -> anyhow::Result<()> {
+    use gimli::write::{AttributeValue, Expression};
+
+    // struct virt { int direct; int computed; } where `computed` is located
+    // by a DWARF expression, as for members of virtual bases
+    let elf = craft_dwarf(|unit| {
+        let root = unit.root();
+        let int = unit.add(root, gimli::DW_TAG_base_type);
+        let entry = unit.get_mut(int);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"int".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(4));
+        entry.set(gimli::DW_AT_encoding,
+                  AttributeValue::Encoding(gimli::DW_ATE_signed));
+
+        let virt = unit.add(root, gimli::DW_TAG_structure_type);
+        let entry = unit.get_mut(virt);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"virt".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(8));
+
+        let direct = unit.add(virt, gimli::DW_TAG_member);
+        let entry = unit.get_mut(direct);
+        entry.set(gimli::DW_AT_name,
+                  AttributeValue::String(b"direct".to_vec()));
+        entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(int));
+        entry.set(gimli::DW_AT_data_member_location, AttributeValue::Udata(0));
+
+        let computed = unit.add(virt, gimli::DW_TAG_member);
+        let entry = unit.get_mut(computed);
+        entry.set(gimli::DW_AT_name,
+                  AttributeValue::String(b"computed".to_vec()));
+        entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(int));
+        // DW_OP_plus_uconst 4
+        entry.set(gimli::DW_AT_data_member_location,
+                  AttributeValue::Exprloc(Expression::raw(vec![0x23, 4])));
+
+        // a definition without a DW_AT_byte_size can't be keyed at all
+        let unsized_struct = unit.add(root, gimli::DW_TAG_structure_type);
+        unit.get_mut(unsized_struct).set(
+            gimli::DW_AT_name, AttributeValue::String(b"unsized".to_vec())
+        );
+    })?;
+    let dwarf = Dwarf::load(&*elf)?;
+
+    let virt = dwarf.lookup_type::<dwat::Struct>("virt".to_string())?.unwrap();
+    let members = virt.members(&dwarf)?;
+    assert!(members[0].has_location(&dwarf)?);
+    assert_eq!(members[0].offset(&dwarf)?, 0);
+
+    // present, but not decodable as an offset
+    assert!(members[1].has_location(&dwarf)?);
+    assert!(matches!(members[1].offset(&dwarf),
+                     Err(dwat::Error::MemberLocationAttributeNotFound)));
+
+    // nor as part of a struct key, which falls back to the name and size
+    // rather than keying the member at offset 0, unsized structs are skipped
+    for map in [dwarf.get_fg_named_structs_map()?,
+                dwarf.get_fg_named_structs_map_fast()?] {
+        let keys: Vec<_> = map.keys()
+            .map(|k| (k.name.as_str(), k.byte_size, k.members.len()))
+            .collect();
+        assert_eq!(keys, [("virt", 8, 0)]);
+    }
+
+    // union members have no location at all
+    let (_tmpdir, path) = compile("union u { int a; char b; } u; \
+                                   int main() {}")?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+    let u = dwarf.lookup_type::<dwat::Union>("u".to_string())?.unwrap();
+    assert!(!u.members(&dwarf)?[0].has_location(&dwarf)?);
+
+    Ok(())
+}
+
+#[test]
+fn member_annotation() -> anyhow::Result<()> {
+    use gimli::write::AttributeValue;
+    use dwat::FormatOptions;
+
+    let elf = craft_dwarf(|unit| {
+        let root = unit.root();
+        let int = unit.add(root, gimli::DW_TAG_base_type);
+        let entry = unit.get_mut(int);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"int".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(4));
+        entry.set(gimli::DW_AT_encoding,
+                  AttributeValue::Encoding(gimli::DW_ATE_signed));
+
+        let regs = unit.add(root, gimli::DW_TAG_structure_type);
+        let entry = unit.get_mut(regs);
+        entry.set(gimli::DW_AT_name, AttributeValue::String(b"regs".to_vec()));
+        entry.set(gimli::DW_AT_byte_size, AttributeValue::Udata(12));
+
+        for (idx, name) in ["ctrl", "status", "count"].iter().enumerate() {
+            let member = unit.add(regs, gimli::DW_TAG_member);
+            let entry = unit.get_mut(member);
+            entry.set(gimli::DW_AT_name,
+                      AttributeValue::String(name.as_bytes().to_vec()));
+            entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(int));
+            entry.set(gimli::DW_AT_data_member_location,
+                      AttributeValue::Udata(idx as u64 * 4));
+            if *name == "ctrl" {
+                entry.set(gimli::DW_AT_description, AttributeValue::String(
+                    b"enables the\ndevice".to_vec()
+                ));
+            }
+            // only a vendor attribute
+            if *name == "count" {
+                entry.set(gimli::DW_AT_GNU_guarded_by,
+                          AttributeValue::String(b"regs_lock".to_vec()));
+            }
+        }
+    })?;
+
+    let dwarf = Dwarf::load(&*elf)?;
+    let regs = dwarf.lookup_type::<dwat::Struct>("regs".to_string())?.unwrap();
+    let members = regs.members(&dwarf)?;
+    assert_eq!(members[0].annotation(&dwarf)?.as_deref(),
+               Some("enables the\ndevice"));
+    assert_eq!(members[1].annotation(&dwarf)?, None);
+    assert_eq!(members[2].annotation(&dwarf)?.as_deref(),
+               Some("guarded_by(regs_lock)"));
+
+    // annotations are left out by default
+    assert!(!regs.to_string(&dwarf)?.contains("//"));
+
+    let opts = FormatOptions { emit_annotations: true, ..Default::default() };
+    let repr = regs.to_string_opts(&dwarf, &opts)?;
+    assert!(repr.contains("    int ctrl; // enables the device\n"));
+    assert!(repr.contains("    int status;\n"));
+    assert!(repr.contains("    int count; // guarded_by(regs_lock)\n"));
+
+    let opts = FormatOptions { verbosity: 1, ..opts };
+    let repr = regs.to_string_opts(&dwarf, &opts)?;
+    assert!(repr.contains("|    0 */ // enables the device\n"));
+
+    Ok(())
+}
+
+#[test]
+fn unique_structs_by_render() -> anyhow::Result<()> {
+    use dwat::FormatOptions;
+
+    let (_tmpdir, path) = compile_sources(&[
+        "struct same { int a; char *b; } s0; \
+         struct differs { int x; } d0; int main() {}",
+        "struct same { int a; char *b; } s1; \
+         struct differs { unsigned int x; } d1;"
+    ], &["-gdwarf-5"])?;
+    let file = File::open(path)?;
+    let mmap = unsafe { Mmap::map(&file) }?;
+    let dwarf = Dwarf::load(&*mmap)?;
+
+    let opts = FormatOptions::default();
+    let unique = dwarf.unique_structs_by_render(&opts)?;
+    let names = unique.iter().map(|s| s.name(&dwarf))
+                      .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(names.iter().filter(|n| *n == "same").count(), 1);
+
+    // the member types differ, which the member names and offsets don't show
+    assert_eq!(names.iter().filter(|n| *n == "differs").count(), 2);
+    let keyed = dwarf.get_fg_named_structs_map()?;
+    assert_eq!(keyed.keys().filter(|k| k.name == "differs").count(), 1);
+
     Ok(())
 }